@@ -1,95 +1,758 @@
-use std::{env, net::SocketAddr, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    env,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    ops::Deref,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context as AnyhowContext, Result};
+use async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery, NextRequest,
+    NextResolve, ResolveInfo,
+};
+use async_graphql::futures_util::{Stream, StreamExt, future, stream};
 use async_graphql::http::{GraphQLPlaygroundConfig, playground_source};
+use async_graphql::parser::types::{DocumentOperations, ExecutableDocument};
+use async_graphql::resolver_utils::EnumType;
 use async_graphql::{
-    ComplexObject, Context, EmptySubscription, Enum, Error, InputObject, Object, Schema,
-    SimpleObject,
+    ComplexObject, Context, Data, Enum, Error, ErrorExtensions, InputObject, Json, MaybeUndefined,
+    Object, Response, Schema, ServerError, ServerResult, SimpleObject, Subscription, Upload,
+    UploadValue, Variables,
 };
-use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
 use axum::{
     Router,
-    extract::State,
-    http::{HeaderMap, header},
+    extract::{ConnectInfo, Path, State, WebSocketUpgrade},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
     response::{Html, IntoResponse},
-    routing::get,
+    routing::{get, post},
 };
-use chrono::Utc;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, NaiveDate, Utc};
+use hmac::{Hmac, Mac};
 use libsql::{Builder, Connection, Value, de};
-use serde::Deserialize;
-use tracing::info;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, broadcast};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-type AppSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+type AppSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 type GqlResult<T> = std::result::Result<T, Error>;
 
 #[derive(Clone)]
 struct Config {
+    bind_addr: IpAddr,
     port: u16,
     db_url: String,
     db_token: Option<String>,
+    db_pool_size: usize,
     base_url: String,
     require_auth: bool,
-    api_key: Option<String>,
+    api_keys: HashSet<String>,
+    allow_raw_sql: bool,
+    not_found_as_null: bool,
+    strict_labels: bool,
+    allow_impersonation: bool,
+    state_cache_ttl: Option<Duration>,
+    allow_snapshots: bool,
+    snapshot_dir: String,
+    upload_dir: Option<String>,
+    busy_retries: u32,
+    max_page_size: i32,
+    cursor_secret: Option<String>,
+    max_issues_per_team: Option<i64>,
+    webhook_url: Option<String>,
+    webhook_secret: Option<String>,
+    cors_origins: CorsOrigins,
+    rate_limit: Option<(f64, f64)>,
+    metrics_enabled: bool,
+    playground_enabled: bool,
+    playground_path: String,
     seed_viewer_name: String,
     seed_viewer_email: String,
     seed_team_name: String,
     seed_team_key: String,
+    seed_org_name: String,
+    seed_org_key: String,
 }
 
 impl Config {
-    fn from_env() -> Self {
-        let port = env::var("SUBLINEAR_PORT")
-            .ok()
+    fn from_env() -> Result<Self> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let env_flag = |name: &str, default: bool| env_flag(var(name), default);
+        let bind_addr = match var("SUBLINEAR_BIND_ADDR").filter(|v| !v.is_empty()) {
+            Some(v) => v.trim().parse::<IpAddr>().with_context(|| {
+                format!("SUBLINEAR_BIND_ADDR must be an IP address like 0.0.0.0 (got {v:?})")
+            })?,
+            None => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        };
+        let port = var("SUBLINEAR_PORT")
             .and_then(|v| v.parse::<u16>().ok())
             .unwrap_or(8787);
-        let db_url = env::var("TURSO_DATABASE_URL").unwrap_or_else(|_| "sublinear.db".to_string());
-        let db_token = env::var("TURSO_AUTH_TOKEN").ok().filter(|v| !v.is_empty());
+        let db_url = var("TURSO_DATABASE_URL").unwrap_or_else(|| "sublinear.db".to_string());
+        let db_token = var("TURSO_AUTH_TOKEN").filter(|v| !v.is_empty());
+        let db_pool_size = var("SUBLINEAR_DB_POOL_SIZE")
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(4);
         let base_url =
-            env::var("SUBLINEAR_BASE_URL").unwrap_or_else(|_| format!("http://localhost:{port}"));
-        let require_auth = env::var("SUBLINEAR_REQUIRE_AUTH")
-            .ok()
-            .map(|v| matches!(v.as_str(), "1" | "true" | "TRUE" | "yes" | "YES"))
-            .unwrap_or(true);
-        let api_key = env::var("SUBLINEAR_API_KEY").ok().filter(|v| !v.is_empty());
+            var("SUBLINEAR_BASE_URL").unwrap_or_else(|| format!("http://localhost:{port}"));
+        let require_auth = env_flag("SUBLINEAR_REQUIRE_AUTH", true);
+        let api_keys: HashSet<String> = ["SUBLINEAR_API_KEYS", "SUBLINEAR_API_KEY"]
+            .iter()
+            .filter_map(|name| var(name))
+            .flat_map(|v| {
+                v.split(',')
+                    .map(|k| k.trim().to_string())
+                    .filter(|k| !k.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let allow_raw_sql = env_flag("SUBLINEAR_ALLOW_RAW_SQL", false);
+        let not_found_as_null = env_flag("SUBLINEAR_NOT_FOUND_AS_NULL", false);
+        let strict_labels = env_flag("SUBLINEAR_STRICT_LABELS", false);
+        let allow_impersonation = env_flag("SUBLINEAR_ALLOW_IMPERSONATION", false);
+        let state_cache_ttl_secs = var("SUBLINEAR_STATE_CACHE_TTL_SECS")
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(30);
+        let state_cache_ttl = Some(Duration::from_secs(state_cache_ttl_secs))
+            .filter(|ttl| env_flag("SUBLINEAR_STATE_CACHE", true) && !ttl.is_zero());
+        let allow_snapshots = env_flag("SUBLINEAR_ALLOW_SNAPSHOTS", false);
+        let snapshot_dir = var("SUBLINEAR_SNAPSHOT_DIR")
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "snapshots".to_string());
+        let upload_dir = var("SUBLINEAR_UPLOAD_DIR").filter(|v| !v.is_empty());
+        let busy_retries = var("SUBLINEAR_BUSY_RETRIES")
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(3);
+        let max_page_size = var("SUBLINEAR_MAX_PAGE_SIZE")
+            .and_then(|v| v.parse::<i32>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(500);
+        let cursor_secret = var("SUBLINEAR_CURSOR_SECRET").filter(|v| !v.is_empty());
+        let max_issues_per_team = var("SUBLINEAR_MAX_ISSUES_PER_TEAM")
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 0);
+        let webhook_url = var("SUBLINEAR_WEBHOOK_URL").filter(|v| !v.is_empty());
+        let webhook_secret = var("SUBLINEAR_WEBHOOK_SECRET").filter(|v| !v.is_empty());
+        let cors_origins = match var("SUBLINEAR_CORS_ORIGINS")
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+        {
+            None => CorsOrigins::Localhost,
+            Some(v) if v == "*" => CorsOrigins::Any,
+            Some(v) => CorsOrigins::List(
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|o| !o.is_empty())
+                    .map(|o| {
+                        o.parse::<HeaderValue>()
+                            .with_context(|| format!("invalid SUBLINEAR_CORS_ORIGINS entry: {o:?}"))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+        };
+        let rate_limit = var("SUBLINEAR_RATE_LIMIT")
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v > 0.0)
+            .map(|rate| {
+                let burst = var("SUBLINEAR_RATE_LIMIT_BURST")
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .filter(|v| *v >= 1.0)
+                    .unwrap_or(rate.max(1.0));
+                (rate, burst)
+            });
+        let metrics_enabled = env_flag("SUBLINEAR_METRICS_ENABLED", false);
+        let playground_enabled = !env_flag("SUBLINEAR_DISABLE_PLAYGROUND", false);
+        let playground_path = var("SUBLINEAR_PLAYGROUND_PATH")
+            .map(|v| v.trim().trim_end_matches('/').to_string())
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                if v.starts_with('/') {
+                    v
+                } else {
+                    format!("/{v}")
+                }
+            })
+            .unwrap_or_else(|| "/graphql".to_string());
         let seed_viewer_name =
-            env::var("SUBLINEAR_SEED_VIEWER_NAME").unwrap_or_else(|_| "Sublinear Dev".to_string());
-        let seed_viewer_email = env::var("SUBLINEAR_SEED_VIEWER_EMAIL")
-            .unwrap_or_else(|_| "sublinear@example.com".to_string());
-        let seed_team_name =
-            env::var("SUBLINEAR_SEED_TEAM_NAME").unwrap_or_else(|_| "Synth".to_string());
-        let seed_team_key = env::var("SUBLINEAR_SEED_TEAM_KEY")
-            .ok()
+            var("SUBLINEAR_SEED_VIEWER_NAME").unwrap_or_else(|| "Sublinear Dev".to_string());
+        let seed_viewer_email = var("SUBLINEAR_SEED_VIEWER_EMAIL")
+            .unwrap_or_else(|| "sublinear@example.com".to_string());
+        let seed_team_name = var("SUBLINEAR_SEED_TEAM_NAME").unwrap_or_else(|| "Synth".to_string());
+        let seed_team_key = var("SUBLINEAR_SEED_TEAM_KEY")
             .map(|v| sanitize_team_key(&v))
             .filter(|v| !v.is_empty())
             .unwrap_or_else(|| "SYN".to_string());
+        let seed_org_name =
+            var("SUBLINEAR_SEED_ORG_NAME").unwrap_or_else(|| "Sublinear".to_string());
+        let seed_org_key = var("SUBLINEAR_SEED_ORG_KEY")
+            .map(|v| slugify(&v))
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "sublinear".to_string());
 
-        Self {
+        Ok(Self {
+            bind_addr,
             port,
             db_url,
             db_token,
+            db_pool_size,
             base_url,
             require_auth,
-            api_key,
+            api_keys,
+            allow_raw_sql,
+            not_found_as_null,
+            strict_labels,
+            allow_impersonation,
+            state_cache_ttl,
+            allow_snapshots,
+            snapshot_dir,
+            upload_dir,
+            busy_retries,
+            max_page_size,
+            cursor_secret,
+            max_issues_per_team,
+            webhook_url,
+            webhook_secret,
+            cors_origins,
+            rate_limit,
+            metrics_enabled,
+            playground_enabled,
+            playground_path,
             seed_viewer_name,
             seed_viewer_email,
             seed_team_name,
             seed_team_key,
-        }
+            seed_org_name,
+            seed_org_key,
+        })
     }
 }
 
+#[derive(Clone)]
+enum CorsOrigins {
+    Localhost,
+    Any,
+    List(Vec<HeaderValue>),
+}
+
+fn cors_layer(origins: &CorsOrigins) -> CorsLayer {
+    let allow_origin = match origins {
+        CorsOrigins::Localhost => AllowOrigin::predicate(|origin, _| is_localhost_origin(origin)),
+        CorsOrigins::Any => AllowOrigin::any(),
+        CorsOrigins::List(list) => AllowOrigin::list(list.clone()),
+    };
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+}
+
+// Any port on localhost / 127.0.0.1 / [::1], over http or https.
+fn is_localhost_origin(origin: &HeaderValue) -> bool {
+    let Ok(origin) = origin.to_str() else {
+        return false;
+    };
+    let Some(authority) = origin
+        .strip_prefix("http://")
+        .or_else(|| origin.strip_prefix("https://"))
+    else {
+        return false;
+    };
+    let host = match authority.strip_prefix("[::1]") {
+        Some(rest) => return rest.is_empty() || rest.starts_with(':'),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    matches!(host, "localhost" | "127.0.0.1")
+}
+
+fn env_flag(value: Option<String>, default: bool) -> bool {
+    value
+        .map(|v| matches!(v.as_str(), "1" | "true" | "TRUE" | "yes" | "YES"))
+        .unwrap_or(default)
+}
+
 #[derive(Clone)]
 struct AppContext {
-    conn: Connection,
+    pool: Arc<DbPool>,
     base_url: String,
     require_auth: bool,
+    allow_raw_sql: bool,
+    not_found_as_null: bool,
+    strict_labels: bool,
+    allow_impersonation: bool,
+    state_cache: Arc<StateCache>,
+    allow_snapshots: bool,
+    snapshot_dir: String,
+    upload_dir: Option<String>,
+    remote_db: bool,
+    busy_retries: u32,
+    max_page_size: i32,
+    max_issues_per_team: Option<i64>,
+    issue_fts: bool,
+    webhook: Option<Arc<Webhook>>,
+    issue_events: broadcast::Sender<Issue>,
 }
 
 #[derive(Clone)]
 struct AppState {
     schema: AppSchema,
     config: Arc<Config>,
+    pool: Arc<DbPool>,
+    state_cache: Arc<StateCache>,
+    metrics: Option<Arc<Metrics>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl AppContext {
+    async fn conn(&self) -> GqlResult<PooledConnection> {
+        self.pool.get().await.map_err(gql_error)
+    }
+
+    fn issue_event(&self, action: &str, issue: &Issue) {
+        if self.issue_events.receiver_count() > 0 {
+            let _ = self.issue_events.send(issue.clone());
+        }
+        if let Some(webhook) = &self.webhook {
+            webhook.send(action, "Issue", issue_webhook_data(issue));
+        }
+    }
+
+    // For mutations whose payload carries no issue; skips the lookup when
+    // nobody is listening and never fails the mutation.
+    async fn issue_event_by_id(&self, conn: &Connection, action: &str, id: &str) {
+        if self.webhook.is_none() && self.issue_events.receiver_count() == 0 {
+            return;
+        }
+        match get_issue(conn, id).await {
+            Ok(Some(issue)) => self.issue_event(action, &issue),
+            Ok(None) => {}
+            Err(err) => warn!("failed to load issue {id} for webhook: {err:#}"),
+        }
+    }
+}
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+type WebhookClient =
+    hyper::Client<hyper_rustls::HttpsConnector<hyper::client::HttpConnector>, hyper::Body>;
+
+// Linear-style `{ action, type, data, createdAt }` POSTs, delivered in the
+// background so a slow or failing receiver never holds up a mutation.
+struct Webhook {
+    url: hyper::Uri,
+    secret: Option<String>,
+    client: WebhookClient,
+}
+
+impl Webhook {
+    fn new(url: &str, secret: Option<String>) -> Result<Self> {
+        let url: hyper::Uri = url
+            .parse()
+            .with_context(|| format!("invalid SUBLINEAR_WEBHOOK_URL: {url}"))?;
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        Ok(Self {
+            url,
+            secret,
+            client: hyper::Client::builder().build(connector),
+        })
+    }
+
+    fn send(self: &Arc<Self>, action: &str, entity: &str, data: serde_json::Value) {
+        let body = serde_json::json!({
+            "action": action,
+            "type": entity,
+            "data": data,
+            "createdAt": now_iso(),
+        })
+        .to_string();
+        let webhook = self.clone();
+        let event = format!("{entity} {action}");
+        tokio::spawn(async move {
+            match tokio::time::timeout(WEBHOOK_TIMEOUT, webhook.deliver(body)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => warn!("webhook delivery failed for {event}: {err:#}"),
+                Err(_) => warn!("webhook delivery timed out for {event}"),
+            }
+        });
+    }
+
+    async fn deliver(&self, body: String) -> Result<()> {
+        let mut request = hyper::Request::post(self.url.clone())
+            .header(hyper::header::CONTENT_TYPE, "application/json");
+        if let Some(secret) = &self.secret {
+            request = request.header("X-Sublinear-Signature", webhook_signature(secret, &body));
+        }
+        let response = self
+            .client
+            .request(request.body(hyper::Body::from(body))?)
+            .await?;
+        if !response.status().is_success() {
+            anyhow::bail!("receiver responded with {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+// Hex HMAC-SHA256 of the raw request body.
+fn webhook_signature(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+// Flat ids rather than nested objects, like Linear's webhook `data`.
+fn issue_webhook_data(issue: &Issue) -> serde_json::Value {
+    serde_json::json!({
+        "id": issue.id,
+        "identifier": issue.identifier,
+        "number": issue.number,
+        "parentId": issue.parent_id,
+        "cycleId": issue.cycle.as_ref().map(|c| c.id.clone()),
+        "title": issue.title,
+        "description": issue.description,
+        "url": issue.url,
+        "priority": issue.priority,
+        "estimate": issue.estimate,
+        "dueDate": issue.due_date,
+        "teamId": issue.team.id,
+        "stateId": issue.state.id,
+        "assigneeId": issue.assignee.as_ref().map(|u| u.id.clone()),
+        "creatorId": issue.creator.as_ref().map(|u| u.id.clone()),
+        "projectId": issue.project.as_ref().map(|p| p.id.clone()),
+        "projectMilestoneId": issue.project_milestone.as_ref().map(|m| m.id.clone()),
+        "labelIds": issue.labels.nodes.iter().map(|l| l.id.clone()).collect::<Vec<_>>(),
+        "createdAt": issue.created_at,
+        "updatedAt": issue.updated_at,
+        "completedAt": issue.completed_at,
+        "archivedAt": issue.archived_at,
+    })
+}
+
+// A fixed set of connections, each lent to one caller at a time. Transactions
+// and ATTACH are per connection, so sharing one across requests isn't safe.
+struct DbPool {
+    idle: Mutex<Vec<Connection>>,
+    permits: Arc<Semaphore>,
+    capacity: usize,
+}
+
+struct PooledConnection {
+    conn: Option<Connection>,
+    pool: Arc<DbPool>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl DbPool {
+    async fn get(self: &Arc<Self>) -> Result<PooledConnection> {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .context("db pool closed")?;
+        let conn = self
+            .idle
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("db pool has no idle connection"))?;
+        Ok(PooledConnection {
+            conn: Some(conn),
+            pool: self.clone(),
+            _permit: permit,
+        })
+    }
+
+    // Waits for every lent connection to come back (background tasks such as
+    // subscriptions may still hold one), then closes the pool.
+    async fn drain(&self, timeout: Duration) {
+        let size = self.capacity as u32;
+        match tokio::time::timeout(timeout, self.permits.acquire_many(size)).await {
+            Ok(Ok(_permits)) => {
+                self.permits.close();
+                self.idle.lock().unwrap_or_else(|e| e.into_inner()).clear();
+            }
+            Ok(Err(_)) => {}
+            Err(_) => warn!("timed out waiting for database connections to be returned"),
+        }
+    }
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+            .as_ref()
+            .expect("pooled connection is present until drop")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool
+                .idle
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(conn);
+        }
+    }
+}
+
+// Workflow states keyed by team id. The generation counter keeps a load that
+// raced with an invalidation from repopulating the cache with stale rows.
+struct StateCache {
+    ttl: Option<Duration>,
+    inner: Mutex<StateCacheInner>,
+}
+
+#[derive(Default)]
+struct StateCacheInner {
+    generation: u64,
+    entries: HashMap<String, (Instant, Vec<WorkflowState>)>,
+}
+
+impl StateCache {
+    fn new(ttl: Option<Duration>) -> Self {
+        Self {
+            ttl,
+            inner: Mutex::new(StateCacheInner::default()),
+        }
+    }
+
+    async fn team_states(&self, conn: &Connection, team_id: &str) -> Result<Vec<WorkflowState>> {
+        let Some(ttl) = self.ttl else {
+            return load_team_workflow_states(conn, team_id).await;
+        };
+        let generation = {
+            let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((loaded_at, states)) = inner.entries.get(team_id)
+                && loaded_at.elapsed() < ttl
+            {
+                return Ok(states.clone());
+            }
+            inner.generation
+        };
+        let states = load_team_workflow_states(conn, team_id).await?;
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.generation == generation {
+            inner
+                .entries
+                .insert(team_id.to_string(), (Instant::now(), states.clone()));
+        }
+        Ok(states)
+    }
+
+    fn invalidate(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.generation += 1;
+        inner.entries.clear();
+    }
+}
+
+const RESOLVER_LATENCY_BUCKETS: [f64; 11] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+// Prometheus counters behind `/metrics`, fed by `MetricsExtension`.
+#[derive(Default)]
+struct Metrics {
+    inner: Mutex<MetricsInner>,
+}
+
+#[derive(Default)]
+struct MetricsInner {
+    requests: u64,
+    errors: u64,
+    operations: BTreeMap<String, u64>,
+    // Per-bucket (non-cumulative) counts, plus the overflow past the last bound.
+    resolver_buckets: [u64; RESOLVER_LATENCY_BUCKETS.len() + 1],
+    resolver_sum: f64,
+    resolver_count: u64,
+}
+
+impl Metrics {
+    fn lock(&self) -> std::sync::MutexGuard<'_, MetricsInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn observe_resolver(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = RESOLVER_LATENCY_BUCKETS
+            .iter()
+            .position(|le| seconds <= *le)
+            .unwrap_or(RESOLVER_LATENCY_BUCKETS.len());
+        let mut inner = self.lock();
+        inner.resolver_buckets[bucket] += 1;
+        inner.resolver_sum += seconds;
+        inner.resolver_count += 1;
+    }
+
+    fn render(&self) -> String {
+        use std::fmt::Write;
+
+        let inner = self.lock();
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# HELP sublinear_graphql_requests_total GraphQL requests executed.\n\
+             # TYPE sublinear_graphql_requests_total counter\n\
+             sublinear_graphql_requests_total {}",
+            inner.requests
+        );
+        let _ = writeln!(
+            out,
+            "# HELP sublinear_graphql_operation_requests_total GraphQL requests by operation name.\n\
+             # TYPE sublinear_graphql_operation_requests_total counter"
+        );
+        for (name, count) in &inner.operations {
+            let _ = writeln!(
+                out,
+                "sublinear_graphql_operation_requests_total{{operation=\"{}\"}} {count}",
+                prometheus_label(name)
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP sublinear_graphql_errors_total GraphQL responses that contained errors.\n\
+             # TYPE sublinear_graphql_errors_total counter\n\
+             sublinear_graphql_errors_total {}",
+            inner.errors
+        );
+        let _ = writeln!(
+            out,
+            "# HELP sublinear_graphql_resolver_duration_seconds Latency of top-level query and mutation resolvers.\n\
+             # TYPE sublinear_graphql_resolver_duration_seconds histogram"
+        );
+        let mut cumulative = 0;
+        for (le, count) in RESOLVER_LATENCY_BUCKETS.iter().zip(&inner.resolver_buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "sublinear_graphql_resolver_duration_seconds_bucket{{le=\"{le}\"}} {cumulative}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "sublinear_graphql_resolver_duration_seconds_bucket{{le=\"+Inf\"}} {}\n\
+             sublinear_graphql_resolver_duration_seconds_sum {}\n\
+             sublinear_graphql_resolver_duration_seconds_count {}",
+            inner.resolver_count, inner.resolver_sum, inner.resolver_count
+        );
+        out
+    }
+}
+
+fn prometheus_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+struct MetricsExtension(Arc<Metrics>);
+
+impl ExtensionFactory for MetricsExtension {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(RequestMetrics {
+            metrics: self.0.clone(),
+            parsed_operation: Mutex::new(None),
+        })
+    }
+}
+
+struct RequestMetrics {
+    metrics: Arc<Metrics>,
+    // Name of the document's only named operation, used when the request
+    // doesn't pass `operationName`.
+    parsed_operation: Mutex<Option<String>>,
+}
+
+#[async_graphql::async_trait::async_trait]
+impl Extension for RequestMetrics {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let response = next.run(ctx).await;
+        let mut inner = self.metrics.lock();
+        inner.requests += 1;
+        if response.is_err() {
+            inner.errors += 1;
+        }
+        response
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+        if let DocumentOperations::Multiple(operations) = &document.operations
+            && operations.len() == 1
+        {
+            *self
+                .parsed_operation
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()) =
+                operations.keys().next().map(|n| n.to_string());
+        }
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let name = operation_name.map(str::to_string).or_else(|| {
+            self.parsed_operation
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone()
+        });
+        *self
+            .metrics
+            .lock()
+            .operations
+            .entry(name.unwrap_or_else(|| "anonymous".to_string()))
+            .or_default() += 1;
+        next.run(ctx, operation_name).await
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<async_graphql::Value>> {
+        if info.path_node.parent.is_some() || info.is_for_introspection {
+            return next.run(ctx, info).await;
+        }
+        let started = Instant::now();
+        let result = next.run(ctx, info).await;
+        self.metrics.observe_resolver(started.elapsed());
+        result
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -105,37 +768,164 @@ pub async fn run_from_env() -> Result<()> {
         )
         .init();
 
-    let config = Arc::new(Config::from_env());
-    let conn = open_connection(&config).await?;
-    migrate(&conn).await?;
-    seed_defaults(&conn, &config).await?;
+    let config = Arc::new(Config::from_env()?);
+    let (app, state) = build_app(config.clone()).await?;
+
+    let addr = SocketAddr::new(config.bind_addr, config.port);
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to bind {addr}"))?;
+    let addr = listener.local_addr()?;
+    info!(
+        "sublinear listening on http://{} (NOT FOR PRODUCTION USE)",
+        addr
+    );
+    if config.playground_enabled {
+        info!(
+            "GraphQL playground at http://{}{}",
+            addr, config.playground_path
+        );
+    }
+    serve(app, state, listener, shutdown_signal()).await?;
+    info!("sublinear stopped");
+
+    Ok(())
+}
+
+// Serves until `shutdown` resolves, then waits for in-flight requests and
+// drains the pool.
+async fn serve(
+    app: Router,
+    state: AppState,
+    listener: tokio::net::TcpListener,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown)
+    .await?;
+    state.pool.drain(SHUTDOWN_DRAIN_TIMEOUT).await;
+    Ok(())
+}
+
+// Opens and migrates the database and wires up the schema and routes.
+async fn build_app(config: Arc<Config>) -> Result<(Router, AppState)> {
+    if let Some(secret) = &config.cursor_secret {
+        let _ = CURSOR_KEY.set(secret.as_bytes().to_vec());
+    }
+    let pool = open_pool(&config).await?;
+    let issue_fts = {
+        let conn = pool.get().await?;
+        migrate(&conn).await?;
+        seed_defaults(&conn, &config).await?;
+        ensure_issue_search_index(&conn).await?
+    };
 
-    let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
-        .data(Arc::new(AppContext {
-            conn: conn.clone(),
+    let webhook = config
+        .webhook_url
+        .as_deref()
+        .map(|url| Webhook::new(url, config.webhook_secret.clone()).map(Arc::new))
+        .transpose()?;
+    let state_cache = Arc::new(StateCache::new(config.state_cache_ttl));
+    let (issue_events, _) = broadcast::channel(ISSUE_EVENT_CAPACITY);
+    let metrics = config.metrics_enabled.then(|| Arc::new(Metrics::default()));
+    let mut schema =
+        Schema::build(QueryRoot, MutationRoot, SubscriptionRoot).data(Arc::new(AppContext {
+            pool: pool.clone(),
             base_url: config.base_url.clone(),
             require_auth: config.require_auth,
-        }))
-        .finish();
+            allow_raw_sql: config.allow_raw_sql,
+            not_found_as_null: config.not_found_as_null,
+            strict_labels: config.strict_labels,
+            allow_impersonation: config.allow_impersonation,
+            state_cache: state_cache.clone(),
+            allow_snapshots: config.allow_snapshots,
+            snapshot_dir: config.snapshot_dir.clone(),
+            upload_dir: config.upload_dir.clone(),
+            remote_db: looks_remote_url(&config.db_url),
+            busy_retries: config.busy_retries,
+            max_page_size: config.max_page_size,
+            max_issues_per_team: config.max_issues_per_team,
+            issue_fts,
+            webhook,
+            issue_events,
+        }));
+    if let Some(metrics) = &metrics {
+        schema = schema.extension(MetricsExtension(metrics.clone()));
+    }
+    let schema = schema.finish();
 
-    let app = Router::new()
+    let serve_playground_at_graphql =
+        config.playground_enabled && config.playground_path == "/graphql";
+    let graphql_get = if serve_playground_at_graphql {
+        get(graphql_playground)
+    } else {
+        get(not_found)
+    };
+    let mut app = Router::new()
         .route("/", get(root))
         .route("/healthz", get(healthz))
-        .route("/graphql", get(graphql_playground).post(graphql_handler))
-        .with_state(AppState {
-            schema,
-            config: config.clone(),
-        });
+        .route("/graphql", graphql_get.post(graphql_handler))
+        .route("/graphql/ws", get(graphql_ws_handler))
+        .route("/graphql/schema.graphql", get(schema_sdl_handler));
+    if config.playground_enabled && !serve_playground_at_graphql {
+        app = app.route(&config.playground_path, get(graphql_playground));
+    }
+    if metrics.is_some() {
+        app = app.route("/metrics", get(metrics_handler));
+    }
+    let state = AppState {
+        schema,
+        config: config.clone(),
+        pool,
+        state_cache,
+        metrics,
+        rate_limiter: config
+            .rate_limit
+            .map(|(rate, burst)| Arc::new(RateLimiter::new(rate, burst))),
+    };
+    let app = app
+        .route("/export", get(export_handler))
+        .route("/import", post(import_handler))
+        .route("/files/{id}", get(file_handler))
+        .with_state(state.clone())
+        .layer(cors_layer(&config.cors_origins));
+    Ok((app, state))
+}
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], config.port));
-    info!(
-        "sublinear listening on http://{} (NOT FOR PRODUCTION USE)",
-        addr
-    );
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
 
-    Ok(())
+// Resolves on Ctrl-C or, on Unix, SIGTERM; axum then stops accepting and
+// lets in-flight requests finish.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            warn!("failed to listen for ctrl-c: {err}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                warn!("failed to listen for SIGTERM: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    info!("shutting down");
 }
 
 async fn root() -> impl IntoResponse {
@@ -146,44 +936,292 @@ async fn healthz() -> impl IntoResponse {
     "ok"
 }
 
+async fn not_found() -> impl IntoResponse {
+    StatusCode::NOT_FOUND
+}
+
 async fn graphql_playground() -> impl IntoResponse {
-    Html(playground_source(GraphQLPlaygroundConfig::new("/graphql")))
+    Html(playground_source(
+        GraphQLPlaygroundConfig::new("/graphql").subscription_endpoint("/graphql/ws"),
+    ))
+}
+
+// Read-only counters; like /healthz it needs no API key.
+async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let body = state
+        .metrics
+        .as_ref()
+        .map(|m| m.render())
+        .unwrap_or_default();
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        body,
+    )
+}
+
+// Public schema text for codegen, so it needs no API key.
+async fn schema_sdl_handler(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        state.schema.sdl(),
+    )
+}
+
+// Browsers can't set headers on a WebSocket, so the API key may also arrive
+// as `Authorization` in the `connection_init` payload.
+async fn graphql_ws_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    protocol: GraphQLProtocol,
+    upgrade: WebSocketUpgrade,
+) -> impl IntoResponse {
+    let header_authorized = is_authorized(&headers, &state.config);
+    upgrade
+        .protocols(async_graphql::http::ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |socket| {
+            let config = state.config.clone();
+            GraphQLWebSocket::new(socket, state.schema, protocol)
+                .on_connection_init(move |payload| async move {
+                    let token = ["Authorization", "authorization"]
+                        .iter()
+                        .find_map(|key| payload.get(key).and_then(|v| v.as_str()));
+                    let authorized = header_authorized || authorization_matches(token, &config);
+                    let mut data = Data::default();
+                    data.insert(RequestAuth { authorized });
+                    Ok(data)
+                })
+                .serve()
+        })
 }
 
 async fn graphql_handler(
     State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     req: GraphQLRequest,
-) -> GraphQLResponse {
+) -> axum::response::Response {
+    if let Some(limiter) = &state.rate_limiter {
+        let key = rate_limit_key(&headers, &state.config, peer);
+        if let Err(retry_after) = limiter.check(&key) {
+            let mut error = ServerError::new("Rate limit exceeded", None);
+            error.extensions = typed_error(ErrorType::RateLimited, "").extensions;
+            let mut response = async_graphql::Response::from_errors(vec![error]);
+            let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            if let Ok(value) = HeaderValue::from_str(&seconds.to_string()) {
+                response.http_headers.insert(header::RETRY_AFTER, value);
+            }
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                GraphQLResponse::from(response),
+            )
+                .into_response();
+        }
+    }
     let authorized = is_authorized(&headers, &state.config);
-    state
-        .schema
-        .execute(req.into_inner().data(RequestAuth { authorized }))
+    GraphQLResponse::from(
+        state
+            .schema
+            .execute(req.into_inner().data(RequestAuth { authorized }))
+            .await,
+    )
+    .into_response()
+}
+
+// Buckets are per configured API key when auth is on (by label, so the map
+// never holds raw keys) and per client IP otherwise, so unknown keys can't
+// mint fresh buckets.
+fn rate_limit_key(headers: &HeaderMap, cfg: &Config, peer: SocketAddr) -> String {
+    let key = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| presented_key(v.trim()))
+        .filter(|k| cfg.require_auth && cfg.api_keys.contains(*k));
+    match key {
+        Some(key) => format!("key:{}", api_key_label(key)),
+        None => format!("ip:{}", peer.ip()),
+    }
+}
+
+// Token bucket per client: `rate` tokens refill each second up to `burst`.
+struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl RateLimiter {
+    fn new(rate: f64, burst: f64) -> Self {
+        Self {
+            rate,
+            burst,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // `Err` carries how long until the next token is available.
+    fn check(&self, key: &str) -> std::result::Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() > 4096 {
+            // Drop buckets that have refilled completely; they carry no state.
+            let (rate, burst) = (self.rate, self.burst);
+            buckets.retain(|_, (tokens, at)| {
+                *tokens + now.duration_since(*at).as_secs_f64() * rate < burst
+            });
+        }
+        let (tokens, at) = buckets.entry(key.to_string()).or_insert((self.burst, now));
+        *tokens = (*tokens + now.duration_since(*at).as_secs_f64() * self.rate).min(self.burst);
+        *at = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - *tokens) / self.rate))
+        }
+    }
+}
+
+async fn export_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> std::result::Result<axum::Json<serde_json::Value>, (StatusCode, String)> {
+    if !is_authorized(&headers, &state.config) {
+        return Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()));
+    }
+    let result = match state.pool.get().await {
+        Ok(conn) => export_bundle(&conn).await,
+        Err(err) => Err(err),
+    };
+    result
+        .map(axum::Json)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{err:#}")))
+}
+
+async fn file_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> std::result::Result<impl IntoResponse, (StatusCode, String)> {
+    if !is_authorized(&headers, &state.config) {
+        return Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()));
+    }
+    let not_found = || (StatusCode::NOT_FOUND, "Not Found".to_string());
+    let Some(dir) = &state.config.upload_dir else {
+        return Err(not_found());
+    };
+    if !is_attachment_id(&id) {
+        return Err(not_found());
+    }
+    let conn = state
+        .pool
+        .get()
         .await
-        .into()
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{err:#}")))?;
+    let row: Option<AttachmentFileRow> = fetch_one(
+        &conn,
+        "SELECT content_type FROM attachments WHERE id = ?1 AND content_type IS NOT NULL",
+        vec![id.clone().into()],
+    )
+    .await
+    .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, format!("{err:#}")))?;
+    // Only ids of uploaded attachments reach the filesystem.
+    let row = row.ok_or_else(not_found)?;
+    let body = tokio::fs::read(std::path::Path::new(dir).join(&id))
+        .await
+        .map_err(|_| not_found())?;
+    // Uploads are untrusted, so browsers must download rather than render them.
+    Ok((
+        [
+            (header::CONTENT_TYPE, row.content_type),
+            (header::CONTENT_DISPOSITION, "attachment".to_string()),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        body,
+    ))
+}
+
+// The `attachment_<short_id>` form that uploads are stored under.
+fn is_attachment_id(id: &str) -> bool {
+    id.strip_prefix("attachment_").is_some_and(|suffix| {
+        suffix.len() == 12
+            && suffix
+                .bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    })
+}
+
+async fn import_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    axum::Json(bundle): axum::Json<serde_json::Value>,
+) -> std::result::Result<axum::Json<serde_json::Value>, (StatusCode, String)> {
+    if !is_authorized(&headers, &state.config) {
+        return Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()));
+    }
+    let result = match state.pool.get().await {
+        Ok(conn) => import_bundle(&conn, &bundle).await,
+        Err(err) => Err(err),
+    };
+    state.state_cache.invalidate();
+    result.map(axum::Json).map_err(|err| {
+        let input_error = err.downcast_ref::<ValidationError>().is_some()
+            || err.downcast_ref::<NotFoundError>().is_some();
+        let status = if input_error {
+            StatusCode::BAD_REQUEST
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        (status, format!("{err:#}"))
+    })
 }
 
 fn is_authorized(headers: &HeaderMap, cfg: &Config) -> bool {
+    let value = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|raw| raw.to_str().ok());
+    authorization_matches(value, cfg)
+}
+
+fn authorization_matches(value: Option<&str>, cfg: &Config) -> bool {
     if !cfg.require_auth {
         return true;
     }
-    let Some(raw) = headers.get(header::AUTHORIZATION) else {
-        return false;
-    };
-    let Ok(value) = raw.to_str() else {
+    let Some(value) = value else {
         return false;
     };
     let trimmed = value.trim();
     if trimmed.is_empty() {
         return false;
     }
-    if let Some(expected) = cfg.api_key.as_deref() {
-        trimmed == expected || trimmed == format!("Bearer {expected}")
-    } else {
+    if cfg.api_keys.is_empty() {
+        return true;
+    }
+    let key = presented_key(trimmed);
+    if cfg.api_keys.contains(key) {
+        debug!("authenticated with API key {}", api_key_label(key));
         true
+    } else {
+        false
     }
 }
 
+// The key itself, for both `<key>` and `Bearer <key>` header values.
+fn presented_key(value: &str) -> &str {
+    value.strip_prefix("Bearer ").unwrap_or(value).trim()
+}
+
+// Short SHA-256 prefix that tells keys apart in logs without revealing them.
+fn api_key_label(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .take(4)
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
 fn ensure_auth(ctx: &Context<'_>) -> GqlResult<()> {
     let app = ctx.data_unchecked::<Arc<AppContext>>();
     if !app.require_auth {
@@ -196,7 +1234,7 @@ fn ensure_auth(ctx: &Context<'_>) -> GqlResult<()> {
     if authorized {
         Ok(())
     } else {
-        Err(Error::new("Unauthorized"))
+        Err(typed_error(ErrorType::Authentication, "Unauthorized"))
     }
 }
 
@@ -204,15 +1242,106 @@ fn app_ctx(ctx: &Context<'_>) -> Arc<AppContext> {
     ctx.data_unchecked::<Arc<AppContext>>().clone()
 }
 
-fn gql_error<E: std::fmt::Display>(err: E) -> Error {
-    Error::new(err.to_string())
+fn require_found<T>(app: &AppContext, value: Option<T>, entity: &str) -> GqlResult<Option<T>> {
+    if value.is_none() && !app.not_found_as_null {
+        return Err(typed_error(
+            ErrorType::EntityNotFound,
+            format!("Entity not found: {entity}"),
+        ));
+    }
+    Ok(value)
 }
 
-async fn open_connection(cfg: &Config) -> Result<Connection> {
-    let db = if looks_remote_url(&cfg.db_url) {
-        let token = cfg.db_token.clone().unwrap_or_default();
-        Builder::new_remote(cfg.db_url.clone(), token)
-            .build()
+// Linear-compatible `extensions.type` values that clients branch on.
+#[derive(Clone, Copy)]
+enum ErrorType {
+    Authentication,
+    EntityNotFound,
+    InvalidInput,
+    FeatureNotAccessible,
+    RateLimited,
+}
+
+impl ErrorType {
+    fn as_str(self) -> &'static str {
+        match self {
+            ErrorType::Authentication => "AUTHENTICATION_ERROR",
+            ErrorType::EntityNotFound => "ENTITY_NOT_FOUND",
+            ErrorType::InvalidInput => "INVALID_INPUT",
+            ErrorType::FeatureNotAccessible => "FEATURE_NOT_ACCESSIBLE",
+            ErrorType::RateLimited => "RATELIMITED",
+        }
+    }
+}
+
+fn typed_error(kind: ErrorType, message: impl Into<String>) -> Error {
+    Error::new(message).extend_with(|_, ext| {
+        ext.set("type", kind.as_str());
+        // Older clients match on the original validation code.
+        if let ErrorType::InvalidInput = kind {
+            ext.set("code", "VALIDATION");
+        }
+    })
+}
+
+// Surfaced to clients with `extensions.type = "INVALID_INPUT"` by `gql_error`.
+#[derive(Debug)]
+struct ValidationError(String);
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+// Surfaced to clients with `extensions.type = "ENTITY_NOT_FOUND"` by `gql_error`.
+#[derive(Debug)]
+struct NotFoundError(String);
+
+impl std::fmt::Display for NotFoundError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for NotFoundError {}
+
+// Surfaced to clients with `extensions.type = "FEATURE_NOT_ACCESSIBLE"` by `gql_error`.
+#[derive(Debug)]
+struct FeatureNotAccessibleError(String);
+
+impl std::fmt::Display for FeatureNotAccessibleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for FeatureNotAccessibleError {}
+
+fn gql_error(err: anyhow::Error) -> Error {
+    if err.downcast_ref::<ValidationError>().is_some() {
+        typed_error(ErrorType::InvalidInput, err.to_string())
+    } else if err.downcast_ref::<NotFoundError>().is_some() {
+        typed_error(ErrorType::EntityNotFound, err.to_string())
+    } else if err.downcast_ref::<FeatureNotAccessibleError>().is_some() {
+        typed_error(ErrorType::FeatureNotAccessible, err.to_string())
+    } else {
+        Error::new(err.to_string())
+    }
+}
+
+async fn open_pool(cfg: &Config) -> Result<Arc<DbPool>> {
+    let db = if looks_remote_url(&cfg.db_url) {
+        let token = cfg.db_token.clone().ok_or_else(|| {
+            anyhow::anyhow!(
+                "remote Turso URL requires TURSO_AUTH_TOKEN (got TURSO_DATABASE_URL={})",
+                cfg.db_url
+            )
+        })?;
+        Builder::new_remote(cfg.db_url.clone(), token)
+            .build()
             .await
             .with_context(|| format!("failed to connect remote turso {}", cfg.db_url))?
     } else {
@@ -222,38 +1351,252 @@ async fn open_connection(cfg: &Config) -> Result<Connection> {
             .await
             .with_context(|| format!("failed to open local db {local_path}"))?
     };
-    db.connect().context("failed to create db connection")
+    let mut conns = Vec::with_capacity(cfg.db_pool_size);
+    for _ in 0..cfg.db_pool_size {
+        let conn = db.connect().context("failed to create db connection")?;
+        if !looks_remote_url(&cfg.db_url) {
+            conn.busy_timeout(Duration::from_secs(5))
+                .context("failed to set busy timeout")?;
+        }
+        conn.execute("PRAGMA foreign_keys = ON", ())
+            .await
+            .context("failed to enable foreign keys")?;
+        conns.push(conn);
+    }
+    Ok(Arc::new(DbPool {
+        idle: Mutex::new(conns),
+        permits: Arc::new(Semaphore::new(cfg.db_pool_size)),
+        capacity: cfg.db_pool_size,
+    }))
 }
 
 fn looks_remote_url(url: &str) -> bool {
     url.starts_with("libsql://") || url.starts_with("https://") || url.starts_with("http://")
 }
 
+struct Migration {
+    version: i64,
+    up: &'static str,
+}
+
+// Applied in order, each in its own transaction and recorded in
+// `schema_migrations`; append new versions, never edit applied ones.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        up: "CREATE TABLE IF NOT EXISTS organization (id TEXT PRIMARY KEY, name TEXT NOT NULL, url_key TEXT NOT NULL, created_at TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS users (id TEXT PRIMARY KEY, name TEXT NOT NULL, email TEXT NOT NULL, created_at TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS teams (id TEXT PRIMARY KEY, name TEXT NOT NULL, key TEXT NOT NULL UNIQUE, created_at TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS team_members (team_id TEXT NOT NULL, user_id TEXT NOT NULL, PRIMARY KEY(team_id, user_id));
+             CREATE TABLE IF NOT EXISTS workflow_states (id TEXT PRIMARY KEY, team_id TEXT NOT NULL, name TEXT NOT NULL, type TEXT NOT NULL, position INTEGER NOT NULL);
+             CREATE TABLE IF NOT EXISTS projects (id TEXT PRIMARY KEY, name TEXT NOT NULL, slug_id TEXT NOT NULL UNIQUE, state TEXT, archived_at TEXT, url TEXT NOT NULL, created_at TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS project_teams (project_id TEXT NOT NULL, team_id TEXT NOT NULL, PRIMARY KEY(project_id, team_id));
+             CREATE TABLE IF NOT EXISTS issues (id TEXT PRIMARY KEY, team_id TEXT NOT NULL, project_id TEXT, number INTEGER NOT NULL, identifier TEXT NOT NULL UNIQUE, title TEXT NOT NULL, description TEXT, state_id TEXT NOT NULL, assignee_id TEXT, archived INTEGER NOT NULL DEFAULT 0, url TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS labels (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS issue_labels (issue_id TEXT NOT NULL, label_id TEXT NOT NULL, PRIMARY KEY(issue_id, label_id));
+             CREATE TABLE IF NOT EXISTS comments (id TEXT PRIMARY KEY, issue_id TEXT NOT NULL, body TEXT NOT NULL, url TEXT NOT NULL, created_at TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS cycles (id TEXT PRIMARY KEY, team_id TEXT NOT NULL, number INTEGER NOT NULL, name TEXT, starts_at TEXT NOT NULL, ends_at TEXT NOT NULL, created_at TEXT NOT NULL, UNIQUE(team_id, number));
+             CREATE TABLE IF NOT EXISTS project_milestones (id TEXT PRIMARY KEY, project_id TEXT NOT NULL, name TEXT NOT NULL, sort_order REAL NOT NULL DEFAULT 0);
+             CREATE TABLE IF NOT EXISTS reactions (id TEXT PRIMARY KEY, subject_type TEXT NOT NULL, subject_id TEXT NOT NULL, emoji TEXT NOT NULL, user_id TEXT NOT NULL, created_at TEXT NOT NULL, UNIQUE(subject_type, subject_id, emoji, user_id));
+             CREATE TABLE IF NOT EXISTS favorites (id TEXT PRIMARY KEY, user_id TEXT NOT NULL, subject_type TEXT NOT NULL, subject_id TEXT NOT NULL, created_at TEXT NOT NULL, UNIQUE(user_id, subject_type, subject_id));
+             CREATE TABLE IF NOT EXISTS attachments (id TEXT PRIMARY KEY, issue_id TEXT NOT NULL, title TEXT NOT NULL, subtitle TEXT, url TEXT NOT NULL, created_at TEXT NOT NULL, content_type TEXT)",
+    },
+    Migration {
+        version: 2,
+        up: "CREATE INDEX IF NOT EXISTS issues_team_id ON issues (team_id);
+             CREATE INDEX IF NOT EXISTS issues_project_id ON issues (project_id);
+             CREATE INDEX IF NOT EXISTS issues_parent_id ON issues (parent_id);
+             CREATE INDEX IF NOT EXISTS issues_cycle_id ON issues (cycle_id);
+             CREATE INDEX IF NOT EXISTS comments_issue_id ON comments (issue_id);
+             CREATE INDEX IF NOT EXISTS attachments_issue_id ON attachments (issue_id)",
+    },
+    Migration {
+        version: 3,
+        // Serves the per-row board rank in `issue_base_select`.
+        up: "CREATE INDEX IF NOT EXISTS issues_state_rank ON issues (state_id, archived, sort_order, id)",
+    },
+    Migration {
+        version: 4,
+        up: "ALTER TABLE attachments ADD COLUMN metadata TEXT;
+             ALTER TABLE attachments ADD COLUMN source_type TEXT",
+    },
+];
+
+// The baseline is `CREATE TABLE IF NOT EXISTS`, so dev DBs from before
+// versioning adopt it as-is and get their missing columns backfilled below.
+const BASELINE_VERSION: i64 = 1;
+
+// Columns added before versioned migrations existed. Fresh DBs get them here
+// too, before any later version runs; new columns belong in `MIGRATIONS`.
+const LEGACY_COLUMNS: [(&str, &str, &str); 18] = [
+    ("teams", "starting_number", "INTEGER NOT NULL DEFAULT 1"),
+    ("workflow_states", "color", "TEXT"),
+    ("projects", "sort_order", "REAL NOT NULL DEFAULT 0"),
+    ("issues", "sort_order", "REAL NOT NULL DEFAULT 0"),
+    ("issues", "creator_id", "TEXT"),
+    ("comments", "user_id", "TEXT"),
+    ("labels", "color", "TEXT"),
+    ("labels", "parent_id", "TEXT"),
+    ("labels", "team_id", "TEXT"),
+    ("issues", "priority", "INTEGER NOT NULL DEFAULT 0"),
+    ("issues", "estimate", "REAL"),
+    ("issues", "due_date", "TEXT"),
+    ("issues", "completed_at", "TEXT"),
+    ("issues", "archived_at", "TEXT"),
+    ("projects", "description", "TEXT"),
+    ("issues", "parent_id", "TEXT"),
+    ("issues", "cycle_id", "TEXT"),
+    ("issues", "project_milestone_id", "TEXT"),
+];
+
+#[derive(Deserialize)]
+struct MigrationVersionRow {
+    version: i64,
+}
+
 async fn migrate(conn: &Connection) -> Result<()> {
-    let stmts = [
-        "PRAGMA foreign_keys = ON",
-        "CREATE TABLE IF NOT EXISTS users (id TEXT PRIMARY KEY, name TEXT NOT NULL, email TEXT NOT NULL, created_at TEXT NOT NULL)",
-        "CREATE TABLE IF NOT EXISTS teams (id TEXT PRIMARY KEY, name TEXT NOT NULL, key TEXT NOT NULL UNIQUE, created_at TEXT NOT NULL)",
-        "CREATE TABLE IF NOT EXISTS team_members (team_id TEXT NOT NULL, user_id TEXT NOT NULL, PRIMARY KEY(team_id, user_id))",
-        "CREATE TABLE IF NOT EXISTS workflow_states (id TEXT PRIMARY KEY, team_id TEXT NOT NULL, name TEXT NOT NULL, type TEXT NOT NULL, position INTEGER NOT NULL)",
-        "CREATE TABLE IF NOT EXISTS projects (id TEXT PRIMARY KEY, name TEXT NOT NULL, slug_id TEXT NOT NULL UNIQUE, state TEXT, archived_at TEXT, url TEXT NOT NULL, created_at TEXT NOT NULL)",
-        "CREATE TABLE IF NOT EXISTS project_teams (project_id TEXT NOT NULL, team_id TEXT NOT NULL, PRIMARY KEY(project_id, team_id))",
-        "CREATE TABLE IF NOT EXISTS issues (id TEXT PRIMARY KEY, team_id TEXT NOT NULL, project_id TEXT, number INTEGER NOT NULL, identifier TEXT NOT NULL UNIQUE, title TEXT NOT NULL, description TEXT, state_id TEXT NOT NULL, assignee_id TEXT, archived INTEGER NOT NULL DEFAULT 0, url TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL)",
-        "CREATE TABLE IF NOT EXISTS labels (id TEXT PRIMARY KEY, name TEXT NOT NULL)",
-        "CREATE TABLE IF NOT EXISTS issue_labels (issue_id TEXT NOT NULL, label_id TEXT NOT NULL, PRIMARY KEY(issue_id, label_id))",
-        "CREATE TABLE IF NOT EXISTS comments (id TEXT PRIMARY KEY, issue_id TEXT NOT NULL, body TEXT NOT NULL, url TEXT NOT NULL, created_at TEXT NOT NULL)",
-    ];
-    for stmt in stmts {
-        conn.execute(stmt, ()).await?;
+    run_migrations(conn, MIGRATIONS).await
+}
+
+async fn run_migrations(conn: &Connection, migrations: &[Migration]) -> Result<()> {
+    conn.execute("PRAGMA foreign_keys = ON", ()).await?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (version INTEGER PRIMARY KEY, applied_at TEXT NOT NULL)",
+        (),
+    )
+    .await?;
+    let applied: HashSet<i64> =
+        fetch_all::<MigrationVersionRow>(conn, "SELECT version FROM schema_migrations", vec![])
+            .await?
+            .into_iter()
+            .map(|r| r.version)
+            .collect();
+
+    for migration in migrations {
+        if !applied.contains(&migration.version) {
+            apply_migration(conn, migration).await?;
+        }
+        if migration.version == BASELINE_VERSION {
+            for (table, column, decl) in LEGACY_COLUMNS {
+                ensure_column(conn, table, column, decl).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn apply_migration(conn: &Connection, migration: &Migration) -> Result<()> {
+    let tx = conn.transaction().await?;
+    tx.execute_batch(migration.up)
+        .await
+        .with_context(|| format!("migration {} failed", migration.version))?;
+    tx.execute(
+        "INSERT INTO schema_migrations (version, applied_at) VALUES (?1, ?2)",
+        vals(vec![migration.version.into(), now_iso().into()]),
+    )
+    .await?;
+    tx.commit().await?;
+    info!("applied schema migration {}", migration.version);
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct ColumnInfoRow {
+    name: String,
+    pk: i64,
+}
+
+async fn ensure_column(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let existing: Vec<ColumnInfoRow> =
+        fetch_all(conn, &format!("PRAGMA table_info({table})"), vec![]).await?;
+    if existing.iter().all(|c| c.name != column) {
+        conn.execute(
+            &format!("ALTER TABLE {table} ADD COLUMN {column} {decl}"),
+            (),
+        )
+        .await
+        .with_context(|| format!("failed to add column {table}.{column}"))?;
     }
     Ok(())
 }
 
+// External-content FTS5 index over `issues`, keyed by rowid and kept in step
+// by triggers. Bundle imports upsert and snapshot restores delete and re-insert,
+// so both fire them; writes made outside sublinear need `adminReindex`.
+const ISSUE_FTS_TRIGGERS: [&str; 3] = [
+    "CREATE TRIGGER IF NOT EXISTS issues_fts_insert AFTER INSERT ON issues BEGIN
+       INSERT INTO issues_fts (rowid, title, description, identifier)
+       VALUES (new.rowid, new.title, new.description, new.identifier);
+     END",
+    "CREATE TRIGGER IF NOT EXISTS issues_fts_delete AFTER DELETE ON issues BEGIN
+       INSERT INTO issues_fts (issues_fts, rowid, title, description, identifier)
+       VALUES ('delete', old.rowid, old.title, old.description, old.identifier);
+     END",
+    "CREATE TRIGGER IF NOT EXISTS issues_fts_update AFTER UPDATE OF title, description, identifier ON issues BEGIN
+       INSERT INTO issues_fts (issues_fts, rowid, title, description, identifier)
+       VALUES ('delete', old.rowid, old.title, old.description, old.identifier);
+       INSERT INTO issues_fts (rowid, title, description, identifier)
+       VALUES (new.rowid, new.title, new.description, new.identifier);
+     END",
+];
+
+// FTS5 is optional in SQLite builds; without it `searchIssues` falls back to LIKE.
+async fn ensure_issue_search_index(conn: &Connection) -> Result<bool> {
+    let existed = count(
+        conn,
+        "SELECT COUNT(*) as value FROM sqlite_master WHERE type = 'table' AND name = 'issues_fts'",
+        vec![],
+    )
+    .await?
+        > 0;
+    if let Err(err) = conn
+        .execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS issues_fts USING fts5(title, description, identifier, content = 'issues', content_rowid = 'rowid')",
+            (),
+        )
+        .await
+    {
+        warn!("FTS5 unavailable, searchIssues will use LIKE: {err}");
+        return Ok(false);
+    }
+    for stmt in ISSUE_FTS_TRIGGERS {
+        conn.execute(stmt, ()).await?;
+    }
+    if !existed {
+        rebuild_issue_search_index(conn).await?;
+    }
+    Ok(true)
+}
+
+async fn rebuild_issue_search_index(conn: &Connection) -> Result<AdminReindexPayload> {
+    conn.execute("INSERT INTO issues_fts (issues_fts) VALUES ('rebuild')", ())
+        .await
+        .context("failed to rebuild issues_fts")?;
+    let indexed = count(conn, "SELECT COUNT(*) as value FROM issues", vec![]).await?;
+    Ok(AdminReindexPayload {
+        success: true,
+        indexed_count: indexed as i32,
+    })
+}
+
 async fn seed_defaults(conn: &Connection, cfg: &Config) -> Result<()> {
     let now = now_iso();
     let viewer_id = "viewer_default";
     let team_id = "team_default";
 
+    if count(conn, "SELECT COUNT(*) as value FROM organization", vec![]).await? == 0 {
+        conn.execute(
+            "INSERT INTO organization (id, name, url_key, created_at) VALUES (?1, ?2, ?3, ?4)",
+            vals(vec![
+                "org_default".into(),
+                cfg.seed_org_name.clone().into(),
+                cfg.seed_org_key.clone().into(),
+                now.clone().into(),
+            ]),
+        )
+        .await?;
+    }
+
     if count(conn, "SELECT COUNT(*) as value FROM users", vec![]).await? == 0 {
         conn.execute(
             "INSERT INTO users (id, name, email, created_at) VALUES (?1, ?2, ?3, ?4)",
@@ -286,21 +1629,28 @@ async fn seed_defaults(conn: &Connection, cfg: &Config) -> Result<()> {
     )
     .await?;
 
-    ensure_workflow_state(conn, team_id, "Backlog", "unstarted", 0).await?;
-    ensure_workflow_state(conn, team_id, "In Progress", "started", 1).await?;
-    ensure_workflow_state(conn, team_id, "In Review", "started", 2).await?;
-    ensure_workflow_state(conn, team_id, "Done", "completed", 3).await?;
-    ensure_workflow_state(conn, team_id, "Canceled", "canceled", 4).await?;
+    for (name, kind, position, color) in DEFAULT_WORKFLOW_STATES {
+        ensure_workflow_state(conn, team_id, name, kind, position, color).await?;
+    }
 
     Ok(())
 }
 
+const DEFAULT_WORKFLOW_STATES: [(&str, &str, i64, &str); 5] = [
+    ("Backlog", "unstarted", 0, "#bec2c8"),
+    ("In Progress", "started", 1, "#4ea7fc"),
+    ("In Review", "started", 2, "#5e6ad2"),
+    ("Done", "completed", 3, "#4cb782"),
+    ("Canceled", "canceled", 4, "#95a2b3"),
+];
+
 async fn ensure_workflow_state(
     conn: &Connection,
     team_id: &str,
     name: &str,
     kind: &str,
     position: i64,
+    color: &str,
 ) -> Result<()> {
     let c = count(
         conn,
@@ -311,13 +1661,25 @@ async fn ensure_workflow_state(
     if c == 0 {
         let id = format!("state_{}", short_id());
         conn.execute(
-            "INSERT INTO workflow_states (id, team_id, name, type, position) VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO workflow_states (id, team_id, name, type, position, color) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             vals(vec![
                 id.into(),
                 team_id.to_string().into(),
                 name.to_string().into(),
                 kind.to_string().into(),
                 position.into(),
+                color.to_string().into(),
+            ]),
+        )
+        .await?;
+    } else {
+        // States seeded before colors existed pick up the default too.
+        conn.execute(
+            "UPDATE workflow_states SET color = ?3 WHERE team_id = ?1 AND name = ?2 AND color IS NULL",
+            vals(vec![
+                team_id.to_string().into(),
+                name.to_string().into(),
+                color.to_string().into(),
             ]),
         )
         .await?;
@@ -335,10 +1697,79 @@ async fn count(conn: &Connection, sql: &str, params: Vec<Value>) -> Result<i64>
     Ok(rows.first().map(|r| r.value).unwrap_or(0))
 }
 
+async fn missing_ids(conn: &Connection, table: &str, ids: &[String]) -> Result<Vec<String>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut query = QueryBuilder::default();
+    let list = query.bind_list(ids.iter().cloned());
+    let found: Vec<IdRow> = fetch_all(
+        conn,
+        &format!("SELECT id FROM {table} WHERE id IN ({list})"),
+        query.into_params(),
+    )
+    .await?;
+    let mut missing: Vec<String> = Vec::new();
+    for id in ids {
+        if !found.iter().any(|f| &f.id == id) && !missing.contains(id) {
+            missing.push(id.clone());
+        }
+    }
+    Ok(missing)
+}
+
+// Local SQLite reports contention as SQLITE_BUSY/SQLITE_LOCKED (primary codes 5/6).
+fn is_busy_error(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| match cause.downcast_ref::<libsql::Error>() {
+            Some(libsql::Error::SqliteFailure(code, _)) => matches!(code & 0xff, 5 | 6),
+            Some(other) => {
+                let message = other.to_string();
+                message.contains("database is locked") || message.contains("SQLITE_BUSY")
+            }
+            None => false,
+        })
+}
+
+// Reruns `op` on busy/locked errors with exponential backoff. `op` must be one
+// whole unit of work (a single statement or a full transaction) so a retry
+// never reapplies a partial write.
+async fn retry_busy<T, F, Fut>(retries: u32, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Err(err) if attempt < retries && is_busy_error(&err) => {
+                tokio::time::sleep(Duration::from_millis(10 << attempt.min(8))).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn execute_retrying(
+    conn: &Connection,
+    retries: u32,
+    sql: &str,
+    params: Vec<Value>,
+) -> Result<u64> {
+    retry_busy(retries, || {
+        let params = params.clone();
+        async move { Ok(conn.execute(sql, params).await?) }
+    })
+    .await
+}
+
 async fn fetch_all<T>(conn: &Connection, sql: &str, params: Vec<Value>) -> Result<Vec<T>>
 where
     T: for<'de> Deserialize<'de>,
 {
+    #[cfg(test)]
+    tests::record_query(sql);
     let mut rows = conn.query(sql, params).await?;
     let mut out = Vec::new();
     while let Some(row) = rows.next().await? {
@@ -362,10 +1793,25 @@ struct QueryRoot;
 
 #[Object]
 impl QueryRoot {
+    #[graphql(name = "_health")]
+    async fn health(&self, ctx: &Context<'_>) -> GqlResult<HealthSummary> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        Ok(health_summary(&*app.conn().await?).await)
+    }
+
     async fn viewer(&self, ctx: &Context<'_>) -> GqlResult<Viewer> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        get_viewer(&app.conn).await.map_err(gql_error)
+        get_viewer(&*app.conn().await?).await.map_err(gql_error)
+    }
+
+    async fn organization(&self, ctx: &Context<'_>) -> GqlResult<Organization> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        get_organization(&*app.conn().await?)
+            .await
+            .map_err(gql_error)
     }
 
     async fn teams(
@@ -373,18 +1819,30 @@ impl QueryRoot {
         ctx: &Context<'_>,
         filter: Option<TeamsFilter>,
         first: Option<i32>,
+        after: Option<String>,
     ) -> GqlResult<TeamConnection> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        list_teams(&app.conn, filter, first)
+        list_teams(&*app.conn().await?, app.max_page_size, filter, first, after)
             .await
             .map_err(gql_error)
     }
 
-    async fn team(&self, ctx: &Context<'_>, id: String) -> GqlResult<Option<Team>> {
+    async fn team(
+        &self,
+        ctx: &Context<'_>,
+        id: Option<String>,
+        key: Option<String>,
+    ) -> GqlResult<Option<Team>> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        get_team(&app.conn, &id).await.map_err(gql_error)
+        let conn = &*app.conn().await?;
+        match (id, key) {
+            (Some(id), None) => get_team(conn, &id).await,
+            (None, Some(key)) => get_team_by_key(conn, &key).await,
+            _ => Err(ValidationError("team takes exactly one of id and key".to_string()).into()),
+        }
+        .map_err(gql_error)
     }
 
     async fn projects(
@@ -392,44 +1850,166 @@ impl QueryRoot {
         ctx: &Context<'_>,
         filter: Option<ProjectsFilter>,
         first: Option<i32>,
+        after: Option<String>,
+        order_by: Option<ProjectOrderBy>,
+        order_direction: Option<OrderDirection>,
     ) -> GqlResult<ProjectConnection> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        list_projects(&app.conn, filter, first)
-            .await
-            .map_err(gql_error)
+        list_projects(
+            &*app.conn().await?,
+            app.max_page_size,
+            None,
+            filter,
+            first,
+            after,
+            order_by,
+            order_direction,
+        )
+        .await
+        .map_err(gql_error)
     }
 
     async fn project(&self, ctx: &Context<'_>, id: String) -> GqlResult<Option<Project>> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        let project = get_project(&app.conn, &id).await.map_err(gql_error)?;
-        if project.is_none() {
-            return Err(Error::new("Entity not found: Project"));
-        }
-        Ok(project)
+        let project = get_project(&*app.conn().await?, &id)
+            .await
+            .map_err(gql_error)?;
+        require_found(&app, project, "Project")
     }
 
     async fn issue(&self, ctx: &Context<'_>, id: String) -> GqlResult<Option<Issue>> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        let issue = get_issue(&app.conn, &id).await.map_err(gql_error)?;
-        if issue.is_none() {
-            return Err(Error::new("Entity not found: Issue"));
-        }
-        Ok(issue)
+        let issue = get_issue(&*app.conn().await?, &id)
+            .await
+            .map_err(gql_error)?;
+        require_found(&app, issue, "Issue")
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn issues(
         &self,
         ctx: &Context<'_>,
         filter: Option<IssuesFilter>,
         first: Option<i32>,
+        after: Option<String>,
         order_by: Option<IssueOrderBy>,
+        order_direction: Option<OrderDirection>,
+        sort: Option<Vec<IssueSort>>,
+        active: Option<bool>,
+        include_archived: Option<bool>,
+    ) -> GqlResult<IssueConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_issues(
+            &*app.conn().await?,
+            app.max_page_size,
+            filter,
+            first,
+            after,
+            IssueOrder::from_args(order_by, order_direction, sort),
+            active.unwrap_or(false),
+            include_archived.unwrap_or(false),
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn search_issues(
+        &self,
+        ctx: &Context<'_>,
+        term: String,
+        first: Option<i32>,
+        after: Option<String>,
+        include_archived: Option<bool>,
     ) -> GqlResult<IssueConnection> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        list_issues(&app.conn, filter, first, order_by)
+        search_issues(
+            &*app.conn().await?,
+            app.max_page_size,
+            app.issue_fts,
+            &term,
+            first,
+            after,
+            include_archived.unwrap_or(false),
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn issues_by_state(
+        &self,
+        ctx: &Context<'_>,
+        filter: IssuesFilter,
+        first: Option<i32>,
+    ) -> GqlResult<Vec<IssueStateGroup>> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_issues_by_state(
+            &*app.conn().await?,
+            app.max_page_size,
+            &app.state_cache,
+            filter,
+            first,
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn issue_creation_stats(
+        &self,
+        ctx: &Context<'_>,
+        team_id: String,
+        since: String,
+        until: String,
+    ) -> GqlResult<Vec<IssueCreationBucket>> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        issue_creation_stats(&*app.conn().await?, &team_id, &since, &until)
+            .await
+            .map_err(gql_error)
+    }
+
+    async fn filter_metadata(&self, ctx: &Context<'_>) -> GqlResult<Vec<FilterDimension>> {
+        ensure_auth(ctx)?;
+        Ok(filter_metadata())
+    }
+
+    async fn labels(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<LabelsFilter>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<LabelConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_labels(&*app.conn().await?, app.max_page_size, filter, first, after)
+            .await
+            .map_err(gql_error)
+    }
+
+    async fn cycles(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<CyclesFilter>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<CycleConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_cycles(&*app.conn().await?, app.max_page_size, filter, first, after)
+            .await
+            .map_err(gql_error)
+    }
+
+    async fn label_names(&self, ctx: &Context<'_>) -> GqlResult<Vec<String>> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_label_names(&*app.conn().await?)
             .await
             .map_err(gql_error)
     }
@@ -441,1060 +2021,6961 @@ impl QueryRoot {
     ) -> GqlResult<WorkflowStateConnection> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        list_workflow_states(&app.conn, filter)
+        list_workflow_states(&*app.conn().await?, &app.state_cache, filter)
             .await
             .map_err(gql_error)
     }
 }
 
+const ISSUE_EVENT_CAPACITY: usize = 256;
+
+struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Issues as they are created, updated, archived or unarchived.
+    async fn issues(
+        &self,
+        ctx: &Context<'_>,
+        team_id: Option<String>,
+    ) -> impl Stream<Item = GqlResult<Issue>> {
+        if let Err(err) = ensure_auth(ctx) {
+            return stream::once(future::ready(Err(err))).boxed();
+        }
+        let app = app_ctx(ctx);
+        let events = stream::unfold(app.issue_events.subscribe(), |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(issue) => return Some((issue, rx)),
+                    // A slow subscriber skips what it missed rather than disconnecting.
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        events
+            .filter(move |issue| {
+                future::ready(team_id.as_ref().is_none_or(|id| *id == issue.team.id))
+            })
+            .map(Ok)
+            .boxed()
+    }
+}
+
 #[derive(Clone, Default)]
 struct MutationRoot;
 
 #[Object]
 impl MutationRoot {
-    async fn project_create(
+    async fn team_create(
         &self,
         ctx: &Context<'_>,
-        input: ProjectCreateInput,
-    ) -> GqlResult<ProjectCreatePayload> {
+        input: TeamCreateInput,
+    ) -> GqlResult<TeamCreatePayload> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        create_project(&app.conn, &app.base_url, input)
-            .await
-            .map_err(gql_error)
+        let result = create_team(&*app.conn().await?, input).await;
+        app.state_cache.invalidate();
+        result.map_err(gql_error)
     }
 
-    async fn issue_create(
+    async fn team_update(
         &self,
         ctx: &Context<'_>,
-        input: IssueCreateInput,
-    ) -> GqlResult<IssueCreatePayload> {
+        id: String,
+        input: TeamUpdateInput,
+    ) -> GqlResult<TeamUpdatePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        update_team(
+            &*app.conn().await?,
+            app.busy_retries,
+            &app.base_url,
+            &id,
+            input,
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn team_membership_create(
+        &self,
+        ctx: &Context<'_>,
+        team_id: String,
+        user_id: String,
+    ) -> GqlResult<TeamMembershipPayload> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        create_issue(&app.conn, &app.base_url, input)
+        create_team_membership(&*app.conn().await?, &team_id, &user_id)
             .await
             .map_err(gql_error)
     }
 
-    async fn comment_create(
+    async fn team_membership_delete(
         &self,
         ctx: &Context<'_>,
-        input: CommentCreateInput,
-    ) -> GqlResult<CommentCreatePayload> {
+        team_id: String,
+        user_id: String,
+    ) -> GqlResult<TeamMembershipPayload> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        create_comment(&app.conn, &app.base_url, input)
+        delete_team_membership(&*app.conn().await?, &team_id, &user_id)
             .await
             .map_err(gql_error)
     }
 
-    async fn issue_update(
+    // Archived issues block the delete unless `deleteArchivedIssues: true`
+    // destroys them along with the team.
+    async fn team_delete(
         &self,
         ctx: &Context<'_>,
         id: String,
-        input: IssueUpdateInput,
-    ) -> GqlResult<IssueUpdatePayload> {
+        delete_archived_issues: Option<bool>,
+    ) -> GqlResult<TeamDeletePayload> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        update_issue(&app.conn, &id, input).await.map_err(gql_error)
+        let result = delete_team(
+            &*app.conn().await?,
+            app.busy_retries,
+            &id,
+            delete_archived_issues.unwrap_or(false),
+        )
+        .await;
+        app.state_cache.invalidate();
+        result.map_err(gql_error)
     }
 
-    async fn issue_archive(&self, ctx: &Context<'_>, id: String) -> GqlResult<IssueArchivePayload> {
+    async fn project_create(
+        &self,
+        ctx: &Context<'_>,
+        input: ProjectCreateInput,
+    ) -> GqlResult<ProjectCreatePayload> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        archive_issue(&app.conn, &id).await.map_err(gql_error)
+        create_project(&*app.conn().await?, &app.base_url, input)
+            .await
+            .map_err(gql_error)
     }
 
-    async fn issue_add_label(
+    async fn project_update(
         &self,
         ctx: &Context<'_>,
         id: String,
-        label_id: String,
-    ) -> GqlResult<IssueAddLabelPayload> {
+        input: ProjectUpdateInput,
+    ) -> GqlResult<ProjectUpdatePayload> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        add_label(&app.conn, &id, &label_id)
+        update_project(&*app.conn().await?, &id, input)
             .await
             .map_err(gql_error)
     }
 
-    async fn admin_import_project(
+    async fn project_archive(
         &self,
         ctx: &Context<'_>,
-        input: AdminImportProjectInput,
-    ) -> GqlResult<AdminImportProjectPayload> {
+        id: String,
+    ) -> GqlResult<ProjectArchivePayload> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        import_project_1to1(&app.conn, input)
+        set_project_archived(&*app.conn().await?, &id, true)
             .await
             .map_err(gql_error)
     }
-}
-
-#[derive(Clone, SimpleObject)]
-struct TeamConnection {
-    nodes: Vec<Team>,
-}
-
-#[derive(Clone, SimpleObject)]
-struct ProjectConnection {
-    nodes: Vec<Project>,
-}
-
-#[derive(Clone, SimpleObject)]
-struct IssueConnection {
-    nodes: Vec<Issue>,
-}
-
-#[derive(Clone, SimpleObject)]
-struct LabelConnection {
-    nodes: Vec<Label>,
-}
 
-#[derive(Clone, SimpleObject)]
-struct WorkflowStateConnection {
-    nodes: Vec<WorkflowState>,
-}
+    async fn project_unarchive(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+    ) -> GqlResult<ProjectArchivePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        set_project_archived(&*app.conn().await?, &id, false)
+            .await
+            .map_err(gql_error)
+    }
 
-#[derive(Clone, SimpleObject)]
-#[graphql(complex, rename_fields = "camelCase")]
-struct Viewer {
-    id: String,
-    name: String,
-    email: String,
-}
+    async fn project_milestone_create(
+        &self,
+        ctx: &Context<'_>,
+        input: ProjectMilestoneCreateInput,
+    ) -> GqlResult<ProjectMilestoneCreatePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        create_project_milestone(&*app.conn().await?, app.busy_retries, input)
+            .await
+            .map_err(gql_error)
+    }
 
-#[ComplexObject]
-impl Viewer {
-    async fn teams(&self, ctx: &Context<'_>, first: Option<i32>) -> GqlResult<TeamConnection> {
+    async fn issue_create(
+        &self,
+        ctx: &Context<'_>,
+        input: IssueCreateInput,
+    ) -> GqlResult<IssueCreatePayload> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        let limit = clamp_limit(first);
-        let rows: Vec<TeamRow> = fetch_all(
-            &app.conn,
-            "SELECT t.id, t.name, t.key
-             FROM teams t
-             INNER JOIN team_members tm ON tm.team_id = t.id
-             WHERE tm.user_id = ?1
-             ORDER BY t.name ASC
-             LIMIT ?2",
-            vec![self.id.clone().into(), i64::from(limit).into()],
+        let payload = create_issue(
+            &*app.conn().await?,
+            app.busy_retries,
+            &app.base_url,
+            app.allow_impersonation,
+            app.max_issues_per_team,
+            input,
         )
         .await
         .map_err(gql_error)?;
-        Ok(TeamConnection {
-            nodes: rows.into_iter().map(Team::from).collect(),
-        })
+        app.issue_event("create", &payload.issue);
+        Ok(payload)
     }
-}
-
-#[derive(Clone, SimpleObject)]
-#[graphql(complex, rename_fields = "camelCase")]
-struct Team {
-    id: String,
-    name: String,
-    key: String,
-}
 
-#[ComplexObject]
-impl Team {
-    async fn states(&self, ctx: &Context<'_>) -> GqlResult<WorkflowStateConnection> {
+    async fn comment_create(
+        &self,
+        ctx: &Context<'_>,
+        input: CommentCreateInput,
+    ) -> GqlResult<CommentCreatePayload> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        let rows: Vec<WorkflowStateRow> = fetch_all(
-            &app.conn,
-            "SELECT id, name, type AS state_type
-             FROM workflow_states
-             WHERE team_id = ?1
-             ORDER BY position ASC",
-            vec![self.id.clone().into()],
+        create_comment(
+            &*app.conn().await?,
+            app.busy_retries,
+            &app.base_url,
+            app.allow_impersonation,
+            input,
         )
         .await
-        .map_err(gql_error)?;
-        Ok(WorkflowStateConnection {
-            nodes: rows.into_iter().map(WorkflowState::from).collect(),
-        })
+        .map_err(gql_error)
     }
-}
 
-#[derive(Clone, SimpleObject)]
-#[graphql(complex, rename_fields = "camelCase")]
-struct Project {
-    id: String,
-    name: String,
-    slug_id: Option<String>,
-    state: Option<String>,
-    archived_at: Option<String>,
-    url: Option<String>,
-}
+    async fn attachment_create(
+        &self,
+        ctx: &Context<'_>,
+        input: AttachmentCreateInput,
+    ) -> GqlResult<AttachmentCreatePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        create_attachment(&*app.conn().await?, app.busy_retries, input)
+            .await
+            .map_err(gql_error)
+    }
 
-#[ComplexObject]
-impl Project {
-    async fn issues(&self, ctx: &Context<'_>, first: Option<i32>) -> GqlResult<IssueConnection> {
+    /// Multipart upload; the file is served back from `Attachment.url`.
+    async fn attachment_upload(
+        &self,
+        ctx: &Context<'_>,
+        issue_id: String,
+        file: Upload,
+        title: Option<String>,
+        subtitle: Option<String>,
+    ) -> GqlResult<AttachmentCreatePayload> {
         ensure_auth(ctx)?;
         let app = app_ctx(ctx);
-        let limit = clamp_limit(first);
-        let rows: Vec<IssueBaseRow> = fetch_all(
-            &app.conn,
-            &format!(
-                "{} WHERE i.archived = 0 AND i.project_id = ?1 ORDER BY i.updated_at DESC LIMIT ?2",
-                issue_base_select()
-            ),
-            vec![self.id.clone().into(), i64::from(limit).into()],
+        let file = file.value(ctx)?;
+        upload_attachment(&*app.conn().await?, &app, issue_id, title, subtitle, file)
+            .await
+            .map_err(gql_error)
+    }
+
+    async fn reaction_create(
+        &self,
+        ctx: &Context<'_>,
+        input: ReactionCreateInput,
+    ) -> GqlResult<ReactionCreatePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        create_reaction(
+            &*app.conn().await?,
+            app.busy_retries,
+            app.allow_impersonation,
+            input,
         )
         .await
-        .map_err(gql_error)?;
-        let mut issues = Vec::with_capacity(rows.len());
-        for row in rows {
-            issues.push(issue_from_row(&app.conn, row).await.map_err(gql_error)?);
-        }
-        Ok(IssueConnection { nodes: issues })
+        .map_err(gql_error)
     }
-}
-
-#[derive(Clone, SimpleObject)]
-#[graphql(rename_fields = "camelCase")]
-struct Issue {
-    id: String,
-    identifier: String,
-    title: String,
-    url: String,
-    description: Option<String>,
-    assignee: Option<User>,
-    project: Option<Project>,
-    state: WorkflowState,
-    labels: LabelConnection,
-    updated_at: Option<String>,
-}
 
-#[derive(Clone, SimpleObject)]
-#[graphql(rename_fields = "camelCase")]
-struct User {
-    id: String,
-    name: String,
-    email: String,
-}
+    async fn reaction_delete(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+    ) -> GqlResult<ReactionDeletePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        delete_reaction(&*app.conn().await?, app.busy_retries, &id)
+            .await
+            .map_err(gql_error)
+    }
 
-#[derive(Clone, SimpleObject)]
-#[graphql(rename_fields = "camelCase")]
-struct Label {
-    id: String,
-    name: String,
-}
+    async fn favorite_create(
+        &self,
+        ctx: &Context<'_>,
+        input: FavoriteCreateInput,
+    ) -> GqlResult<FavoriteCreatePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        create_favorite(&*app.conn().await?, app.busy_retries, input)
+            .await
+            .map_err(gql_error)
+    }
 
-#[derive(Clone, SimpleObject)]
-#[graphql(rename_fields = "camelCase")]
-struct WorkflowState {
-    id: String,
-    name: String,
-    #[graphql(name = "type")]
-    r#type: Option<String>,
-}
+    async fn favorite_delete(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+    ) -> GqlResult<FavoriteDeletePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        delete_favorite(&*app.conn().await?, app.busy_retries, &id)
+            .await
+            .map_err(gql_error)
+    }
 
-#[derive(Clone, SimpleObject)]
-#[graphql(rename_fields = "camelCase")]
-struct Comment {
-    id: String,
-    body: String,
-    url: String,
-}
+    async fn issue_update(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        input: IssueUpdateInput,
+    ) -> GqlResult<IssueUpdatePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let payload = update_issue(&*app.conn().await?, app.busy_retries, &id, input)
+            .await
+            .map_err(gql_error)?;
+        app.issue_event("update", &payload.issue);
+        Ok(payload)
+    }
 
-#[derive(Clone, SimpleObject)]
-#[graphql(rename_fields = "camelCase")]
-struct ProjectCreatePayload {
-    success: bool,
-    project: Project,
-}
+    async fn issue_batch_update(
+        &self,
+        ctx: &Context<'_>,
+        ids: Vec<String>,
+        input: IssueUpdateInput,
+    ) -> GqlResult<IssueBatchPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let payload = batch_update_issues(&*app.conn().await?, app.busy_retries, ids, input)
+            .await
+            .map_err(gql_error)?;
+        for issue in &payload.issues {
+            app.issue_event("update", issue);
+        }
+        Ok(payload)
+    }
 
-#[derive(Clone, SimpleObject)]
-#[graphql(rename_fields = "camelCase")]
-struct IssueCreatePayload {
-    success: bool,
-    issue: Issue,
-}
+    async fn issue_resolve(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        state_id: String,
+        comment: String,
+    ) -> GqlResult<IssueResolvePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let payload = resolve_issue(
+            &*app.conn().await?,
+            app.busy_retries,
+            &app.base_url,
+            &id,
+            &state_id,
+            comment,
+        )
+        .await
+        .map_err(gql_error)?;
+        app.issue_event("update", &payload.issue);
+        Ok(payload)
+    }
 
-#[derive(Clone, SimpleObject)]
-#[graphql(rename_fields = "camelCase")]
-struct CommentCreatePayload {
-    success: bool,
-    comment: Comment,
-}
+    async fn issue_cycle_state(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        direction: CycleDirection,
+    ) -> GqlResult<IssueUpdatePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let payload = cycle_issue_state(&*app.conn().await?, app.busy_retries, &id, direction)
+            .await
+            .map_err(gql_error)?;
+        app.issue_event("update", &payload.issue);
+        Ok(payload)
+    }
 
-#[derive(Clone, SimpleObject)]
-#[graphql(rename_fields = "camelCase")]
-struct IssueUpdatePayload {
-    success: bool,
-    issue: Issue,
-}
+    async fn issue_archive(&self, ctx: &Context<'_>, id: String) -> GqlResult<IssueArchivePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let conn = app.conn().await?;
+        let payload = set_issue_archived(&conn, app.busy_retries, &id, true)
+            .await
+            .map_err(gql_error)?;
+        app.issue_event_by_id(&conn, "archive", &id).await;
+        Ok(payload)
+    }
 
-#[derive(Clone, SimpleObject)]
-#[graphql(rename_fields = "camelCase")]
-struct IssueArchivePayload {
-    success: bool,
-}
+    async fn issue_unarchive(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+    ) -> GqlResult<IssueArchivePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let conn = app.conn().await?;
+        let payload = set_issue_archived(&conn, app.busy_retries, &id, false)
+            .await
+            .map_err(gql_error)?;
+        app.issue_event_by_id(&conn, "unarchive", &id).await;
+        Ok(payload)
+    }
 
-#[derive(Clone, SimpleObject)]
+    async fn project_delete(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        reassign: Option<bool>,
+    ) -> GqlResult<ProjectDeletePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        delete_project(&*app.conn().await?, app.busy_retries, &id, reassign)
+            .await
+            .map_err(gql_error)
+    }
+
+    async fn project_archive_issues(
+        &self,
+        ctx: &Context<'_>,
+        project_id: String,
+    ) -> GqlResult<ProjectArchiveIssuesPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let conn = app.conn().await?;
+        let archived = archive_project_issues(&conn, app.busy_retries, &project_id)
+            .await
+            .map_err(gql_error)?;
+        for id in &archived {
+            app.issue_event_by_id(&conn, "archive", id).await;
+        }
+        Ok(ProjectArchiveIssuesPayload {
+            success: true,
+            archived_count: archived.len() as i64,
+        })
+    }
+
+    async fn user_reassign_issues(
+        &self,
+        ctx: &Context<'_>,
+        from_user_id: String,
+        to_user_id: String,
+    ) -> GqlResult<UserReassignIssuesPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        reassign_user_issues(
+            &*app.conn().await?,
+            app.busy_retries,
+            &from_user_id,
+            &to_user_id,
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn label_create(
+        &self,
+        ctx: &Context<'_>,
+        input: LabelCreateInput,
+    ) -> GqlResult<LabelCreatePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        create_label(&*app.conn().await?, input)
+            .await
+            .map_err(gql_error)
+    }
+
+    async fn cycle_create(
+        &self,
+        ctx: &Context<'_>,
+        input: CycleCreateInput,
+    ) -> GqlResult<CycleCreatePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        create_cycle(&*app.conn().await?, app.busy_retries, input)
+            .await
+            .map_err(gql_error)
+    }
+
+    async fn issue_add_label(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        label_id: String,
+    ) -> GqlResult<IssueAddLabelPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let conn = app.conn().await?;
+        let payload = add_label(&conn, app.busy_retries, &id, &label_id, app.strict_labels)
+            .await
+            .map_err(gql_error)?;
+        if payload.success {
+            app.issue_event_by_id(&conn, "update", &id).await;
+        }
+        Ok(payload)
+    }
+
+    async fn issue_remove_label(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        label_id: String,
+    ) -> GqlResult<IssueRemoveLabelPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let conn = app.conn().await?;
+        let payload = remove_label(&conn, app.busy_retries, &id, &label_id)
+            .await
+            .map_err(gql_error)?;
+        if payload.success {
+            app.issue_event_by_id(&conn, "update", &id).await;
+        }
+        Ok(payload)
+    }
+
+    async fn issue_set_labels(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        label_ids: Vec<String>,
+    ) -> GqlResult<IssueSetLabelsPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let payload = set_labels(&*app.conn().await?, app.busy_retries, &id, label_ids)
+            .await
+            .map_err(gql_error)?;
+        app.issue_event("update", &payload.issue);
+        Ok(payload)
+    }
+
+    async fn issue_set_cycle(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        cycle_id: Option<String>,
+    ) -> GqlResult<IssueSetCyclePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let payload = set_issue_cycle(&*app.conn().await?, app.busy_retries, &id, cycle_id)
+            .await
+            .map_err(gql_error)?;
+        app.issue_event("update", &payload.issue);
+        Ok(payload)
+    }
+
+    async fn issue_move_to_active_cycle(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+    ) -> GqlResult<IssueSetCyclePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let payload = move_issue_to_active_cycle(&*app.conn().await?, app.busy_retries, &id)
+            .await
+            .map_err(gql_error)?;
+        app.issue_event("update", &payload.issue);
+        Ok(payload)
+    }
+
+    async fn workflow_state_create(
+        &self,
+        ctx: &Context<'_>,
+        input: WorkflowStateCreateInput,
+    ) -> GqlResult<WorkflowStatePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let result = create_workflow_state(&*app.conn().await?, input).await;
+        app.state_cache.invalidate();
+        result.map_err(gql_error)
+    }
+
+    async fn workflow_state_update(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+        input: WorkflowStateUpdateInput,
+    ) -> GqlResult<WorkflowStatePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let result = update_workflow_state(&*app.conn().await?, &id, input).await;
+        app.state_cache.invalidate();
+        result.map_err(gql_error)
+    }
+
+    async fn admin_import_project(
+        &self,
+        ctx: &Context<'_>,
+        input: AdminImportProjectInput,
+    ) -> GqlResult<AdminImportProjectPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        import_project_1to1(&*app.conn().await?, input)
+            .await
+            .map_err(gql_error)
+    }
+
+    async fn admin_import_issue(
+        &self,
+        ctx: &Context<'_>,
+        input: AdminImportIssueInput,
+    ) -> GqlResult<AdminImportIssuePayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let (payload, created) = import_issue_1to1(&*app.conn().await?, app.busy_retries, input)
+            .await
+            .map_err(gql_error)?;
+        app.issue_event(if created { "create" } else { "update" }, &payload.issue);
+        Ok(payload)
+    }
+
+    async fn admin_import_team(
+        &self,
+        ctx: &Context<'_>,
+        input: AdminImportTeamInput,
+    ) -> GqlResult<AdminImportTeamPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let result = import_team_1to1(&*app.conn().await?, input).await;
+        app.state_cache.invalidate();
+        result.map_err(gql_error)
+    }
+
+    async fn admin_import_labels(
+        &self,
+        ctx: &Context<'_>,
+        inputs: Vec<AdminImportLabelInput>,
+    ) -> GqlResult<AdminImportLabelsPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        import_labels_1to1(&*app.conn().await?, app.busy_retries, inputs)
+            .await
+            .map_err(gql_error)
+    }
+
+    async fn admin_reindex(&self, ctx: &Context<'_>) -> GqlResult<AdminReindexPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        if !app.issue_fts {
+            return Err(gql_error(anyhow::anyhow!(
+                "full-text search is unavailable (this SQLite build lacks FTS5)"
+            )));
+        }
+        rebuild_issue_search_index(&*app.conn().await?)
+            .await
+            .map_err(gql_error)
+    }
+
+    async fn admin_snapshot(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+    ) -> GqlResult<AdminSnapshotPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let path = snapshot_path(&app, &name).map_err(gql_error)?;
+        save_snapshot(&*app.conn().await?, &path)
+            .await
+            .map_err(gql_error)?;
+        Ok(AdminSnapshotPayload {
+            success: true,
+            name,
+            path,
+        })
+    }
+
+    async fn admin_restore(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+    ) -> GqlResult<AdminSnapshotPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let path = snapshot_path(&app, &name).map_err(gql_error)?;
+        let result = restore_snapshot(&*app.conn().await?, &path).await;
+        app.state_cache.invalidate();
+        result.map_err(gql_error)?;
+        Ok(AdminSnapshotPayload {
+            success: true,
+            name,
+            path,
+        })
+    }
+
+    async fn admin_query(&self, ctx: &Context<'_>, sql: String) -> GqlResult<AdminQueryPayload> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        if !app.allow_raw_sql {
+            return Err(typed_error(
+                ErrorType::FeatureNotAccessible,
+                "adminQuery is disabled (set SUBLINEAR_ALLOW_RAW_SQL=true to enable)",
+            ));
+        }
+        run_read_only_query(&*app.conn().await?, &sql)
+            .await
+            .map_err(gql_error)
+    }
+}
+
+// Backs `totalCount`: the list query's WHERE clause without the cursor, run as
+// a COUNT(*) only when the field is selected.
+#[derive(Clone)]
+enum TotalCount {
+    Query { sql: String, params: Vec<Value> },
+    Known(i64),
+}
+
+impl TotalCount {
+    async fn resolve(&self, ctx: &Context<'_>) -> GqlResult<i64> {
+        match self {
+            TotalCount::Query { sql, params } => {
+                let app = app_ctx(ctx);
+                count(&*app.conn().await?, sql, params.clone())
+                    .await
+                    .map_err(gql_error)
+            }
+            TotalCount::Known(total) => Ok(*total),
+        }
+    }
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(complex, rename_fields = "camelCase")]
+struct TeamConnection {
+    nodes: Vec<Team>,
+    page_info: PageInfo,
+    #[graphql(skip)]
+    total: TotalCount,
+}
+
+#[ComplexObject]
+impl TeamConnection {
+    /// Matches across every page, ignoring `first` and `after`.
+    async fn total_count(&self, ctx: &Context<'_>) -> GqlResult<i64> {
+        self.total.resolve(ctx).await
+    }
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(complex, rename_fields = "camelCase")]
+struct ProjectConnection {
+    nodes: Vec<Project>,
+    page_info: PageInfo,
+    #[graphql(skip)]
+    total: TotalCount,
+}
+
+#[ComplexObject]
+impl ProjectConnection {
+    /// Matches across every page, ignoring `first` and `after`.
+    async fn total_count(&self, ctx: &Context<'_>) -> GqlResult<i64> {
+        self.total.resolve(ctx).await
+    }
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(complex, rename_fields = "camelCase")]
+struct IssueConnection {
+    nodes: Vec<Issue>,
+    page_info: PageInfo,
+    #[graphql(skip)]
+    total: TotalCount,
+    #[graphql(skip)]
+    estimates: IssueEstimates,
+}
+
+#[ComplexObject]
+impl IssueConnection {
+    /// Matches across every page, ignoring `first` and `after`.
+    async fn total_count(&self, ctx: &Context<'_>) -> GqlResult<i64> {
+        self.total.resolve(ctx).await
+    }
+
+    /// Sum of `estimate` across every page; 0 when no match has one.
+    async fn estimate_sum(&self, ctx: &Context<'_>) -> GqlResult<f64> {
+        Ok(self.estimates.resolve(ctx).await?.estimate_sum)
+    }
+
+    /// Matches with an `estimate`, across every page.
+    async fn estimate_count(&self, ctx: &Context<'_>) -> GqlResult<i64> {
+        Ok(self.estimates.resolve(ctx).await?.estimate_count)
+    }
+}
+
+// Backs `estimateSum` / `estimateCount`: one SUM/COUNT over the list query's
+// FROM and WHERE without the cursor, shared by both fields.
+#[derive(Clone)]
+struct IssueEstimates {
+    from: String,
+    params: Vec<Value>,
+    totals: Arc<tokio::sync::OnceCell<EstimateTotalsRow>>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+struct EstimateTotalsRow {
+    estimate_sum: f64,
+    estimate_count: i64,
+}
+
+impl IssueEstimates {
+    fn new(from: String, params: Vec<Value>) -> Self {
+        Self {
+            from,
+            params,
+            totals: Arc::default(),
+        }
+    }
+
+    async fn resolve(&self, ctx: &Context<'_>) -> GqlResult<EstimateTotalsRow> {
+        let totals = self
+            .totals
+            .get_or_try_init(|| async {
+                let app = app_ctx(ctx);
+                let sql = format!(
+                    "SELECT COALESCE(SUM(i.estimate), 0.0) AS estimate_sum, COUNT(i.estimate) AS estimate_count FROM {}",
+                    self.from
+                );
+                fetch_one::<EstimateTotalsRow>(&*app.conn().await?, &sql, self.params.clone())
+                    .await
+                    .map_err(gql_error)?
+                    .ok_or_else(|| Error::new("estimate totals returned no row"))
+            })
+            .await?;
+        Ok(*totals)
+    }
+}
+
+#[derive(Clone, SimpleObject)]
 #[graphql(rename_fields = "camelCase")]
-struct IssueAddLabelPayload {
-    success: bool,
+struct PageInfo {
+    has_next_page: bool,
+    has_previous_page: bool,
+    start_cursor: Option<String>,
+    end_cursor: Option<String>,
 }
 
 #[derive(Clone, SimpleObject)]
 #[graphql(rename_fields = "camelCase")]
-struct AdminImportProjectPayload {
-    success: bool,
-    project: Project,
+struct UserConnection {
+    nodes: Vec<User>,
+    page_info: PageInfo,
 }
 
-#[derive(InputObject, Clone, Default)]
+#[derive(Clone, SimpleObject)]
 #[graphql(rename_fields = "camelCase")]
-struct StringFilter {
-    eq: Option<String>,
-    neq: Option<String>,
+struct LabelConnection {
+    nodes: Vec<Label>,
+    page_info: PageInfo,
 }
 
-#[derive(InputObject, Clone, Default)]
+#[derive(Clone, SimpleObject)]
 #[graphql(rename_fields = "camelCase")]
-struct IdFilter {
-    eq: Option<String>,
+struct ProjectMilestoneConnection {
+    nodes: Vec<ProjectMilestone>,
+    page_info: PageInfo,
 }
 
-#[derive(InputObject, Clone, Default)]
+#[derive(Clone, SimpleObject)]
 #[graphql(rename_fields = "camelCase")]
-struct FloatFilter {
-    #[graphql(name = "in")]
-    in_values: Option<Vec<f64>>,
+struct CycleConnection {
+    nodes: Vec<Cycle>,
+    page_info: PageInfo,
 }
 
-#[derive(InputObject, Clone, Default)]
+#[derive(Clone, SimpleObject)]
 #[graphql(rename_fields = "camelCase")]
-struct TeamFilter {
-    id: Option<IdFilter>,
-    key: Option<StringFilter>,
-    name: Option<StringFilter>,
+struct WorkflowStateConnection {
+    nodes: Vec<WorkflowState>,
+    page_info: PageInfo,
+}
+
+impl WorkflowStateConnection {
+    fn complete(nodes: Vec<WorkflowState>) -> Self {
+        let page_info = page_info(&nodes, false, false, "workflowStates:position", |s| {
+            vec![s.position.to_string(), s.id.clone()]
+        });
+        Self { nodes, page_info }
+    }
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct FilterDimension {
+    dimension: String,
+    values: Vec<String>,
+}
+
+#[derive(Clone, SimpleObject, Deserialize)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueCreationBucket {
+    date: String,
+    count: i64,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueStateGroup {
+    state: WorkflowState,
+    issues: IssueConnection,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct HealthSummary {
+    ok: bool,
+    db_latency_ms: Option<i32>,
+    issue_count: Option<i32>,
+    team_count: Option<i32>,
+    error: Option<String>,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(complex, rename_fields = "camelCase")]
+struct Organization {
+    id: String,
+    name: String,
+    url_key: String,
+    created_at: String,
+}
+
+#[ComplexObject]
+impl Organization {
+    async fn teams(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<TeamConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_teams(&*app.conn().await?, app.max_page_size, None, first, after)
+            .await
+            .map_err(gql_error)
+    }
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(complex, rename_fields = "camelCase")]
+struct Viewer {
+    id: String,
+    name: String,
+    email: String,
+}
+
+#[ComplexObject]
+impl Viewer {
+    async fn favorites(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<FavoriteConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_favorites(
+            &*app.conn().await?,
+            app.max_page_size,
+            &self.id,
+            first,
+            after,
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn teams(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<TeamConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_user_teams(
+            &*app.conn().await?,
+            app.max_page_size,
+            &self.id,
+            first,
+            after,
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn assigned_issues(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        filter: Option<IssuesFilter>,
+    ) -> GqlResult<IssueConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let mut filter = filter.unwrap_or_default();
+        filter.assignee = Some(AssigneeFilter {
+            id: Some(IdFilter {
+                eq: Some(self.id.clone()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        list_issues(
+            &*app.conn().await?,
+            app.max_page_size,
+            Some(filter),
+            first,
+            after,
+            IssueOrder::default(),
+            false,
+            false,
+        )
+        .await
+        .map_err(gql_error)
+    }
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(complex, rename_fields = "camelCase")]
+struct Team {
+    id: String,
+    name: String,
+    key: String,
+}
+
+#[ComplexObject]
+impl Team {
+    async fn states(&self, ctx: &Context<'_>) -> GqlResult<WorkflowStateConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let nodes = app
+            .state_cache
+            .team_states(&*app.conn().await?, &self.id)
+            .await
+            .map_err(gql_error)?;
+        Ok(WorkflowStateConnection::complete(nodes))
+    }
+
+    async fn issues(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<IssueConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let filter = IssuesFilter {
+            team: Some(TeamFilter {
+                id: Some(IdFilter {
+                    eq: Some(self.id.clone()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        list_issues(
+            &*app.conn().await?,
+            app.max_page_size,
+            Some(filter),
+            first,
+            after,
+            IssueOrder::default(),
+            false,
+            false,
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn projects(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<ProjectConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_projects(
+            &*app.conn().await?,
+            app.max_page_size,
+            Some(&self.id),
+            None,
+            first,
+            after,
+            None,
+            None,
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn members(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<UserConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_team_members(
+            &*app.conn().await?,
+            app.max_page_size,
+            &self.id,
+            first,
+            after,
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn cycles(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<CycleConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let filter = CyclesFilter {
+            team: Some(TeamFilter {
+                id: Some(IdFilter {
+                    eq: Some(self.id.clone()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        };
+        list_cycles(
+            &*app.conn().await?,
+            app.max_page_size,
+            Some(filter),
+            first,
+            after,
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn active_cycle(&self, ctx: &Context<'_>) -> GqlResult<Option<Cycle>> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        team_cycle(
+            &*app.conn().await?,
+            &self.id,
+            CycleWindow::Active,
+            &now_iso(),
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn previous_cycle(&self, ctx: &Context<'_>) -> GqlResult<Option<Cycle>> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        team_cycle(
+            &*app.conn().await?,
+            &self.id,
+            CycleWindow::Previous,
+            &now_iso(),
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn next_cycle(&self, ctx: &Context<'_>) -> GqlResult<Option<Cycle>> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        team_cycle(&*app.conn().await?, &self.id, CycleWindow::Next, &now_iso())
+            .await
+            .map_err(gql_error)
+    }
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(complex, rename_fields = "camelCase")]
+struct Project {
+    id: String,
+    name: String,
+    slug_id: Option<String>,
+    state: Option<String>,
+    description: Option<String>,
+    archived_at: Option<String>,
+    url: Option<String>,
+    sort_order: f64,
+}
+
+#[ComplexObject]
+impl Project {
+    async fn issues(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        include_archived: Option<bool>,
+    ) -> GqlResult<IssueConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let filter = IssuesFilter {
+            project: Some(ProjectFilter {
+                id: Some(IdFilter {
+                    eq: Some(self.id.clone()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        list_issues(
+            &*app.conn().await?,
+            app.max_page_size,
+            Some(filter),
+            first,
+            after,
+            IssueOrder::default(),
+            false,
+            include_archived.unwrap_or(false),
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn milestones(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<ProjectMilestoneConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_project_milestones(
+            &*app.conn().await?,
+            app.max_page_size,
+            &self.id,
+            first,
+            after,
+        )
+        .await
+        .map_err(gql_error)
+    }
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(complex, rename_fields = "camelCase")]
+struct Issue {
+    id: String,
+    identifier: String,
+    number: i32,
+    #[graphql(skip)]
+    parent_id: Option<String>,
+    team_key: String,
+    title: String,
+    url: String,
+    description: Option<String>,
+    team: Team,
+    assignee: Option<User>,
+    creator: Option<User>,
+    project: Option<Project>,
+    project_milestone: Option<ProjectMilestone>,
+    cycle: Option<Cycle>,
+    state: WorkflowState,
+    labels: LabelConnection,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    completed_at: Option<String>,
+    archived: bool,
+    archived_at: Option<String>,
+    priority: i32,
+    priority_label: String,
+    estimate: Option<f64>,
+    due_date: Option<String>,
+    sort_order: f64,
+    /// 0-based position among the state's non-archived issues by `sortOrder`;
+    /// only issue lists fill it in.
+    state_column_rank: Option<i32>,
+    comment_count: i32,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(complex, rename_fields = "camelCase")]
+struct User {
+    id: String,
+    name: String,
+    email: String,
+}
+
+#[ComplexObject]
+impl Issue {
+    async fn parent(&self, ctx: &Context<'_>) -> GqlResult<Option<Issue>> {
+        ensure_auth(ctx)?;
+        let Some(parent_id) = &self.parent_id else {
+            return Ok(None);
+        };
+        let app = app_ctx(ctx);
+        get_issue(&*app.conn().await?, parent_id)
+            .await
+            .map_err(gql_error)
+    }
+
+    async fn children(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<IssueConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        let filter = IssuesFilter {
+            parent: Some(ParentFilter {
+                id: Some(IdFilter {
+                    eq: Some(self.id.clone()),
+                    ..Default::default()
+                }),
+            }),
+            ..Default::default()
+        };
+        list_issues(
+            &*app.conn().await?,
+            app.max_page_size,
+            Some(filter),
+            first,
+            after,
+            IssueOrder::default(),
+            false,
+            false,
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn comments(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<CommentConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_issue_comments(
+            &*app.conn().await?,
+            app.max_page_size,
+            &self.id,
+            first,
+            after,
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn reactions(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<ReactionConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_reactions(
+            &*app.conn().await?,
+            app.max_page_size,
+            SubjectType::Issue,
+            &self.id,
+            first,
+            after,
+        )
+        .await
+        .map_err(gql_error)
+    }
+
+    async fn attachments(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<AttachmentConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_issue_attachments(
+            &*app.conn().await?,
+            app.max_page_size,
+            &self.id,
+            first,
+            after,
+        )
+        .await
+        .map_err(gql_error)
+    }
+}
+
+#[ComplexObject]
+impl User {
+    async fn teams(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<TeamConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_user_teams(
+            &*app.conn().await?,
+            app.max_page_size,
+            &self.id,
+            first,
+            after,
+        )
+        .await
+        .map_err(gql_error)
+    }
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct Label {
+    id: String,
+    name: String,
+    color: Option<String>,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct ProjectMilestone {
+    id: String,
+    name: String,
+    sort_order: f64,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct Cycle {
+    id: String,
+    number: i32,
+    name: Option<String>,
+    starts_at: String,
+    ends_at: String,
+    created_at: String,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct WorkflowState {
+    id: String,
+    name: String,
+    #[graphql(name = "type")]
+    r#type: Option<String>,
+    position: i32,
+    color: Option<String>,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(complex, rename_fields = "camelCase")]
+struct Comment {
+    id: String,
+    body: String,
+    url: String,
+    created_at: String,
+    user: Option<User>,
+}
+
+#[ComplexObject]
+impl Comment {
+    async fn reactions(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> GqlResult<ReactionConnection> {
+        ensure_auth(ctx)?;
+        let app = app_ctx(ctx);
+        list_reactions(
+            &*app.conn().await?,
+            app.max_page_size,
+            SubjectType::Comment,
+            &self.id,
+            first,
+            after,
+        )
+        .await
+        .map_err(gql_error)
+    }
+}
+
+// Exactly one of `issue` and `project` is set; both are null once the
+// favorited entity is gone.
+#[derive(Clone, SimpleObject)]
+#[graphql(complex, rename_fields = "camelCase")]
+struct Favorite {
+    id: String,
+    #[graphql(name = "type")]
+    r#type: String,
+    created_at: String,
+    #[graphql(skip)]
+    subject_id: String,
+}
+
+#[ComplexObject]
+impl Favorite {
+    async fn issue(&self, ctx: &Context<'_>) -> GqlResult<Option<Issue>> {
+        ensure_auth(ctx)?;
+        if self.r#type != SubjectType::Issue.name() {
+            return Ok(None);
+        }
+        let app = app_ctx(ctx);
+        get_issue(&*app.conn().await?, &self.subject_id)
+            .await
+            .map_err(gql_error)
+    }
+
+    async fn project(&self, ctx: &Context<'_>) -> GqlResult<Option<Project>> {
+        ensure_auth(ctx)?;
+        if self.r#type != SubjectType::Project.name() {
+            return Ok(None);
+        }
+        let app = app_ctx(ctx);
+        get_project(&*app.conn().await?, &self.subject_id)
+            .await
+            .map_err(gql_error)
+    }
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct FavoriteConnection {
+    nodes: Vec<Favorite>,
+    page_info: PageInfo,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct Attachment {
+    id: String,
+    title: String,
+    subtitle: Option<String>,
+    url: String,
+    metadata: Option<Json<serde_json::Value>>,
+    source_type: Option<String>,
+    created_at: String,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct AttachmentConnection {
+    nodes: Vec<Attachment>,
+    page_info: PageInfo,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct Reaction {
+    id: String,
+    emoji: String,
+    user: Option<User>,
+    created_at: String,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct ReactionGroup {
+    emoji: String,
+    count: i32,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct ReactionConnection {
+    nodes: Vec<Reaction>,
+    page_info: PageInfo,
+    /// Counts per emoji across every reaction on the subject, not just this page.
+    groups: Vec<ReactionGroup>,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct CommentConnection {
+    nodes: Vec<Comment>,
+    page_info: PageInfo,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct TeamCreatePayload {
+    success: bool,
+    team: Team,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct TeamUpdatePayload {
+    success: bool,
+    team: Team,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct ProjectCreatePayload {
+    success: bool,
+    project: Project,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct ProjectArchivePayload {
+    success: bool,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct ProjectUpdatePayload {
+    success: bool,
+    project: Project,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueCreatePayload {
+    success: bool,
+    issue: Issue,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct CommentCreatePayload {
+    success: bool,
+    comment: Comment,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueResolvePayload {
+    success: bool,
+    issue: Issue,
+    comment: Comment,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueUpdatePayload {
+    success: bool,
+    issue: Issue,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueBatchPayload {
+    success: bool,
+    issues: Vec<Issue>,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueArchivePayload {
+    success: bool,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct TeamMembershipPayload {
+    success: bool,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct TeamDeletePayload {
+    success: bool,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct ProjectDeletePayload {
+    success: bool,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct ProjectArchiveIssuesPayload {
+    success: bool,
+    archived_count: i64,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct UserReassignIssuesPayload {
+    success: bool,
+    reassigned_count: i64,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct LabelCreatePayload {
+    success: bool,
+    label: Label,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct ProjectMilestoneCreatePayload {
+    success: bool,
+    project_milestone: ProjectMilestone,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct CycleCreatePayload {
+    success: bool,
+    cycle: Cycle,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueAddLabelPayload {
+    success: bool,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueRemoveLabelPayload {
+    success: bool,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct AttachmentCreatePayload {
+    success: bool,
+    attachment: Attachment,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct ReactionCreatePayload {
+    success: bool,
+    reaction: Reaction,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct ReactionDeletePayload {
+    success: bool,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct FavoriteCreatePayload {
+    success: bool,
+    favorite: Favorite,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct FavoriteDeletePayload {
+    success: bool,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueSetLabelsPayload {
+    success: bool,
+    issue: Issue,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueSetCyclePayload {
+    success: bool,
+    issue: Issue,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct WorkflowStatePayload {
+    success: bool,
+    workflow_state: WorkflowState,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct AdminImportProjectPayload {
+    success: bool,
+    project: Project,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct AdminImportIssuePayload {
+    success: bool,
+    issue: Issue,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct AdminImportTeamPayload {
+    success: bool,
+    team: Team,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct AdminReindexPayload {
+    success: bool,
+    indexed_count: i32,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct AdminImportLabelsPayload {
+    success: bool,
+    created_count: i32,
+    updated_count: i32,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct AdminSnapshotPayload {
+    success: bool,
+    name: String,
+    path: String,
+}
+
+#[derive(Clone, SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+struct AdminQueryPayload {
+    success: bool,
+    columns: Vec<String>,
+    rows: Vec<Json<serde_json::Value>>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct StringFilter {
+    eq: Option<String>,
+    neq: Option<String>,
+    #[graphql(name = "in")]
+    in_values: Option<Vec<String>>,
+    // The pattern operators use SQLite `LIKE`, so they ignore ASCII case.
+    contains: Option<String>,
+    starts_with: Option<String>,
+    ends_with: Option<String>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct IdFilter {
+    eq: Option<String>,
+    neq: Option<String>,
+}
+
+// RFC 3339 bounds, compared against the stored UTC timestamps.
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct DateFilter {
+    gt: Option<String>,
+    gte: Option<String>,
+    lt: Option<String>,
+    lte: Option<String>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct FloatFilter {
+    #[graphql(name = "in")]
+    in_values: Option<Vec<f64>>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct TeamFilter {
+    id: Option<IdFilter>,
+    key: Option<StringFilter>,
+    name: Option<StringFilter>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct ProjectFilter {
+    id: Option<IdFilter>,
+    name: Option<StringFilter>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct ParentFilter {
+    id: Option<IdFilter>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct CycleFilter {
+    id: Option<IdFilter>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct StateFilter {
+    name: Option<StringFilter>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct AssigneeFilter {
+    id: Option<IdFilter>,
+    /// `true` matches unassigned issues, `false` matches assigned ones.
+    null: Option<bool>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct LabelFilter {
+    id: Option<IdFilter>,
+    name: Option<StringFilter>,
+    /// Matches issues carrying at least one label that satisfies every condition.
+    some: Option<LabelMatchFilter>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct LabelMatchFilter {
+    id: Option<IdFilter>,
+    name: Option<StringFilter>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct IssuesFilter {
+    team: Option<TeamFilter>,
+    project: Option<ProjectFilter>,
+    state: Option<StateFilter>,
+    assignee: Option<AssigneeFilter>,
+    number: Option<FloatFilter>,
+    labels: Option<LabelFilter>,
+    has_comments: Option<bool>,
+    has_attachments: Option<bool>,
+    created_at: Option<DateFilter>,
+    updated_at: Option<DateFilter>,
+    parent: Option<ParentFilter>,
+    cycle: Option<CycleFilter>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct TeamsFilter {
+    name: Option<StringFilter>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct ProjectsFilter {
+    name: Option<StringFilter>,
+    /// `true` lists only archived projects; archived projects are hidden otherwise.
+    archived: Option<bool>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct LabelsFilter {
+    name: Option<StringFilter>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct CyclesFilter {
+    team: Option<TeamFilter>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct WorkflowStatesFilter {
+    team: Option<TeamFilter>,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct LabelCreateInput {
+    name: String,
+    color: Option<String>,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct CycleCreateInput {
+    team_id: String,
+    name: Option<String>,
+    // RFC 3339; `endsAt` must be after `startsAt`.
+    starts_at: String,
+    ends_at: String,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct TeamCreateInput {
+    name: String,
+    key: Option<String>,
+    starting_number: Option<i32>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct TeamUpdateInput {
+    name: Option<String>,
+    key: Option<String>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct ProjectUpdateInput {
+    name: Option<String>,
+    state: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct ProjectCreateInput {
+    team_ids: Vec<String>,
+    name: String,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct ProjectMilestoneCreateInput {
+    project_id: String,
+    name: String,
+    // Defaults to after the project's last milestone.
+    sort_order: Option<f64>,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueCreateInput {
+    team_id: String,
+    project_id: Option<String>,
+    title: String,
+    description: Option<String>,
+    priority: Option<i32>,
+    estimate: Option<f64>,
+    due_date: Option<String>,
+    parent_id: Option<String>,
+    cycle_id: Option<String>,
+    // Must belong to `projectId`.
+    project_milestone_id: Option<String>,
+    // Omit to assign the `createAsUserId` user (if any); `null` leaves the
+    // issue unassigned.
+    assignee_id: MaybeUndefined<String>,
+    create_as_user_id: Option<String>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueUpdateInput {
+    title: Option<String>,
+    description: Option<String>,
+    state_id: Option<String>,
+    sort_order: Option<f64>,
+    priority: Option<i32>,
+    // Omit to leave unchanged; an explicit `null` clears the estimate.
+    estimate: MaybeUndefined<f64>,
+    // Omit to leave unchanged; an explicit `null` clears the due date.
+    due_date: MaybeUndefined<String>,
+    // Omit to leave unchanged; an explicit `null` unassigns the issue.
+    assignee_id: MaybeUndefined<String>,
+    // Omit to leave unchanged; an explicit `null` detaches it from its parent.
+    parent_id: MaybeUndefined<String>,
+    // Omit to leave unchanged; an explicit `null` removes it from its cycle.
+    cycle_id: MaybeUndefined<String>,
+    // Must belong to the issue's project; an explicit `null` clears it.
+    project_milestone_id: MaybeUndefined<String>,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct CommentCreateInput {
+    issue_id: String,
+    body: String,
+    create_as_user_id: Option<String>,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct AttachmentCreateInput {
+    issue_id: String,
+    title: String,
+    url: String,
+    subtitle: Option<String>,
+    // A JSON object, e.g. a linked PR's number and status.
+    metadata: Option<Json<serde_json::Value>>,
+    // Where the link comes from, e.g. "github" or "figma".
+    source_type: Option<String>,
+}
+
+// Exactly one of `issueId` and `commentId` names the subject.
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct ReactionCreateInput {
+    issue_id: Option<String>,
+    comment_id: Option<String>,
+    emoji: String,
+    create_as_user_id: Option<String>,
+}
+
+// Exactly one of `issueId` and `projectId` names the subject.
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct FavoriteCreateInput {
+    issue_id: Option<String>,
+    project_id: Option<String>,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct WorkflowStateCreateInput {
+    team_id: String,
+    name: String,
+    #[graphql(name = "type")]
+    r#type: String,
+    position: Option<i32>,
+    color: Option<String>,
+}
+
+#[derive(InputObject, Clone, Default)]
+#[graphql(rename_fields = "camelCase")]
+struct WorkflowStateUpdateInput {
+    name: Option<String>,
+    position: Option<i32>,
+    color: Option<String>,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct AdminImportProjectInput {
+    id: String,
+    name: String,
+    slug_id: String,
+    state: Option<String>,
+    archived_at: Option<String>,
+    url: String,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct AdminImportIssueInput {
+    id: String,
+    team_id: String,
+    number: i32,
+    identifier: String,
+    title: String,
+    description: Option<String>,
+    state_id: String,
+    assignee_id: Option<String>,
+    project_id: Option<String>,
+    archived: Option<bool>,
+    url: String,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    completed_at: Option<String>,
+    archived_at: Option<String>,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct AdminImportTeamInput {
+    id: String,
+    name: String,
+    key: String,
+    starting_number: Option<i32>,
+}
+
+#[derive(InputObject, Clone)]
+#[graphql(rename_fields = "camelCase")]
+struct AdminImportLabelInput {
+    id: String,
+    name: String,
+    color: Option<String>,
+    parent_id: Option<String>,
+    team_id: Option<String>,
+}
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq)]
+enum IssueOrderBy {
+    #[graphql(name = "updatedAt")]
+    UpdatedAt,
+    #[graphql(name = "createdAt")]
+    CreatedAt,
+    #[graphql(name = "priority")]
+    Priority,
+    #[graphql(name = "number")]
+    Number,
+}
+
+impl IssueOrderBy {
+    fn sql(self) -> &'static str {
+        match self {
+            IssueOrderBy::UpdatedAt => "i.updated_at",
+            IssueOrderBy::CreatedAt => "i.created_at",
+            IssueOrderBy::Priority => PRIORITY_RANK_SQL,
+            IssueOrderBy::Number => "i.number",
+        }
+    }
+
+    fn default_direction(self) -> OrderDirection {
+        match self {
+            IssueOrderBy::Priority => OrderDirection::Asc,
+            _ => OrderDirection::Desc,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            IssueOrderBy::UpdatedAt => "updatedAt",
+            IssueOrderBy::CreatedAt => "createdAt",
+            IssueOrderBy::Priority => "priority",
+            IssueOrderBy::Number => "number",
+        }
+    }
+
+    // Cursor values are bound back with the column's type so comparisons
+    // against integer expressions don't fall back to text ordering.
+    fn cursor_param(self, value: &str) -> Option<Value> {
+        match self {
+            IssueOrderBy::UpdatedAt | IssueOrderBy::CreatedAt => {
+                Some(Value::Text(value.to_string()))
+            }
+            IssueOrderBy::Priority | IssueOrderBy::Number => {
+                value.parse::<i64>().ok().map(Value::Integer)
+            }
+        }
+    }
+
+    fn cursor_value(self, issue: &Issue) -> String {
+        match self {
+            IssueOrderBy::UpdatedAt => issue.updated_at.clone().unwrap_or_default(),
+            IssueOrderBy::CreatedAt => issue.created_at.clone().unwrap_or_default(),
+            IssueOrderBy::Priority => priority_rank(issue.priority).to_string(),
+            IssueOrderBy::Number => issue.number.to_string(),
+        }
+    }
+}
+
+#[derive(InputObject, Clone, Copy)]
+#[graphql(rename_fields = "camelCase")]
+struct IssueSort {
+    order_by: IssueOrderBy,
+    direction: Option<OrderDirection>,
+}
+
+// The requested sort keys, followed by `updatedAt DESC` (unless already
+// present) and `id DESC` so every issue has a distinct position.
+#[derive(Clone)]
+struct IssueOrder(Vec<(IssueOrderBy, OrderDirection)>);
+
+impl Default for IssueOrder {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl IssueOrder {
+    fn new(sort: &[IssueSort]) -> Self {
+        let mut keys: Vec<(IssueOrderBy, OrderDirection)> = Vec::new();
+        for s in sort {
+            if keys.iter().all(|(k, _)| *k != s.order_by) {
+                let direction = s.direction.unwrap_or(s.order_by.default_direction());
+                keys.push((s.order_by, direction));
+            }
+        }
+        if keys.iter().all(|(k, _)| *k != IssueOrderBy::UpdatedAt) {
+            keys.push((IssueOrderBy::UpdatedAt, OrderDirection::Desc));
+        }
+        Self(keys)
+    }
+
+    // `sort` wins over the single-key `orderBy` / `orderDirection` arguments.
+    fn from_args(
+        order_by: Option<IssueOrderBy>,
+        order_direction: Option<OrderDirection>,
+        sort: Option<Vec<IssueSort>>,
+    ) -> Self {
+        match sort.filter(|s| !s.is_empty()) {
+            Some(sort) => Self::new(&sort),
+            None => Self::new(&[IssueSort {
+                order_by: order_by.unwrap_or(IssueOrderBy::UpdatedAt),
+                direction: order_direction,
+            }]),
+        }
+    }
+
+    fn scope(&self) -> String {
+        let keys: Vec<String> = self
+            .0
+            .iter()
+            .map(|(k, d)| format!("{}.{}", k.name(), d.sql()))
+            .collect();
+        format!("issues:{}", keys.join(","))
+    }
+
+    fn sql(&self) -> String {
+        let mut terms: Vec<String> = self
+            .0
+            .iter()
+            .map(|(k, d)| format!("{} {}", k.sql(), d.sql()))
+            .collect();
+        terms.push("i.id DESC".to_string());
+        terms.join(", ")
+    }
+
+    fn cursor_key(&self, issue: &Issue) -> Vec<String> {
+        let mut key: Vec<String> = self.0.iter().map(|(k, _)| k.cursor_value(issue)).collect();
+        key.push(issue.id.clone());
+        key
+    }
+
+    // Keyset predicate selecting rows strictly after `values` in this order.
+    fn after_sql(
+        &self,
+        query: &mut QueryBuilder,
+        cursor: &str,
+        values: Vec<String>,
+    ) -> Result<String> {
+        let invalid = || ValidationError(format!("invalid cursor: {cursor}"));
+        if values.len() != self.0.len() + 1 {
+            return Err(invalid().into());
+        }
+        let mut values = values.into_iter().rev();
+        let id = query.bind(values.next().ok_or_else(invalid)?);
+        let mut clause = format!("i.id < {id}");
+        for ((key, direction), value) in self.0.iter().rev().zip(values) {
+            let value = query.bind(key.cursor_param(&value).ok_or_else(invalid)?);
+            let expr = key.sql();
+            let op = match direction {
+                OrderDirection::Asc => ">",
+                OrderDirection::Desc => "<",
+            };
+            clause = format!("({expr} {op} {value} OR ({expr} = {value} AND {clause}))");
+        }
+        Ok(clause)
+    }
+}
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq)]
+enum ProjectOrderBy {
+    #[graphql(name = "createdAt")]
+    CreatedAt,
+    #[graphql(name = "name")]
+    Name,
+    #[graphql(name = "sortOrder")]
+    SortOrder,
+}
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq)]
+enum CycleDirection {
+    Forward,
+    Backward,
+}
+
+#[derive(Enum, Clone, Copy, Eq, PartialEq)]
+enum OrderDirection {
+    Asc,
+    Desc,
+}
+
+impl OrderDirection {
+    fn sql(self) -> &'static str {
+        match self {
+            OrderDirection::Asc => "ASC",
+            OrderDirection::Desc => "DESC",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UserRow {
+    id: String,
+    name: String,
+    email: String,
+}
+
+impl From<UserRow> for User {
+    fn from(v: UserRow) -> Self {
+        Self {
+            id: v.id,
+            name: v.name,
+            email: v.email,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TeamRow {
+    id: String,
+    name: String,
+    key: String,
+}
+
+impl From<TeamRow> for Team {
+    fn from(v: TeamRow) -> Self {
+        Self {
+            id: v.id,
+            name: v.name,
+            key: v.key,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ProjectRow {
+    id: String,
+    name: String,
+    slug_id: Option<String>,
+    state: Option<String>,
+    description: Option<String>,
+    archived_at: Option<String>,
+    url: Option<String>,
+    sort_order: f64,
+    created_at: String,
+}
+
+impl From<ProjectRow> for Project {
+    fn from(v: ProjectRow) -> Self {
+        Self {
+            id: v.id,
+            name: v.name,
+            slug_id: v.slug_id,
+            state: v.state,
+            description: v.description,
+            archived_at: v.archived_at,
+            url: v.url,
+            sort_order: v.sort_order,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct WorkflowStateRow {
+    id: String,
+    name: String,
+    state_type: Option<String>,
+    position: i64,
+    color: Option<String>,
+}
+
+impl From<WorkflowStateRow> for WorkflowState {
+    fn from(v: WorkflowStateRow) -> Self {
+        Self {
+            id: v.id,
+            name: v.name,
+            r#type: v.state_type,
+            position: v.position as i32,
+            color: v.color,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ProjectMilestoneRow {
+    id: String,
+    name: String,
+    sort_order: f64,
+}
+
+impl From<ProjectMilestoneRow> for ProjectMilestone {
+    fn from(row: ProjectMilestoneRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            sort_order: row.sort_order,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CycleRow {
+    id: String,
+    number: i64,
+    name: Option<String>,
+    starts_at: String,
+    ends_at: String,
+    created_at: String,
+}
+
+impl From<CycleRow> for Cycle {
+    fn from(row: CycleRow) -> Self {
+        Self {
+            id: row.id,
+            number: row.number as i32,
+            name: row.name,
+            starts_at: row.starts_at,
+            ends_at: row.ends_at,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct IdRow {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct LabelRow {
+    id: String,
+    name: String,
+    color: Option<String>,
+}
+
+impl From<LabelRow> for Label {
+    fn from(row: LabelRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            color: row.color,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct IssueBaseRow {
+    id: String,
+    identifier: String,
+    number: i64,
+    parent_id: Option<String>,
+    title: String,
+    url: String,
+    description: Option<String>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    completed_at: Option<String>,
+    archived: i64,
+    archived_at: Option<String>,
+    priority: i64,
+    estimate: Option<f64>,
+    due_date: Option<String>,
+    sort_order: f64,
+    state_column_rank: Option<i64>,
+    ws_id: Option<String>,
+    ws_name: Option<String>,
+    ws_type: Option<String>,
+    ws_position: Option<i64>,
+    ws_color: Option<String>,
+    p_id: Option<String>,
+    p_name: Option<String>,
+    p_slug_id: Option<String>,
+    p_state: Option<String>,
+    p_description: Option<String>,
+    p_archived_at: Option<String>,
+    p_url: Option<String>,
+    p_sort_order: Option<f64>,
+    pm_id: Option<String>,
+    pm_name: Option<String>,
+    pm_sort_order: Option<f64>,
+    c_id: Option<String>,
+    c_number: Option<i64>,
+    c_name: Option<String>,
+    c_starts_at: Option<String>,
+    c_ends_at: Option<String>,
+    c_created_at: Option<String>,
+    u_id: Option<String>,
+    u_name: Option<String>,
+    u_email: Option<String>,
+    cu_id: Option<String>,
+    cu_name: Option<String>,
+    cu_email: Option<String>,
+    t_id: String,
+    t_name: Option<String>,
+    t_key: Option<String>,
+}
+
+async fn health_summary(conn: &Connection) -> HealthSummary {
+    let started = Instant::now();
+    let probe = async {
+        count(conn, "SELECT 1 as value", vec![]).await?;
+        let db_latency_ms = started.elapsed().as_millis() as i32;
+        let issue_count = count(conn, "SELECT COUNT(*) as value FROM issues", vec![]).await?;
+        let team_count = count(conn, "SELECT COUNT(*) as value FROM teams", vec![]).await?;
+        Ok::<_, anyhow::Error>((db_latency_ms, issue_count as i32, team_count as i32))
+    };
+    match probe.await {
+        Ok((db_latency_ms, issue_count, team_count)) => HealthSummary {
+            ok: true,
+            db_latency_ms: Some(db_latency_ms),
+            issue_count: Some(issue_count),
+            team_count: Some(team_count),
+            error: None,
+        },
+        Err(err) => HealthSummary {
+            ok: false,
+            db_latency_ms: None,
+            issue_count: None,
+            team_count: None,
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+#[derive(Deserialize)]
+struct OrganizationRow {
+    id: String,
+    name: String,
+    url_key: String,
+    created_at: String,
+}
+
+async fn get_organization(conn: &Connection) -> Result<Organization> {
+    let row: OrganizationRow = fetch_one(
+        conn,
+        "SELECT id, name, url_key, created_at FROM organization ORDER BY created_at ASC LIMIT 1",
+        vec![],
+    )
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("no organization configured"))?;
+    Ok(Organization {
+        id: row.id,
+        name: row.name,
+        url_key: row.url_key,
+        created_at: row.created_at,
+    })
+}
+
+async fn get_viewer(conn: &Connection) -> Result<Viewer> {
+    let row: UserRow = fetch_one(
+        conn,
+        "SELECT id, name, email FROM users ORDER BY created_at ASC LIMIT 1",
+        vec![],
+    )
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("no viewer configured"))?;
+    Ok(Viewer {
+        id: row.id,
+        name: row.name,
+        email: row.email,
+    })
+}
+
+async fn list_teams(
+    conn: &Connection,
+    max_page_size: i32,
+    filter: Option<TeamsFilter>,
+    first: Option<i32>,
+    after: Option<String>,
+) -> Result<TeamConnection> {
+    let limit = clamp_limit(first, max_page_size);
+    let mut query = QueryBuilder::default();
+    if let Some(mut name) = filter.and_then(|f| f.name) {
+        name.eq = name.eq.filter(|v| !v.is_empty());
+        query.push_string_filter("name", &name);
+    }
+    let total = query.total_count("teams");
+    let has_previous_page = after.is_some();
+    if let Some(cursor) = after {
+        let [name, id] = decode_cursor(&cursor, TEAM_CURSOR_SCOPE)?;
+        let name = query.bind(name);
+        let id = query.bind(id);
+        query.push(format!("(name > {name} OR (name = {name} AND id > {id}))"));
+    }
+    let limit_param = query.bind(i64::from(limit + 1));
+    let sql = format!(
+        "SELECT id, name, key FROM teams{} ORDER BY name ASC, id ASC LIMIT {}",
+        query.where_sql(),
+        limit_param
+    );
+    let rows: Vec<TeamRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    Ok(team_page(rows, limit as usize, has_previous_page, total))
+}
+
+fn team_page(
+    rows: Vec<TeamRow>,
+    limit: usize,
+    has_previous_page: bool,
+    total: TotalCount,
+) -> TeamConnection {
+    let nodes: Vec<Team> = rows.into_iter().map(Team::from).collect();
+    let (nodes, page_info) = paginate(nodes, limit, has_previous_page, TEAM_CURSOR_SCOPE, |t| {
+        vec![t.name.clone(), t.id.clone()]
+    });
+    TeamConnection {
+        nodes,
+        page_info,
+        total,
+    }
+}
+
+async fn list_user_teams(
+    conn: &Connection,
+    max_page_size: i32,
+    user_id: &str,
+    first: Option<i32>,
+    after: Option<String>,
+) -> Result<TeamConnection> {
+    let limit = clamp_limit(first, max_page_size);
+    let mut query = QueryBuilder::default();
+    let user_id_param = query.bind(user_id.to_string());
+    query.push(format!("tm.user_id = {user_id_param}"));
+    let has_previous_page = after.is_some();
+    if let Some(cursor) = after {
+        let [name, id] = decode_cursor(&cursor, TEAM_CURSOR_SCOPE)?;
+        let name = query.bind(name);
+        let id = query.bind(id);
+        query.push(format!(
+            "(t.name > {name} OR (t.name = {name} AND t.id > {id}))"
+        ));
+    }
+    let limit_param = query.bind(i64::from(limit + 1));
+    let sql = format!(
+        "SELECT t.id, t.name, t.key
+         FROM teams t
+         INNER JOIN team_members tm ON tm.team_id = t.id{}
+         ORDER BY t.name ASC, t.id ASC
+         LIMIT {}",
+        query.where_sql(),
+        limit_param
+    );
+    let rows: Vec<TeamRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    let total = TotalCount::Query {
+        sql: "SELECT COUNT(*) as value FROM team_members WHERE user_id = ?1".to_string(),
+        params: vec![user_id.to_string().into()],
+    };
+    Ok(team_page(rows, limit as usize, has_previous_page, total))
+}
+
+async fn get_team(conn: &Connection, id: &str) -> Result<Option<Team>> {
+    let row: Option<TeamRow> = fetch_one(
+        conn,
+        "SELECT id, name, key FROM teams WHERE id = ?1",
+        vec![id.to_string().into()],
+    )
+    .await?;
+    Ok(row.map(Team::from))
+}
+
+async fn get_team_by_key(conn: &Connection, key: &str) -> Result<Option<Team>> {
+    let row: Option<TeamRow> = fetch_one(
+        conn,
+        "SELECT id, name, key FROM teams WHERE key = ?1",
+        vec![key.trim().to_ascii_uppercase().into()],
+    )
+    .await?;
+    Ok(row.map(Team::from))
+}
+
+async fn create_team(conn: &Connection, input: TeamCreateInput) -> Result<TeamCreatePayload> {
+    let starting_number = input.starting_number.unwrap_or(1);
+    if starting_number < 1 {
+        return Err(ValidationError("startingNumber must be at least 1".to_string()).into());
+    }
+    let key = match input.key {
+        Some(raw) => {
+            let key = sanitize_team_key(&raw);
+            if team_key_taken(conn, &key).await? {
+                return Err(ValidationError(format!("team key already in use: {key}")).into());
+            }
+            key
+        }
+        None => {
+            // Derived keys take the first three characters of the name and get
+            // a numeric suffix until they're unique.
+            let base: String = sanitize_team_key(&input.name).chars().take(3).collect();
+            let mut key = base.clone();
+            let mut suffix = 2;
+            while team_key_taken(conn, &key).await? {
+                key = format!("{base}{suffix}");
+                suffix += 1;
+            }
+            key
+        }
+    };
+
+    let team_id = format!("team_{}", short_id());
+    let viewer = get_viewer(conn).await?;
+    let tx = conn.transaction().await?;
+    tx.execute(
+        "INSERT INTO teams (id, name, key, starting_number, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        vals(vec![
+            team_id.clone().into(),
+            input.name.into(),
+            key.into(),
+            i64::from(starting_number).into(),
+            now_iso().into(),
+        ]),
+    )
+    .await?;
+    tx.execute(
+        "INSERT OR IGNORE INTO team_members (team_id, user_id) VALUES (?1, ?2)",
+        vals(vec![team_id.clone().into(), viewer.id.into()]),
+    )
+    .await?;
+    for (name, kind, position, color) in DEFAULT_WORKFLOW_STATES {
+        ensure_workflow_state(&tx, &team_id, name, kind, position, color).await?;
+    }
+    tx.commit().await?;
+
+    let team = get_team(conn, &team_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("failed to load created team"))?;
+    Ok(TeamCreatePayload {
+        success: true,
+        team,
+    })
+}
+
+// Archived issues go with the team, since their workflow states do too.
+async fn delete_team(
+    conn: &Connection,
+    retries: u32,
+    team_id: &str,
+    delete_archived_issues: bool,
+) -> Result<TeamDeletePayload> {
+    if get_team(conn, team_id).await?.is_none() {
+        return Err(NotFoundError(format!("team not found: {team_id}")).into());
+    }
+    let open_issues = count(
+        conn,
+        "SELECT COUNT(*) as value FROM issues WHERE team_id = ?1 AND archived = 0",
+        vec![team_id.to_string().into()],
+    )
+    .await?;
+    if open_issues > 0 {
+        return Err(ValidationError(format!(
+            "team {team_id} still has {open_issues} non-archived issues"
+        ))
+        .into());
+    }
+    let archived_issues = count(
+        conn,
+        "SELECT COUNT(*) as value FROM issues WHERE team_id = ?1",
+        vec![team_id.to_string().into()],
+    )
+    .await?;
+    if archived_issues > 0 && !delete_archived_issues {
+        return Err(ValidationError(format!(
+            "team {team_id} still has {archived_issues} archived issues; pass deleteArchivedIssues: true to delete them"
+        ))
+        .into());
+    }
+
+    retry_busy(retries, || async move {
+        let tx = conn.transaction().await?;
+        // With no issues left the issue statements are no-ops.
+        let statements = [
+            "DELETE FROM issue_labels WHERE issue_id IN (SELECT id FROM issues WHERE team_id = ?1)",
+            "DELETE FROM reactions WHERE subject_type = 'comment' AND subject_id IN (SELECT c.id FROM comments c INNER JOIN issues i ON i.id = c.issue_id WHERE i.team_id = ?1)",
+            "DELETE FROM reactions WHERE subject_type = 'issue' AND subject_id IN (SELECT id FROM issues WHERE team_id = ?1)",
+            "DELETE FROM favorites WHERE subject_type = 'issue' AND subject_id IN (SELECT id FROM issues WHERE team_id = ?1)",
+            "DELETE FROM comments WHERE issue_id IN (SELECT id FROM issues WHERE team_id = ?1)",
+            "DELETE FROM attachments WHERE issue_id IN (SELECT id FROM issues WHERE team_id = ?1)",
+            "DELETE FROM issues WHERE team_id = ?1",
+            "DELETE FROM team_members WHERE team_id = ?1",
+            "DELETE FROM workflow_states WHERE team_id = ?1",
+            "DELETE FROM project_teams WHERE team_id = ?1",
+            "DELETE FROM cycles WHERE team_id = ?1",
+            "UPDATE labels SET team_id = NULL WHERE team_id = ?1",
+            "DELETE FROM teams WHERE id = ?1",
+        ];
+        for sql in statements {
+            tx.execute(sql, vals(vec![team_id.to_string().into()]))
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    })
+    .await?;
+
+    Ok(TeamDeletePayload { success: true })
+}
+
+async fn ensure_team_and_user(conn: &Connection, team_id: &str, user_id: &str) -> Result<()> {
+    if get_team(conn, team_id).await?.is_none() {
+        return Err(NotFoundError(format!("team not found: {team_id}")).into());
+    }
+    if get_user(conn, user_id).await?.is_none() {
+        return Err(NotFoundError(format!("user not found: {user_id}")).into());
+    }
+    Ok(())
+}
+
+async fn create_team_membership(
+    conn: &Connection,
+    team_id: &str,
+    user_id: &str,
+) -> Result<TeamMembershipPayload> {
+    ensure_team_and_user(conn, team_id, user_id).await?;
+    conn.execute(
+        "INSERT OR IGNORE INTO team_members (team_id, user_id) VALUES (?1, ?2)",
+        vals(vec![team_id.to_string().into(), user_id.to_string().into()]),
+    )
+    .await?;
+    Ok(TeamMembershipPayload { success: true })
+}
+
+async fn delete_team_membership(
+    conn: &Connection,
+    team_id: &str,
+    user_id: &str,
+) -> Result<TeamMembershipPayload> {
+    ensure_team_and_user(conn, team_id, user_id).await?;
+    let removed = conn
+        .execute(
+            "DELETE FROM team_members WHERE team_id = ?1 AND user_id = ?2",
+            vals(vec![team_id.to_string().into(), user_id.to_string().into()]),
+        )
+        .await?;
+    Ok(TeamMembershipPayload {
+        success: removed > 0,
+    })
+}
+
+const USER_CURSOR_SCOPE: &str = "users:name";
+
+async fn list_team_members(
+    conn: &Connection,
+    max_page_size: i32,
+    team_id: &str,
+    first: Option<i32>,
+    after: Option<String>,
+) -> Result<UserConnection> {
+    let limit = clamp_limit(first, max_page_size);
+    let mut query = QueryBuilder::default();
+    let team_id = query.bind(team_id.to_string());
+    query.push(format!("tm.team_id = {team_id}"));
+    let has_previous_page = after.is_some();
+    if let Some(cursor) = after {
+        let [name, id] = decode_cursor(&cursor, USER_CURSOR_SCOPE)?;
+        let name = query.bind(name);
+        let id = query.bind(id);
+        query.push(format!(
+            "(u.name > {name} OR (u.name = {name} AND u.id > {id}))"
+        ));
+    }
+    let limit_param = query.bind(i64::from(limit + 1));
+    let sql = format!(
+        "SELECT u.id, u.name, u.email
+         FROM users u
+         INNER JOIN team_members tm ON tm.user_id = u.id{}
+         ORDER BY u.name ASC, u.id ASC
+         LIMIT {}",
+        query.where_sql(),
+        limit_param
+    );
+    let rows: Vec<UserRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    let nodes: Vec<User> = rows.into_iter().map(User::from).collect();
+    let (nodes, page_info) = paginate(
+        nodes,
+        limit as usize,
+        has_previous_page,
+        USER_CURSOR_SCOPE,
+        |u| vec![u.name.clone(), u.id.clone()],
+    );
+    Ok(UserConnection { nodes, page_info })
+}
+
+async fn team_key_taken(conn: &Connection, key: &str) -> Result<bool> {
+    Ok(count(
+        conn,
+        "SELECT COUNT(*) as value FROM teams WHERE key = ?1",
+        vec![key.to_string().into()],
+    )
+    .await?
+        > 0)
+}
+
+async fn update_team(
+    conn: &Connection,
+    retries: u32,
+    base_url: &str,
+    team_id: &str,
+    input: TeamUpdateInput,
+) -> Result<TeamUpdatePayload> {
+    let current: TeamRow = fetch_one(
+        conn,
+        "SELECT id, name, key FROM teams WHERE id = ?1",
+        vec![team_id.to_string().into()],
+    )
+    .await?
+    .ok_or_else(|| NotFoundError(format!("team not found: {team_id}")))?;
+
+    let name = input.name.unwrap_or(current.name);
+    let key = match input.key {
+        Some(raw) => sanitize_team_key(&raw),
+        None => current.key.clone(),
+    };
+    if key != current.key {
+        let key_taken = count(
+            conn,
+            "SELECT COUNT(*) as value FROM teams WHERE key = ?1 AND id <> ?2",
+            vec![key.clone().into(), team_id.to_string().into()],
+        )
+        .await?;
+        if key_taken > 0 {
+            return Err(ValidationError(format!("team key already in use: {key}")).into());
+        }
+    }
+
+    let link_base = &link_base(conn, base_url).await?;
+    let (name, key, current_key) = (&name, &key, &current.key);
+    retry_busy(retries, || async move {
+        let tx = conn.transaction().await?;
+        tx.execute(
+            "UPDATE teams SET name = ?1, key = ?2 WHERE id = ?3",
+            vals(vec![
+                name.clone().into(),
+                key.clone().into(),
+                team_id.to_string().into(),
+            ]),
+        )
+        .await?;
+        if key != current_key {
+            tx.execute(
+                "UPDATE issues
+                 SET identifier = ?1 || '-' || number,
+                     url = ?2 || '/issue/' || ?1 || '-' || number,
+                     updated_at = ?3
+                 WHERE team_id = ?4",
+                vals(vec![
+                    key.clone().into(),
+                    link_base.clone().into(),
+                    now_iso().into(),
+                    team_id.to_string().into(),
+                ]),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    })
+    .await?;
+
+    let team = get_team(conn, team_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("failed to load updated team"))?;
+    Ok(TeamUpdatePayload {
+        success: true,
+        team,
+    })
+}
+
+// `team_id` limits the list to projects linked to that team via `project_teams`.
+#[allow(clippy::too_many_arguments)]
+async fn list_projects(
+    conn: &Connection,
+    max_page_size: i32,
+    team_id: Option<&str>,
+    filter: Option<ProjectsFilter>,
+    first: Option<i32>,
+    after: Option<String>,
+    order_by: Option<ProjectOrderBy>,
+    direction: Option<OrderDirection>,
+) -> Result<ProjectConnection> {
+    let limit = clamp_limit(first, max_page_size);
+    let order_by = order_by.unwrap_or(ProjectOrderBy::CreatedAt);
+    let (column, direction) = project_order(order_by, direction);
+    let cursor_scope = format!("projects:{column}:{}", direction.sql());
+    let mut query = QueryBuilder::default();
+    let filter = filter.unwrap_or_default();
+    if let Some(mut name) = filter.name {
+        name.eq = name.eq.filter(|v| !v.is_empty());
+        query.push_string_filter("name", &name);
+    }
+    query.push(if filter.archived.unwrap_or(false) {
+        "archived_at IS NOT NULL"
+    } else {
+        "archived_at IS NULL"
+    });
+    if let Some(team_id) = team_id {
+        let team_id = query.bind(team_id.to_string());
+        query.push(format!(
+            "id IN (SELECT project_id FROM project_teams WHERE team_id = {team_id})"
+        ));
+    }
+    let total = query.total_count("projects");
+    let has_previous_page = after.is_some();
+    if let Some(cursor) = after {
+        let [key, id] = decode_cursor(&cursor, &cursor_scope)?;
+        let op = match direction {
+            OrderDirection::Asc => ">",
+            OrderDirection::Desc => "<",
+        };
+        let key = query.bind(key);
+        let id = query.bind(id);
+        query.push(format!(
+            "({column} {op} {key} OR ({column} = {key} AND id {op} {id}))"
+        ));
+    }
+    let limit_param = query.bind(i64::from(limit + 1));
+    let sql = format!(
+        "SELECT id, name, slug_id, state, description, archived_at, url, sort_order, created_at FROM projects{} ORDER BY {column} {dir}, id {dir} LIMIT {limit_param}",
+        query.where_sql(),
+        dir = direction.sql()
+    );
+    let rows: Vec<ProjectRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    let (rows, page_info) = paginate(
+        rows,
+        limit as usize,
+        has_previous_page,
+        &cursor_scope,
+        |p| {
+            let key = match order_by {
+                ProjectOrderBy::CreatedAt => p.created_at.clone(),
+                ProjectOrderBy::Name => p.name.clone(),
+                ProjectOrderBy::SortOrder => p.sort_order.to_string(),
+            };
+            vec![key, p.id.clone()]
+        },
+    );
+    Ok(ProjectConnection {
+        nodes: rows.into_iter().map(Project::from).collect(),
+        page_info,
+        total,
+    })
+}
+
+fn project_order(
+    order_by: ProjectOrderBy,
+    direction: Option<OrderDirection>,
+) -> (&'static str, OrderDirection) {
+    let (column, default_direction) = match order_by {
+        ProjectOrderBy::CreatedAt => ("created_at", OrderDirection::Desc),
+        ProjectOrderBy::Name => ("name", OrderDirection::Asc),
+        ProjectOrderBy::SortOrder => ("sort_order", OrderDirection::Asc),
+    };
+    (column, direction.unwrap_or(default_direction))
+}
+
+async fn get_project(conn: &Connection, id: &str) -> Result<Option<Project>> {
+    let row: Option<ProjectRow> = fetch_one(
+        conn,
+        "SELECT id, name, slug_id, state, description, archived_at, url, sort_order, created_at FROM projects WHERE id = ?1",
+        vec![id.to_string().into()],
+    )
+    .await?;
+    Ok(row.map(Project::from))
+}
+
+async fn get_issue(conn: &Connection, id: &str) -> Result<Option<Issue>> {
+    let sql = format!("{} WHERE i.id = ?1", issue_base_select());
+    let row: Option<IssueBaseRow> = fetch_one(conn, &sql, vec![id.to_string().into()]).await?;
+    match row {
+        Some(v) => Ok(issues_from_rows(conn, vec![v]).await?.pop()),
+        None => Ok(None),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn list_issues(
+    conn: &Connection,
+    max_page_size: i32,
+    filter: Option<IssuesFilter>,
+    first: Option<i32>,
+    after: Option<String>,
+    order: IssueOrder,
+    active: bool,
+    include_archived: bool,
+) -> Result<IssueConnection> {
+    let limit = clamp_limit(first, max_page_size);
+    let mut query = QueryBuilder::default();
+    if !include_archived {
+        query.push("i.archived = 0");
+    }
+
+    if active {
+        query.push("ws.type IN ('started', 'unstarted')");
+    }
+
+    if let Some(filter) = filter {
+        push_issue_filters(&mut query, filter)?;
+    }
+
+    let total = query.total_count(ISSUE_COUNT_FROM);
+    let estimates = query.issue_estimates(ISSUE_COUNT_FROM);
+    let has_previous_page = after.is_some();
+    if let Some(cursor) = after {
+        let values = decode_cursor_values(&cursor, &order.scope())?;
+        let after_sql = order.after_sql(&mut query, &cursor, values)?;
+        query.push(after_sql);
+    }
+
+    let where_sql = query.where_sql();
+    let order_sql = order.sql();
+    let limit_param = query.bind(i64::from(limit + 1));
+    let sql = format!(
+        "{}{} ORDER BY {} LIMIT {}",
+        issue_base_select(),
+        where_sql,
+        order_sql,
+        limit_param
+    );
+    let mut rows: Vec<IssueBaseRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    rank_in_state_columns(conn, &mut rows).await?;
+    let issues = issues_from_rows(conn, rows).await?;
+    Ok(issue_page(
+        issues,
+        limit as usize,
+        has_previous_page,
+        &order,
+        total,
+        estimates,
+    ))
+}
+
+// Appends the WHERE clauses for `filter`; every clause reads `issues i`,
+// `workflow_states ws` or `teams t`, as joined by ISSUE_COUNT_FROM.
+fn push_issue_filters(query: &mut QueryBuilder, filter: IssuesFilter) -> Result<()> {
+    if let Some(team_id) = filter
+        .team
+        .as_ref()
+        .and_then(|t| t.id.as_ref())
+        .and_then(|v| v.eq.clone())
+    {
+        let team_id = query.bind(team_id);
+        query.push(format!("i.team_id = {team_id}"));
+    }
+    if let Some(team_key) = filter
+        .team
+        .as_ref()
+        .and_then(|t| t.key.as_ref())
+        .and_then(|k| k.eq.clone())
+    {
+        let team_key = query.bind(team_key);
+        query.push(format!("t.key = {team_key}"));
+    }
+    if let Some(project_id) = filter
+        .project
+        .as_ref()
+        .and_then(|p| p.id.as_ref())
+        .and_then(|v| v.eq.clone())
+    {
+        let project_id = query.bind(project_id);
+        query.push(format!("i.project_id = {project_id}"));
+    }
+    if let Some(project_id) = filter
+        .project
+        .as_ref()
+        .and_then(|p| p.id.as_ref())
+        .and_then(|v| v.neq.clone())
+    {
+        // Issues without a project must still match a `neq`.
+        let project_id = query.bind(project_id);
+        query.push(format!(
+            "(i.project_id <> {project_id} OR i.project_id IS NULL)"
+        ));
+    }
+    if let Some(assignee_id) = filter
+        .assignee
+        .as_ref()
+        .and_then(|a| a.id.as_ref())
+        .and_then(|v| v.eq.clone())
+    {
+        let assignee_id = query.bind(assignee_id);
+        query.push(format!("i.assignee_id = {assignee_id}"));
+    }
+    if let Some(assignee_id) = filter
+        .assignee
+        .as_ref()
+        .and_then(|a| a.id.as_ref())
+        .and_then(|v| v.neq.clone())
+    {
+        // Unassigned issues must still match a `neq`.
+        let assignee_id = query.bind(assignee_id);
+        query.push(format!(
+            "(i.assignee_id <> {assignee_id} OR i.assignee_id IS NULL)"
+        ));
+    }
+    if let Some(is_null) = filter.assignee.as_ref().and_then(|a| a.null) {
+        query.push(if is_null {
+            "i.assignee_id IS NULL"
+        } else {
+            "i.assignee_id IS NOT NULL"
+        });
+    }
+    if let Some(state_name) = filter.state.as_ref().and_then(|s| s.name.as_ref()) {
+        query.push_string_filter("ws.name", state_name);
+    }
+    if let Some(label_name_eq) = filter
+        .labels
+        .as_ref()
+        .and_then(|l| l.name.as_ref())
+        .and_then(|n| n.eq.clone())
+    {
+        let name = query.bind(label_name_eq);
+        query.push(format!("EXISTS ({})", issue_label_name_match(&name)));
+    }
+    if let Some(label_name_neq) = filter
+        .labels
+        .as_ref()
+        .and_then(|l| l.name.as_ref())
+        .and_then(|n| n.neq.clone())
+    {
+        let name = query.bind(label_name_neq);
+        query.push(format!("NOT EXISTS ({})", issue_label_name_match(&name)));
+    }
+    if let Some(label_id_eq) = filter
+        .labels
+        .as_ref()
+        .and_then(|l| l.id.as_ref())
+        .and_then(|v| v.eq.clone())
+    {
+        let id = query.bind(label_id_eq);
+        query.push(format!("EXISTS ({})", issue_label_id_match(&id)));
+    }
+    if let Some(label_id_neq) = filter
+        .labels
+        .as_ref()
+        .and_then(|l| l.id.as_ref())
+        .and_then(|v| v.neq.clone())
+    {
+        let id = query.bind(label_id_neq);
+        query.push(format!("NOT EXISTS ({})", issue_label_id_match(&id)));
+    }
+    if let Some(some) = filter.labels.as_ref().and_then(|l| l.some.as_ref()) {
+        let mut label_clauses = Vec::new();
+        let conditions = [
+            ("l.id =", some.id.as_ref().and_then(|v| v.eq.clone())),
+            ("l.id <>", some.id.as_ref().and_then(|v| v.neq.clone())),
+            ("l.name =", some.name.as_ref().and_then(|n| n.eq.clone())),
+            ("l.name <>", some.name.as_ref().and_then(|n| n.neq.clone())),
+        ];
+        for (clause, value) in conditions {
+            if let Some(value) = value {
+                label_clauses.push(format!(" AND {clause} {}", query.bind(value)));
+            }
+        }
+        query.push(format!(
+            "EXISTS (SELECT 1 FROM issue_labels il
+             INNER JOIN labels l ON l.id = il.label_id
+             WHERE il.issue_id = i.id{})",
+            label_clauses.concat()
+        ));
+    }
+    if let Some(has_comments) = filter.has_comments {
+        let exists = if has_comments { "EXISTS" } else { "NOT EXISTS" };
+        query.push(format!(
+            "{exists} (SELECT 1 FROM comments c WHERE c.issue_id = i.id)"
+        ));
+    }
+    if let Some(has_attachments) = filter.has_attachments {
+        let exists = if has_attachments {
+            "EXISTS"
+        } else {
+            "NOT EXISTS"
+        };
+        query.push(format!(
+            "{exists} (SELECT 1 FROM attachments a WHERE a.issue_id = i.id)"
+        ));
+    }
+    if let Some(numbers) = filter
+        .number
+        .and_then(|n| n.in_values)
+        .filter(|v| !v.is_empty())
+    {
+        let numbers = query.bind_list(numbers.into_iter().map(|n| n as i64));
+        query.push(format!("i.number IN ({numbers})"));
+    }
+    if let Some(parent_id) = filter
+        .parent
+        .as_ref()
+        .and_then(|p| p.id.as_ref())
+        .and_then(|v| v.eq.clone())
+    {
+        let parent_id = query.bind(parent_id);
+        query.push(format!("i.parent_id = {parent_id}"));
+    }
+    if let Some(cycle_id) = filter
+        .cycle
+        .as_ref()
+        .and_then(|c| c.id.as_ref())
+        .and_then(|v| v.eq.clone())
+    {
+        let cycle_id = query.bind(cycle_id);
+        query.push(format!("i.cycle_id = {cycle_id}"));
+    }
+    if let Some(created_at) = &filter.created_at {
+        query.push_date_filter("i.created_at", created_at)?;
+    }
+    if let Some(updated_at) = &filter.updated_at {
+        query.push_date_filter("i.updated_at", updated_at)?;
+    }
+    Ok(())
+}
+
+// Linear sorts "No priority" (0) after Low (4).
+const PRIORITY_RANK_SQL: &str = "(CASE WHEN i.priority = 0 THEN 5 ELSE i.priority END)";
+
+fn priority_rank(priority: i32) -> i32 {
+    if priority == 0 { 5 } else { priority }
+}
+
+fn issue_page(
+    issues: Vec<Issue>,
+    limit: usize,
+    has_previous_page: bool,
+    order: &IssueOrder,
+    total: TotalCount,
+    estimates: IssueEstimates,
+) -> IssueConnection {
+    let scope = order.scope();
+    let (nodes, page_info) = paginate(issues, limit, has_previous_page, &scope, |i| {
+        order.cursor_key(i)
+    });
+    IssueConnection {
+        nodes,
+        page_info,
+        total,
+        estimates,
+    }
+}
+
+// List queries fetch `limit + 1` rows in their ORDER BY; the extra row only
+// signals that another page exists. Cursors encode that row's ORDER BY key.
+fn paginate<T>(
+    mut nodes: Vec<T>,
+    limit: usize,
+    has_previous_page: bool,
+    scope: &str,
+    cursor_key: impl Fn(&T) -> Vec<String>,
+) -> (Vec<T>, PageInfo) {
+    let has_next_page = nodes.len() > limit;
+    nodes.truncate(limit);
+    let info = page_info(&nodes, has_next_page, has_previous_page, scope, cursor_key);
+    (nodes, info)
+}
+
+fn page_info<T>(
+    nodes: &[T],
+    has_next_page: bool,
+    has_previous_page: bool,
+    scope: &str,
+    cursor_key: impl Fn(&T) -> Vec<String>,
+) -> PageInfo {
+    let cursor = |node: &T| encode_cursor(scope, cursor_key(node));
+    PageInfo {
+        has_next_page,
+        has_previous_page,
+        start_cursor: nodes.first().map(cursor),
+        end_cursor: nodes.last().map(cursor),
+    }
+}
+
+const TEAM_CURSOR_SCOPE: &str = "teams:name";
+
+static CURSOR_KEY: OnceLock<Vec<u8>> = OnceLock::new();
+
+// SUBLINEAR_CURSOR_SECRET when set, otherwise a random key per process.
+fn cursor_key() -> &'static [u8] {
+    CURSOR_KEY.get_or_init(|| {
+        let mut key = Uuid::new_v4().as_bytes().to_vec();
+        key.extend_from_slice(Uuid::new_v4().as_bytes());
+        key
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+struct CursorPayload {
+    scope: String,
+    key: Vec<String>,
+}
+
+fn cursor_mac() -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(cursor_key()).expect("HMAC accepts keys of any length")
+}
+
+// `<payload>.<signature>`, both base64url; the payload names the ordering the
+// cursor belongs to so it can't be replayed against a different query.
+fn encode_cursor(scope: &str, key: Vec<String>) -> String {
+    let payload = serde_json::to_vec(&CursorPayload {
+        scope: scope.to_string(),
+        key,
+    })
+    .unwrap_or_default();
+    let mut mac = cursor_mac();
+    mac.update(&payload);
+    format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(&payload),
+        URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+    )
+}
+
+fn decode_cursor<const N: usize>(cursor: &str, scope: &str) -> Result<[String; N]> {
+    let key = decode_cursor_values(cursor, scope)?;
+    Ok(key
+        .try_into()
+        .map_err(|_| ValidationError(format!("invalid cursor: {cursor}")))?)
+}
+
+fn decode_cursor_values(cursor: &str, scope: &str) -> Result<Vec<String>> {
+    let invalid = || ValidationError(format!("invalid cursor: {cursor}"));
+    let (payload, signature) = cursor.split_once('.').ok_or_else(invalid)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+    let mut mac = cursor_mac();
+    mac.update(&payload);
+    mac.verify_slice(&signature).map_err(|_| invalid())?;
+    let payload: CursorPayload = serde_json::from_slice(&payload).map_err(|_| invalid())?;
+    if payload.scope != scope {
+        return Err(ValidationError(format!(
+            "cursor was issued for {} and cannot be used with {scope}",
+            payload.scope
+        ))
+        .into());
+    }
+    Ok(payload.key)
+}
+
+#[derive(Deserialize)]
+struct SearchKeyRow {
+    id: String,
+    updated_at: String,
+    search_exact: i64,
+    search_rank: f64,
+}
+
+// With FTS5, matches are ranked by bm25() with title weighted over identifier
+// over description; the LIKE fallback ranks title matches over description
+// ones. Either way an exact identifier comes first and ties go to the most
+// recently updated issue.
+#[allow(clippy::too_many_arguments)]
+async fn search_issues(
+    conn: &Connection,
+    max_page_size: i32,
+    use_fts: bool,
+    term: &str,
+    first: Option<i32>,
+    after: Option<String>,
+    include_archived: bool,
+) -> Result<IssueConnection> {
+    let term = term.trim();
+    if term.is_empty() {
+        return Err(ValidationError("searchIssues term must not be empty".to_string()).into());
+    }
+    let limit = clamp_limit(first, max_page_size);
+    let fts_query = fts_match_query(term).filter(|_| use_fts);
+    let (join, filter, rank) = match &fts_query {
+        Some(_) => (
+            "INNER JOIN issues_fts ON issues_fts.rowid = i.rowid",
+            "(i.archived = 0 OR ?3) AND issues_fts MATCH ?2",
+            "bm25(issues_fts, 10.0, 1.0, 5.0)",
+        ),
+        None => (
+            "",
+            "(i.archived = 0 OR ?3)
+               AND (i.identifier = ?1 COLLATE NOCASE
+                    OR i.title LIKE ?2 ESCAPE '\\'
+                    OR i.description LIKE ?2 ESCAPE '\\')",
+            "CASE WHEN i.title LIKE ?2 ESCAPE '\\' THEN 0 ELSE 1 END",
+        ),
+    };
+    let pattern = fts_query.unwrap_or_else(|| format!("%{}%", escape_like(term)));
+    let params: Vec<Value> = vec![
+        term.to_string().into(),
+        pattern.into(),
+        i64::from(include_archived).into(),
+    ];
+    let from = format!("issues i {join} WHERE {filter}");
+    let total = TotalCount::Query {
+        sql: format!("SELECT COUNT(*) as value FROM {from}"),
+        params: params.clone(),
+    };
+    let estimates = IssueEstimates::new(from, params.clone());
+    let mut list_params = params;
+    list_params.push(i64::from(limit + 1).into());
+
+    // The cursor carries the full sort key, so a page boundary holds even if
+    // the last issue seen has since changed or stopped matching.
+    let has_previous_page = after.is_some();
+    let after_sql = match after {
+        Some(cursor) => {
+            let [exact, rank, updated_at, id] = decode_cursor::<4>(&cursor, SEARCH_CURSOR_SCOPE)?;
+            let invalid = || ValidationError(format!("invalid cursor: {cursor}"));
+            list_params.push(exact.parse::<i64>().map_err(|_| invalid())?.into());
+            list_params.push(rank.parse::<f64>().map_err(|_| invalid())?.into());
+            list_params.push(updated_at.into());
+            list_params.push(id.into());
+            "WHERE s.search_exact > ?5
+               OR (s.search_exact = ?5 AND (s.search_rank > ?6
+                 OR (s.search_rank = ?6 AND (i.updated_at < ?7
+                   OR (i.updated_at = ?7 AND i.id < ?8)))))"
+        }
+        None => "",
+    };
+    let sql = format!(
+        "{}
+         INNER JOIN (
+           SELECT i.id AS search_id,
+                  CASE WHEN i.identifier = ?1 COLLATE NOCASE THEN 0 ELSE 1 END AS search_exact,
+                  {rank} AS search_rank
+           FROM issues i {join}
+           WHERE {filter}
+         ) s ON s.search_id = i.id
+         {after_sql}
+         ORDER BY s.search_exact, s.search_rank, i.updated_at DESC, i.id DESC
+         LIMIT ?4",
+        issue_base_select().replacen("SELECT", "SELECT s.search_exact, s.search_rank,", 1)
+    );
+    let mut rows = conn.query(&sql, list_params).await?;
+    let (mut base_rows, mut keys) = (Vec::new(), HashMap::new());
+    while let Some(row) = rows.next().await? {
+        let decode_failed = |e| anyhow::anyhow!("row decode failed: {e}");
+        let key = de::from_row::<SearchKeyRow>(&row).map_err(decode_failed)?;
+        base_rows.push(de::from_row::<IssueBaseRow>(&row).map_err(decode_failed)?);
+        keys.insert(
+            key.id,
+            vec![
+                key.search_exact.to_string(),
+                key.search_rank.to_string(),
+                key.updated_at,
+            ],
+        );
+    }
+    rank_in_state_columns(conn, &mut base_rows).await?;
+    let issues = issues_from_rows(conn, base_rows).await?;
+    let (nodes, page_info) = paginate(
+        issues,
+        limit as usize,
+        has_previous_page,
+        SEARCH_CURSOR_SCOPE,
+        |i| {
+            let mut key = keys.get(&i.id).cloned().unwrap_or_default();
+            key.push(i.id.clone());
+            key
+        },
+    );
+    Ok(IssueConnection {
+        nodes,
+        page_info,
+        total,
+        estimates,
+    })
+}
+
+const SEARCH_CURSOR_SCOPE: &str = "searchIssues:relevance";
+
+// Each whitespace-separated word becomes a quoted prefix query, so user input
+// can't inject FTS5 syntax. `None` when no word has anything to index, which
+// sends punctuation-only terms down the LIKE path.
+fn fts_match_query(term: &str) -> Option<String> {
+    let words: Vec<String> = term
+        .split_whitespace()
+        .filter(|w| w.chars().any(char::is_alphanumeric))
+        .map(|w| format!("\"{}\"*", w.replace('"', "\"\"")))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+#[derive(Deserialize)]
+struct StateCountRow {
+    state_id: String,
+    value: i64,
+}
+
+async fn list_issues_by_state(
+    conn: &Connection,
+    max_page_size: i32,
+    cache: &StateCache,
+    filter: IssuesFilter,
+    first: Option<i32>,
+) -> Result<Vec<IssueStateGroup>> {
+    let team_id = filter
+        .team
+        .as_ref()
+        .and_then(|t| t.id.as_ref())
+        .and_then(|id| id.eq.clone())
+        .ok_or_else(|| ValidationError("issuesByState requires filter.team.id.eq".to_string()))?;
+    let per_state = clamp_limit(first, max_page_size);
+    let states = cache.team_states(conn, &team_id).await?;
+    let order = IssueOrder::default();
+    let mut query = QueryBuilder::default();
+    query.push("i.archived = 0");
+    push_issue_filters(&mut query, filter)?;
+    let (count_sql, count_params) = query.grouped_count(ISSUE_COUNT_FROM, "i.state_id");
+    let totals: Vec<StateCountRow> = fetch_all(conn, &count_sql, count_params).await?;
+    let mut grouped: Vec<(WorkflowState, Vec<Issue>, i64, IssueEstimates)> = states
+        .into_iter()
+        .map(|state| {
+            let total = totals
+                .iter()
+                .find(|t| t.state_id == state.id)
+                .map_or(0, |t| t.value);
+            let mut scoped = query.clone();
+            let state_id = scoped.bind(state.id.clone());
+            scoped.push(format!("i.state_id = {state_id}"));
+            let estimates = scoped.issue_estimates(ISSUE_COUNT_FROM);
+            (state, Vec::new(), total, estimates)
+        })
+        .collect();
+    // Ranks within each state so only one page per column is loaded.
+    let ranked = format!(
+        "SELECT i.id AS ranked_id,
+                ROW_NUMBER() OVER (PARTITION BY i.state_id ORDER BY {order}) AS state_rank
+         FROM {ISSUE_COUNT_FROM}{}",
+        query.where_sql(),
+        order = order.sql(),
+    );
+    let limit_param = query.bind(i64::from(per_state + 1));
+    let sql = format!(
+        "{}
+         INNER JOIN ({ranked}) ranked ON ranked.ranked_id = i.id
+         WHERE ranked.state_rank <= {limit_param}
+         ORDER BY {}",
+        issue_base_select(),
+        order.sql(),
+    );
+    let mut rows: Vec<IssueBaseRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    rank_in_state_columns(conn, &mut rows).await?;
+    let issues = issues_from_rows(conn, rows).await?;
+
+    for issue in issues {
+        if let Some((_, nodes, _, _)) = grouped
+            .iter_mut()
+            .find(|(s, _, _, _)| s.id == issue.state.id)
+        {
+            nodes.push(issue);
+        }
+    }
+    Ok(grouped
+        .into_iter()
+        .map(|(state, nodes, total, estimates)| IssueStateGroup {
+            state,
+            issues: issue_page(
+                nodes,
+                per_state as usize,
+                false,
+                &order,
+                TotalCount::Known(total),
+                estimates,
+            ),
+        })
+        .collect())
+}
+
+async fn issue_creation_stats(
+    conn: &Connection,
+    team_id: &str,
+    since: &str,
+    until: &str,
+) -> Result<Vec<IssueCreationBucket>> {
+    let parse = |field: &str, value: &str| {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+            ValidationError(format!("{field} must be a YYYY-MM-DD date (got {value:?})"))
+        })
+    };
+    let since = parse("since", since)?;
+    let until = parse("until", until)?;
+    if since > until {
+        return Err(ValidationError("since must not be after until".to_string()).into());
+    }
+    let team_exists = count(
+        conn,
+        "SELECT COUNT(*) as value FROM teams WHERE id = ?1",
+        vec![team_id.to_string().into()],
+    )
+    .await?;
+    if team_exists == 0 {
+        return Err(NotFoundError(format!("team not found: {team_id}")).into());
+    }
+    fetch_all(
+        conn,
+        "SELECT date(created_at) AS date, COUNT(*) AS count
+         FROM issues
+         WHERE team_id = ?1 AND date(created_at) BETWEEN ?2 AND ?3
+         GROUP BY date(created_at)
+         ORDER BY date ASC",
+        vec![
+            team_id.to_string().into(),
+            since.to_string().into(),
+            until.to_string().into(),
+        ],
+    )
+    .await
+}
+
+fn filter_metadata() -> Vec<FilterDimension> {
+    fn enum_values<T: EnumType>() -> Vec<String> {
+        T::items()
+            .iter()
+            .map(|item| item.name.to_string())
+            .collect()
+    }
+    let dimension = |name: &str, values: Vec<String>| FilterDimension {
+        dimension: name.to_string(),
+        values,
+    };
+    vec![
+        dimension(
+            "workflowStateType",
+            WORKFLOW_STATE_TYPES.iter().map(|t| t.to_string()).collect(),
+        ),
+        dimension(
+            "priority",
+            PRIORITY_LABELS.iter().map(|l| l.to_string()).collect(),
+        ),
+        dimension("issueOrderBy", enum_values::<IssueOrderBy>()),
+        dimension("projectOrderBy", enum_values::<ProjectOrderBy>()),
+        dimension("orderDirection", enum_values::<OrderDirection>()),
+    ]
+}
+
+#[derive(Deserialize)]
+struct NameRow {
+    name: String,
+}
+
+const LABEL_CURSOR_SCOPE: &str = "labels:name";
+
+async fn list_labels(
+    conn: &Connection,
+    max_page_size: i32,
+    filter: Option<LabelsFilter>,
+    first: Option<i32>,
+    after: Option<String>,
+) -> Result<LabelConnection> {
+    let limit = clamp_limit(first, max_page_size);
+    let mut query = QueryBuilder::default();
+    if let Some(name) = filter.and_then(|f| f.name) {
+        query.push_string_filter("name", &name);
+    }
+    let has_previous_page = after.is_some();
+    if let Some(cursor) = after {
+        let [name, id] = decode_cursor(&cursor, LABEL_CURSOR_SCOPE)?;
+        let name = query.bind(name);
+        let id = query.bind(id);
+        query.push(format!("(name > {name} OR (name = {name} AND id > {id}))"));
+    }
+    let limit_param = query.bind(i64::from(limit + 1));
+    let sql = format!(
+        "SELECT id, name, color FROM labels{} ORDER BY name ASC, id ASC LIMIT {}",
+        query.where_sql(),
+        limit_param
+    );
+    let rows: Vec<LabelRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    let nodes: Vec<Label> = rows.into_iter().map(Label::from).collect();
+    let (nodes, page_info) = paginate(
+        nodes,
+        limit as usize,
+        has_previous_page,
+        LABEL_CURSOR_SCOPE,
+        |l| vec![l.name.clone(), l.id.clone()],
+    );
+    Ok(LabelConnection { nodes, page_info })
+}
+
+async fn list_label_names(conn: &Connection) -> Result<Vec<String>> {
+    let rows: Vec<NameRow> = fetch_all(
+        conn,
+        "SELECT DISTINCT l.name
+         FROM labels l
+         INNER JOIN issue_labels il ON il.label_id = l.id
+         ORDER BY l.name ASC",
+        vec![],
+    )
+    .await?;
+    Ok(rows.into_iter().map(|r| r.name).collect())
+}
+
+fn issue_label_name_match(name: &str) -> String {
+    format!(
+        "SELECT 1 FROM issue_labels il
+         INNER JOIN labels l ON l.id = il.label_id
+         WHERE il.issue_id = i.id AND l.name = {name}"
+    )
+}
+
+fn issue_label_id_match(id: &str) -> String {
+    format!("SELECT 1 FROM issue_labels il WHERE il.issue_id = i.id AND il.label_id = {id}")
+}
+
+const MILESTONE_CURSOR_SCOPE: &str = "projectMilestones:sortOrder";
+
+async fn list_project_milestones(
+    conn: &Connection,
+    max_page_size: i32,
+    project_id: &str,
+    first: Option<i32>,
+    after: Option<String>,
+) -> Result<ProjectMilestoneConnection> {
+    let limit = clamp_limit(first, max_page_size);
+    let mut query = QueryBuilder::default();
+    let project_id = query.bind(project_id.to_string());
+    query.push(format!("project_id = {project_id}"));
+    let has_previous_page = after.is_some();
+    if let Some(cursor) = after {
+        let [sort_order, id] = decode_cursor(&cursor, MILESTONE_CURSOR_SCOPE)?;
+        let sort_order = query.bind(sort_order);
+        let id = query.bind(id);
+        query.push(format!(
+            "(sort_order > {sort_order} OR (sort_order = {sort_order} AND id > {id}))"
+        ));
+    }
+    let limit_param = query.bind(i64::from(limit + 1));
+    let sql = format!(
+        "SELECT id, name, sort_order FROM project_milestones{} ORDER BY sort_order ASC, id ASC LIMIT {}",
+        query.where_sql(),
+        limit_param
+    );
+    let rows: Vec<ProjectMilestoneRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    let nodes: Vec<ProjectMilestone> = rows.into_iter().map(ProjectMilestone::from).collect();
+    let (nodes, page_info) = paginate(
+        nodes,
+        limit as usize,
+        has_previous_page,
+        MILESTONE_CURSOR_SCOPE,
+        |m| vec![m.sort_order.to_string(), m.id.clone()],
+    );
+    Ok(ProjectMilestoneConnection { nodes, page_info })
+}
+
+async fn create_project_milestone(
+    conn: &Connection,
+    retries: u32,
+    input: ProjectMilestoneCreateInput,
+) -> Result<ProjectMilestoneCreatePayload> {
+    if input.name.trim().is_empty() {
+        return Err(ValidationError("project milestone name must not be empty".to_string()).into());
+    }
+    if get_project(conn, &input.project_id).await?.is_none() {
+        return Err(NotFoundError(format!("project not found: {}", input.project_id)).into());
+    }
+    let milestone_id = format!("milestone_{}", short_id());
+    execute_retrying(
+        conn,
+        retries,
+        "INSERT INTO project_milestones (id, project_id, name, sort_order)
+         SELECT ?1, ?2, ?3, COALESCE(?4, COALESCE(MAX(sort_order), 0) + 1)
+         FROM project_milestones WHERE project_id = ?2",
+        vals(vec![
+            milestone_id.clone().into(),
+            input.project_id.into(),
+            input.name.into(),
+            input.sort_order.map_or(Value::Null, Value::Real),
+        ]),
+    )
+    .await?;
+
+    let milestone: ProjectMilestoneRow = fetch_one(
+        conn,
+        "SELECT id, name, sort_order FROM project_milestones WHERE id = ?1",
+        vec![milestone_id.into()],
+    )
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("failed to load created project milestone"))?;
+    Ok(ProjectMilestoneCreatePayload {
+        success: true,
+        project_milestone: milestone.into(),
+    })
+}
+
+const CYCLE_CURSOR_SCOPE: &str = "cycles:startsAt";
+
+async fn list_cycles(
+    conn: &Connection,
+    max_page_size: i32,
+    filter: Option<CyclesFilter>,
+    first: Option<i32>,
+    after: Option<String>,
+) -> Result<CycleConnection> {
+    let limit = clamp_limit(first, max_page_size);
+    let mut query = QueryBuilder::default();
+    if let Some(team_id) = filter
+        .and_then(|f| f.team)
+        .and_then(|t| t.id)
+        .and_then(|id| id.eq)
+    {
+        let team_id = query.bind(team_id);
+        query.push(format!("team_id = {team_id}"));
+    }
+    let has_previous_page = after.is_some();
+    if let Some(cursor) = after {
+        let [starts_at, id] = decode_cursor(&cursor, CYCLE_CURSOR_SCOPE)?;
+        let starts_at = query.bind(starts_at);
+        let id = query.bind(id);
+        query.push(format!(
+            "(starts_at < {starts_at} OR (starts_at = {starts_at} AND id < {id}))"
+        ));
+    }
+    let limit_param = query.bind(i64::from(limit + 1));
+    let sql = format!(
+        "SELECT id, number, name, starts_at, ends_at, created_at FROM cycles{} ORDER BY starts_at DESC, id DESC LIMIT {}",
+        query.where_sql(),
+        limit_param
+    );
+    let rows: Vec<CycleRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    let nodes: Vec<Cycle> = rows.into_iter().map(Cycle::from).collect();
+    let (nodes, page_info) = paginate(
+        nodes,
+        limit as usize,
+        has_previous_page,
+        CYCLE_CURSOR_SCOPE,
+        |c| vec![c.starts_at.clone(), c.id.clone()],
+    );
+    Ok(CycleConnection { nodes, page_info })
+}
+
+#[derive(Clone, Copy)]
+enum CycleWindow {
+    Active,
+    Previous,
+    Next,
+}
+
+// The team's cycle relative to `now`: the one running (latest start wins if
+// they overlap), the last to have ended, or the first still to start.
+async fn team_cycle(
+    conn: &Connection,
+    team_id: &str,
+    window: CycleWindow,
+    now: &str,
+) -> Result<Option<Cycle>> {
+    let (condition, order) = match window {
+        CycleWindow::Active => ("starts_at <= ?2 AND ends_at > ?2", "starts_at DESC"),
+        CycleWindow::Previous => ("ends_at <= ?2", "ends_at DESC"),
+        CycleWindow::Next => ("starts_at > ?2", "starts_at ASC"),
+    };
+    let row: Option<CycleRow> = fetch_one(
+        conn,
+        &format!(
+            "SELECT id, number, name, starts_at, ends_at, created_at FROM cycles
+             WHERE team_id = ?1 AND {condition}
+             ORDER BY {order}, number DESC
+             LIMIT 1"
+        ),
+        vec![team_id.to_string().into(), now.to_string().into()],
+    )
+    .await?;
+    Ok(row.map(Cycle::from))
+}
+
+async fn ensure_cycle_exists(conn: &Connection, cycle_id: &str) -> Result<()> {
+    let exists = count(
+        conn,
+        "SELECT COUNT(*) as value FROM cycles WHERE id = ?1",
+        vec![cycle_id.to_string().into()],
+    )
+    .await?;
+    if exists == 0 {
+        return Err(NotFoundError(format!("cycle not found: {cycle_id}")).into());
+    }
+    Ok(())
+}
+
+async fn create_cycle(
+    conn: &Connection,
+    retries: u32,
+    input: CycleCreateInput,
+) -> Result<CycleCreatePayload> {
+    if get_team(conn, &input.team_id).await?.is_none() {
+        return Err(NotFoundError(format!("team not found: {}", input.team_id)).into());
+    }
+    let starts_at = parse_timestamp(&input.starts_at)?;
+    let ends_at = parse_timestamp(&input.ends_at)?;
+    if ends_at <= starts_at {
+        return Err(ValidationError(format!(
+            "cycle endsAt ({ends_at}) must be after startsAt ({starts_at})"
+        ))
+        .into());
+    }
+    let name = input.name.filter(|n| !n.trim().is_empty());
+    let cycle_id = format!("cycle_{}", short_id());
+    // Numbered per team like issues, allocated inside the INSERT.
+    execute_retrying(
+        conn,
+        retries,
+        "INSERT INTO cycles (id, team_id, number, name, starts_at, ends_at, created_at)
+         SELECT ?1, ?2, COALESCE(MAX(number), 0) + 1, ?3, ?4, ?5, ?6 FROM cycles WHERE team_id = ?2",
+        vals(vec![
+            cycle_id.clone().into(),
+            input.team_id.into(),
+            option_string_to_value(name),
+            starts_at.into(),
+            ends_at.into(),
+            now_iso().into(),
+        ]),
+    )
+    .await?;
+
+    let cycle: CycleRow = fetch_one(
+        conn,
+        "SELECT id, number, name, starts_at, ends_at, created_at FROM cycles WHERE id = ?1",
+        vec![cycle_id.into()],
+    )
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("failed to load created cycle"))?;
+    Ok(CycleCreatePayload {
+        success: true,
+        cycle: cycle.into(),
+    })
+}
+
+async fn list_workflow_states(
+    conn: &Connection,
+    cache: &StateCache,
+    filter: Option<WorkflowStatesFilter>,
+) -> Result<WorkflowStateConnection> {
+    if let Some(team_id) = filter
+        .and_then(|f| f.team)
+        .and_then(|t| t.id)
+        .and_then(|id| id.eq)
+    {
+        let nodes = cache.team_states(conn, &team_id).await?;
+        return Ok(WorkflowStateConnection::complete(nodes));
+    }
+    let rows: Vec<WorkflowStateRow> = fetch_all(
+        conn,
+        "SELECT id, name, type AS state_type, position, color FROM workflow_states ORDER BY position ASC",
+        vec![],
+    )
+    .await?;
+    Ok(WorkflowStateConnection::complete(
+        rows.into_iter().map(WorkflowState::from).collect(),
+    ))
+}
+
+async fn load_team_workflow_states(conn: &Connection, team_id: &str) -> Result<Vec<WorkflowState>> {
+    let rows: Vec<WorkflowStateRow> = fetch_all(
+        conn,
+        "SELECT id, name, type AS state_type, position, color
+         FROM workflow_states
+         WHERE team_id = ?1
+         ORDER BY position ASC",
+        vec![team_id.to_string().into()],
+    )
+    .await?;
+    Ok(rows.into_iter().map(WorkflowState::from).collect())
+}
+
+const WORKFLOW_STATE_TYPES: [&str; 6] = [
+    "triage",
+    "backlog",
+    "unstarted",
+    "started",
+    "completed",
+    "canceled",
+];
+
+async fn get_workflow_state(conn: &Connection, id: &str) -> Result<Option<WorkflowState>> {
+    let row: Option<WorkflowStateRow> = fetch_one(
+        conn,
+        "SELECT id, name, type AS state_type, position, color FROM workflow_states WHERE id = ?1",
+        vec![id.to_string().into()],
+    )
+    .await?;
+    Ok(row.map(WorkflowState::from))
+}
+
+async fn create_workflow_state(
+    conn: &Connection,
+    input: WorkflowStateCreateInput,
+) -> Result<WorkflowStatePayload> {
+    let team_exists = count(
+        conn,
+        "SELECT COUNT(*) as value FROM teams WHERE id = ?1",
+        vec![input.team_id.clone().into()],
+    )
+    .await?;
+    if team_exists == 0 {
+        return Err(NotFoundError(format!("team not found: {}", input.team_id)).into());
+    }
+    if !WORKFLOW_STATE_TYPES.contains(&input.r#type.as_str()) {
+        return Err(ValidationError(format!(
+            "invalid workflow state type: {} (expected one of {})",
+            input.r#type,
+            WORKFLOW_STATE_TYPES.join(", ")
+        ))
+        .into());
+    }
+    if let Some(ref color) = input.color {
+        validate_hex_color(color)?;
+    }
+    let position = match input.position {
+        Some(p) => i64::from(p),
+        None => {
+            count(
+                conn,
+                "SELECT COALESCE(MAX(position), -1) + 1 as value FROM workflow_states WHERE team_id = ?1",
+                vec![input.team_id.clone().into()],
+            )
+            .await?
+        }
+    };
+
+    let id = format!("state_{}", short_id());
+    conn.execute(
+        "INSERT INTO workflow_states (id, team_id, name, type, position, color) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        vals(vec![
+            id.clone().into(),
+            input.team_id.into(),
+            input.name.into(),
+            input.r#type.into(),
+            position.into(),
+            option_string_to_value(input.color),
+        ]),
+    )
+    .await?;
+
+    let workflow_state = get_workflow_state(conn, &id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("failed to load created workflow state"))?;
+    Ok(WorkflowStatePayload {
+        success: true,
+        workflow_state,
+    })
+}
+
+async fn update_workflow_state(
+    conn: &Connection,
+    state_id: &str,
+    input: WorkflowStateUpdateInput,
+) -> Result<WorkflowStatePayload> {
+    let mut sets: Vec<String> = Vec::new();
+    let mut params: Vec<Value> = Vec::new();
+
+    if let Some(name) = input.name {
+        sets.push("name = ?".to_string());
+        params.push(name.into());
+    }
+    if let Some(position) = input.position {
+        sets.push("position = ?".to_string());
+        params.push(i64::from(position).into());
+    }
+    if let Some(color) = input.color {
+        validate_hex_color(&color)?;
+        sets.push("color = ?".to_string());
+        params.push(color.into());
+    }
+
+    if !sets.is_empty() {
+        params.push(state_id.to_string().into());
+        let sql = format!(
+            "UPDATE workflow_states SET {} WHERE id = ?",
+            sets.join(", ")
+        );
+        conn.execute(&sql, params).await?;
+    }
+
+    let workflow_state = get_workflow_state(conn, state_id)
+        .await?
+        .ok_or_else(|| NotFoundError(format!("workflow state not found: {state_id}")))?;
+    Ok(WorkflowStatePayload {
+        success: true,
+        workflow_state,
+    })
+}
+
+async fn create_project(
+    conn: &Connection,
+    base_url: &str,
+    input: ProjectCreateInput,
+) -> Result<ProjectCreatePayload> {
+    if input.team_ids.is_empty() {
+        return Err(
+            ValidationError("teamIds must contain at least one team id".to_string()).into(),
+        );
+    }
+    let missing = missing_ids(conn, "teams", &input.team_ids).await?;
+    if !missing.is_empty() {
+        return Err(NotFoundError(format!("teams not found: {}", missing.join(", "))).into());
+    }
+
+    let project_id = format!("project_{}", short_id());
+    let slug = next_project_slug(conn, &input.name).await?;
+    let now = now_iso();
+    let url = format!("{}/project/{project_id}", link_base(conn, base_url).await?);
+    conn.execute(
+        "INSERT INTO projects (id, name, slug_id, state, archived_at, url, created_at)
+         VALUES (?1, ?2, ?3, ?4, NULL, ?5, ?6)",
+        vals(vec![
+            project_id.clone().into(),
+            input.name.clone().into(),
+            slug.clone().into(),
+            "planned".into(),
+            url.clone().into(),
+            now.into(),
+        ]),
+    )
+    .await?;
+
+    for team_id in input.team_ids {
+        conn.execute(
+            "INSERT OR IGNORE INTO project_teams (project_id, team_id) VALUES (?1, ?2)",
+            vals(vec![project_id.clone().into(), team_id.into()]),
+        )
+        .await?;
+    }
+
+    let project = Project {
+        id: project_id,
+        name: input.name,
+        slug_id: Some(slug),
+        state: Some("planned".to_string()),
+        description: None,
+        archived_at: None,
+        url: Some(url),
+        sort_order: 0.0,
+    };
+
+    Ok(ProjectCreatePayload {
+        success: true,
+        project,
+    })
+}
+
+const PROJECT_STATES: [&str; 5] = ["planned", "started", "paused", "completed", "canceled"];
+
+// `slug_id` is left alone on rename so existing project URLs keep working.
+async fn update_project(
+    conn: &Connection,
+    project_id: &str,
+    input: ProjectUpdateInput,
+) -> Result<ProjectUpdatePayload> {
+    let mut sets: Vec<String> = Vec::new();
+    let mut params: Vec<Value> = Vec::new();
+    if let Some(name) = input.name {
+        if name.trim().is_empty() {
+            return Err(ValidationError("project name must not be empty".to_string()).into());
+        }
+        sets.push("name = ?".to_string());
+        params.push(name.into());
+    }
+    if let Some(state) = input.state {
+        if !PROJECT_STATES.contains(&state.as_str()) {
+            return Err(ValidationError(format!(
+                "unknown project state {state:?} (expected one of {})",
+                PROJECT_STATES.join(", ")
+            ))
+            .into());
+        }
+        sets.push("state = ?".to_string());
+        params.push(state.into());
+    }
+    if let Some(description) = input.description {
+        sets.push("description = ?".to_string());
+        params.push(description.into());
+    }
+    if !sets.is_empty() {
+        params.push(project_id.to_string().into());
+        let sql = format!("UPDATE projects SET {} WHERE id = ?", sets.join(", "));
+        conn.execute(&sql, params).await?;
+    }
+
+    let project = get_project(conn, project_id)
+        .await?
+        .ok_or_else(|| NotFoundError(format!("project not found: {project_id}")))?;
+    Ok(ProjectUpdatePayload {
+        success: true,
+        project,
+    })
+}
+
+async fn set_project_archived(
+    conn: &Connection,
+    project_id: &str,
+    archived: bool,
+) -> Result<ProjectArchivePayload> {
+    let changed = conn
+        .execute(
+            "UPDATE projects SET archived_at = CASE WHEN ?1 THEN COALESCE(archived_at, ?2) END WHERE id = ?3",
+            vals(vec![
+                i64::from(archived).into(),
+                now_iso().into(),
+                project_id.to_string().into(),
+            ]),
+        )
+        .await?;
+    Ok(ProjectArchivePayload {
+        success: changed > 0,
+    })
+}
+
+async fn create_issue(
+    conn: &Connection,
+    retries: u32,
+    base_url: &str,
+    allow_impersonation: bool,
+    max_issues_per_team: Option<i64>,
+    input: IssueCreateInput,
+) -> Result<IssueCreatePayload> {
+    let team: TeamRow = fetch_one(
+        conn,
+        "SELECT id, name, key FROM teams WHERE id = ?1",
+        vec![input.team_id.clone().into()],
+    )
+    .await?
+    .ok_or_else(|| NotFoundError(format!("team not found: {}", input.team_id)))?;
+
+    if let Some(max) = max_issues_per_team {
+        let open_issues = count(
+            conn,
+            "SELECT COUNT(*) as value FROM issues WHERE team_id = ?1 AND archived = 0",
+            vec![team.id.clone().into()],
+        )
+        .await?;
+        if open_issues >= max {
+            return Err(ValidationError(format!(
+                "team {} already has {open_issues} issues (SUBLINEAR_MAX_ISSUES_PER_TEAM={max})",
+                team.key
+            ))
+            .into());
+        }
+    }
+
+    if let Some(ref project_id) = input.project_id {
+        let exists = count(
+            conn,
+            "SELECT COUNT(*) as value FROM projects WHERE id = ?1",
+            vec![project_id.clone().into()],
+        )
+        .await?;
+        if exists == 0 {
+            return Err(NotFoundError(format!("project not found: {project_id}")).into());
+        }
+    }
+
+    if let Some(ref parent_id) = input.parent_id {
+        ensure_issue_exists(conn, parent_id).await?;
+    }
+    if let Some(ref cycle_id) = input.cycle_id {
+        ensure_cycle_in_team(conn, cycle_id, &team.id).await?;
+    }
+    if let Some(ref milestone_id) = input.project_milestone_id {
+        ensure_milestone_in_project(conn, milestone_id, input.project_id.as_deref()).await?;
+    }
+
+    let state: WorkflowStateRow = fetch_one(
+        conn,
+        "SELECT id, name, type AS state_type, position, color
+         FROM workflow_states
+         WHERE team_id = ?1
+         ORDER BY position ASC
+         LIMIT 1",
+        vec![team.id.clone().into()],
+    )
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("team {} has no workflow states", team.id))?;
+
+    let priority = validate_priority(input.priority.unwrap_or(0))?;
+    if let Some(estimate) = input.estimate {
+        validate_estimate(estimate)?;
+    }
+    let due_date = input.due_date.as_deref().map(parse_due_date).transpose()?;
+    let creator = resolve_actor(conn, input.create_as_user_id.clone(), allow_impersonation).await?;
+    let assignee_id = match &input.assignee_id {
+        MaybeUndefined::Undefined => input
+            .create_as_user_id
+            .is_some()
+            .then(|| creator.id.clone()),
+        MaybeUndefined::Null => None,
+        MaybeUndefined::Value(assignee_id) => {
+            if get_user(conn, assignee_id).await?.is_none() {
+                return Err(NotFoundError(format!("user not found: {assignee_id}")).into());
+            }
+            Some(assignee_id.clone())
+        }
+    };
+    let issue_id = format!("issue_{}", short_id());
+    let issue_url_prefix = format!("{}/issue/", link_base(conn, base_url).await?);
+    let now = now_iso();
+    // The number is allocated inside the INSERT itself, so concurrent creates
+    // for one team can't both read the same MAX(number).
+    execute_retrying(
+        conn,
+        retries,
+        "WITH next AS (
+           SELECT t.key || '-' || n.number AS identifier, n.number
+           FROM teams t,
+             (SELECT MAX(
+                COALESCE((SELECT MAX(number) FROM issues WHERE team_id = ?2), 0),
+                starting_number - 1
+              ) + 1 AS number
+              FROM teams WHERE id = ?2) n
+           WHERE t.id = ?2
+         )
+         INSERT INTO issues
+         (id, team_id, project_id, number, identifier, title, description, state_id, assignee_id, archived, url, created_at, updated_at, sort_order, creator_id, priority, estimate, due_date, parent_id, cycle_id, project_milestone_id)
+         SELECT ?1, ?2, ?3, next.number, next.identifier, ?4, ?5, ?6, ?17, 0, ?7 || next.identifier, ?8, ?9,
+           (SELECT COALESCE(MAX(sort_order), 0) + 1 FROM issues WHERE team_id = ?2), ?10, ?11, ?12, ?13, ?14, ?15, ?16
+         FROM next",
+        vals(vec![
+            issue_id.clone().into(),
+            team.id.into(),
+            option_string_to_value(input.project_id.clone()),
+            input.title.clone().into(),
+            option_string_to_value(input.description.clone()),
+            state.id.clone().into(),
+            issue_url_prefix.into(),
+            now.clone().into(),
+            now.into(),
+            creator.id.into(),
+            priority.into(),
+            input.estimate.map_or(Value::Null, Value::Real),
+            option_string_to_value(due_date),
+            option_string_to_value(input.parent_id.clone()),
+            option_string_to_value(input.cycle_id.clone()),
+            option_string_to_value(input.project_milestone_id.clone()),
+            option_string_to_value(assignee_id),
+        ]),
+    )
+    .await?;
+
+    let issue = get_issue(conn, &issue_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("failed to load created issue"))?;
+    Ok(IssueCreatePayload {
+        success: true,
+        issue,
+    })
+}
+
+async fn get_user(conn: &Connection, id: &str) -> Result<Option<User>> {
+    let row: Option<UserRow> = fetch_one(
+        conn,
+        "SELECT id, name, email FROM users WHERE id = ?1",
+        vec![id.to_string().into()],
+    )
+    .await?;
+    Ok(row.map(User::from))
+}
+
+async fn resolve_actor(
+    conn: &Connection,
+    create_as_user_id: Option<String>,
+    allow_impersonation: bool,
+) -> Result<User> {
+    let Some(user_id) = create_as_user_id else {
+        let viewer = get_viewer(conn).await?;
+        return Ok(User {
+            id: viewer.id,
+            name: viewer.name,
+            email: viewer.email,
+        });
+    };
+    if !allow_impersonation {
+        return Err(FeatureNotAccessibleError(
+            "createAsUserId is disabled (set SUBLINEAR_ALLOW_IMPERSONATION=true to enable)"
+                .to_string(),
+        )
+        .into());
+    }
+    get_user(conn, &user_id)
+        .await?
+        .ok_or_else(|| NotFoundError(format!("user not found: {user_id}")).into())
+}
+
+#[derive(Deserialize)]
+struct CommentRow {
+    id: String,
+    body: String,
+    url: String,
+    created_at: String,
+    u_id: Option<String>,
+    u_name: Option<String>,
+    u_email: Option<String>,
+}
+
+const COMMENT_CURSOR_SCOPE: &str = "comments:createdAt";
+
+async fn list_issue_comments(
+    conn: &Connection,
+    max_page_size: i32,
+    issue_id: &str,
+    first: Option<i32>,
+    after: Option<String>,
+) -> Result<CommentConnection> {
+    let limit = clamp_limit(first, max_page_size);
+    let mut query = QueryBuilder::default();
+    let issue_id = query.bind(issue_id.to_string());
+    query.push(format!("c.issue_id = {issue_id}"));
+    let has_previous_page = after.is_some();
+    if let Some(cursor) = after {
+        let [created_at, id] = decode_cursor(&cursor, COMMENT_CURSOR_SCOPE)?;
+        let created_at = query.bind(created_at);
+        let id = query.bind(id);
+        query.push(format!(
+            "(c.created_at > {created_at} OR (c.created_at = {created_at} AND c.id > {id}))"
+        ));
+    }
+    let limit_param = query.bind(i64::from(limit + 1));
+    let sql = format!(
+        "SELECT c.id, c.body, c.url, c.created_at, u.id AS u_id, u.name AS u_name, u.email AS u_email
+         FROM comments c
+         LEFT JOIN users u ON u.id = c.user_id{}
+         ORDER BY c.created_at ASC, c.id ASC
+         LIMIT {}",
+        query.where_sql(),
+        limit_param
+    );
+    let rows: Vec<CommentRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    let nodes: Vec<Comment> = rows
+        .into_iter()
+        .map(|row| Comment {
+            id: row.id,
+            body: row.body,
+            url: row.url,
+            created_at: row.created_at,
+            user: row.u_id.map(|id| User {
+                id,
+                name: row.u_name.unwrap_or_default(),
+                email: row.u_email.unwrap_or_default(),
+            }),
+        })
+        .collect();
+    let (nodes, page_info) = paginate(
+        nodes,
+        limit as usize,
+        has_previous_page,
+        COMMENT_CURSOR_SCOPE,
+        |c| vec![c.created_at.clone(), c.id.clone()],
+    );
+    Ok(CommentConnection { nodes, page_info })
+}
+
+#[derive(Deserialize)]
+struct AttachmentRow {
+    id: String,
+    title: String,
+    subtitle: Option<String>,
+    url: String,
+    metadata: Option<String>,
+    source_type: Option<String>,
+    created_at: String,
+}
+
+impl From<AttachmentRow> for Attachment {
+    fn from(row: AttachmentRow) -> Self {
+        Self {
+            id: row.id,
+            title: row.title,
+            subtitle: row.subtitle,
+            url: row.url,
+            metadata: row
+                .metadata
+                .and_then(|m| serde_json::from_str(&m).ok())
+                .map(Json),
+            source_type: row.source_type,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AttachmentFileRow {
+    content_type: String,
+}
+
+const ATTACHMENT_CURSOR_SCOPE: &str = "attachments:createdAt";
+
+async fn list_issue_attachments(
+    conn: &Connection,
+    max_page_size: i32,
+    issue_id: &str,
+    first: Option<i32>,
+    after: Option<String>,
+) -> Result<AttachmentConnection> {
+    let limit = clamp_limit(first, max_page_size);
+    let mut query = QueryBuilder::default();
+    let issue_id = query.bind(issue_id.to_string());
+    query.push(format!("issue_id = {issue_id}"));
+    let has_previous_page = after.is_some();
+    if let Some(cursor) = after {
+        let [created_at, id] = decode_cursor(&cursor, ATTACHMENT_CURSOR_SCOPE)?;
+        let created_at = query.bind(created_at);
+        let id = query.bind(id);
+        query.push(format!(
+            "(created_at > {created_at} OR (created_at = {created_at} AND id > {id}))"
+        ));
+    }
+    let limit_param = query.bind(i64::from(limit + 1));
+    let sql = format!(
+        "SELECT id, title, subtitle, url, metadata, source_type, created_at
+         FROM attachments{}
+         ORDER BY created_at ASC, id ASC
+         LIMIT {}",
+        query.where_sql(),
+        limit_param
+    );
+    let rows: Vec<AttachmentRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    let nodes: Vec<Attachment> = rows.into_iter().map(Attachment::from).collect();
+    let (nodes, page_info) = paginate(
+        nodes,
+        limit as usize,
+        has_previous_page,
+        ATTACHMENT_CURSOR_SCOPE,
+        |a| vec![a.created_at.clone(), a.id.clone()],
+    );
+    Ok(AttachmentConnection { nodes, page_info })
+}
+
+async fn create_attachment(
+    conn: &Connection,
+    retries: u32,
+    input: AttachmentCreateInput,
+) -> Result<AttachmentCreatePayload> {
+    if input.title.trim().is_empty() {
+        return Err(ValidationError("attachment title must not be empty".to_string()).into());
+    }
+    let url = url::Url::parse(input.url.trim()).map_err(|err| {
+        ValidationError(format!("attachment url {:?} is invalid: {err}", input.url))
+    })?;
+    if let Some(metadata) = input.metadata.as_ref().filter(|m| !m.is_object()) {
+        return Err(ValidationError(format!(
+            "attachment metadata must be a JSON object (got {})",
+            metadata.0
+        ))
+        .into());
+    }
+    ensure_issue_exists(conn, &input.issue_id).await?;
+    let attachment = Attachment {
+        id: format!("attachment_{}", short_id()),
+        title: input.title,
+        subtitle: input.subtitle,
+        url: url.to_string(),
+        metadata: input.metadata,
+        source_type: input
+            .source_type
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty()),
+        created_at: now_iso(),
+    };
+    insert_attachment(conn, retries, &input.issue_id, &attachment, None).await?;
+    Ok(AttachmentCreatePayload {
+        success: true,
+        attachment,
+    })
+}
+
+// Stored as `<SUBLINEAR_UPLOAD_DIR>/<attachment id>`; the row's content type
+// marks it as an uploaded file for `/files/{id}`.
+async fn upload_attachment(
+    conn: &Connection,
+    app: &AppContext,
+    issue_id: String,
+    title: Option<String>,
+    subtitle: Option<String>,
+    mut file: UploadValue,
+) -> Result<AttachmentCreatePayload> {
+    let Some(dir) = &app.upload_dir else {
+        return Err(FeatureNotAccessibleError(
+            "file uploads are disabled (set SUBLINEAR_UPLOAD_DIR to enable)".to_string(),
+        )
+        .into());
+    };
+    ensure_issue_exists(conn, &issue_id).await?;
+    let attachment_id = format!("attachment_{}", short_id());
+    let attachment = Attachment {
+        url: format!(
+            "{}/files/{attachment_id}",
+            trim_trailing_slash(&app.base_url)
+        ),
+        id: attachment_id,
+        title: title
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or_else(|| file.filename.clone()),
+        subtitle,
+        metadata: None,
+        source_type: None,
+        created_at: now_iso(),
+    };
+    let content_type = file
+        .content_type
+        .take()
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    let path = std::path::Path::new(dir).join(&attachment.id);
+    let (dir, target, mut content) = (dir.clone(), path.clone(), file.content);
+    // The upload arrives as a blocking `std::fs::File`.
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create upload dir {dir}"))?;
+        let mut out = std::fs::File::create(&target)
+            .with_context(|| format!("failed to create {}", target.display()))?;
+        std::io::copy(&mut content, &mut out)
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        Ok(())
+    })
+    .await??;
+    if let Err(err) = insert_attachment(
+        conn,
+        app.busy_retries,
+        &issue_id,
+        &attachment,
+        Some(content_type),
+    )
+    .await
+    {
+        let _ = tokio::fs::remove_file(&path).await;
+        return Err(err);
+    }
+    Ok(AttachmentCreatePayload {
+        success: true,
+        attachment,
+    })
+}
+
+async fn insert_attachment(
+    conn: &Connection,
+    retries: u32,
+    issue_id: &str,
+    attachment: &Attachment,
+    content_type: Option<String>,
+) -> Result<()> {
+    execute_retrying(
+        conn,
+        retries,
+        "INSERT INTO attachments (id, issue_id, title, subtitle, url, metadata, source_type, created_at, content_type)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        vals(vec![
+            attachment.id.clone().into(),
+            issue_id.to_string().into(),
+            attachment.title.clone().into(),
+            option_string_to_value(attachment.subtitle.clone()),
+            attachment.url.clone().into(),
+            option_string_to_value(attachment.metadata.as_ref().map(|m| m.0.to_string())),
+            option_string_to_value(attachment.source_type.clone()),
+            attachment.created_at.clone().into(),
+            option_string_to_value(content_type),
+        ]),
+    )
+    .await?;
+    Ok(())
+}
+
+// What a reaction or favorite points at; `name` is the stored `subject_type`.
+#[derive(Clone, Copy)]
+enum SubjectType {
+    Issue,
+    Comment,
+    Project,
+}
+
+impl SubjectType {
+    fn table(self) -> &'static str {
+        match self {
+            Self::Issue => "issues",
+            Self::Comment => "comments",
+            Self::Project => "projects",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Issue => "issue",
+            Self::Comment => "comment",
+            Self::Project => "project",
+        }
+    }
+}
+
+async fn ensure_subject_exists(conn: &Connection, subject: SubjectType, id: &str) -> Result<()> {
+    let exists = count(
+        conn,
+        &format!(
+            "SELECT COUNT(*) as value FROM {} WHERE id = ?1",
+            subject.table()
+        ),
+        vec![id.to_string().into()],
+    )
+    .await?;
+    if exists == 0 {
+        return Err(NotFoundError(format!("{} not found: {id}", subject.name())).into());
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct ReactionRow {
+    id: String,
+    emoji: String,
+    created_at: String,
+    u_id: Option<String>,
+    u_name: Option<String>,
+    u_email: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ReactionGroupRow {
+    emoji: String,
+    count: i64,
+}
+
+const REACTION_CURSOR_SCOPE: &str = "reactions:createdAt";
+
+async fn list_reactions(
+    conn: &Connection,
+    max_page_size: i32,
+    subject: SubjectType,
+    subject_id: &str,
+    first: Option<i32>,
+    after: Option<String>,
+) -> Result<ReactionConnection> {
+    let limit = clamp_limit(first, max_page_size);
+    let mut query = QueryBuilder::default();
+    let subject_type = query.bind(subject.name());
+    let subject_id_param = query.bind(subject_id.to_string());
+    query.push(format!(
+        "r.subject_type = {subject_type} AND r.subject_id = {subject_id_param}"
+    ));
+    let has_previous_page = after.is_some();
+    if let Some(cursor) = after {
+        let [created_at, id] = decode_cursor(&cursor, REACTION_CURSOR_SCOPE)?;
+        let created_at = query.bind(created_at);
+        let id = query.bind(id);
+        query.push(format!(
+            "(r.created_at > {created_at} OR (r.created_at = {created_at} AND r.id > {id}))"
+        ));
+    }
+    let limit_param = query.bind(i64::from(limit + 1));
+    let sql = format!(
+        "SELECT r.id, r.emoji, r.created_at, u.id AS u_id, u.name AS u_name, u.email AS u_email
+         FROM reactions r
+         LEFT JOIN users u ON u.id = r.user_id{}
+         ORDER BY r.created_at ASC, r.id ASC
+         LIMIT {}",
+        query.where_sql(),
+        limit_param
+    );
+    let rows: Vec<ReactionRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    let groups: Vec<ReactionGroupRow> = fetch_all(
+        conn,
+        "SELECT emoji, COUNT(*) AS count
+         FROM reactions
+         WHERE subject_type = ?1 AND subject_id = ?2
+         GROUP BY emoji
+         ORDER BY MIN(created_at) ASC",
+        vec![subject.name().into(), subject_id.to_string().into()],
+    )
+    .await?;
+    let nodes: Vec<Reaction> = rows
+        .into_iter()
+        .map(|row| Reaction {
+            id: row.id,
+            emoji: row.emoji,
+            created_at: row.created_at,
+            user: row.u_id.map(|id| User {
+                id,
+                name: row.u_name.unwrap_or_default(),
+                email: row.u_email.unwrap_or_default(),
+            }),
+        })
+        .collect();
+    let (nodes, page_info) = paginate(
+        nodes,
+        limit as usize,
+        has_previous_page,
+        REACTION_CURSOR_SCOPE,
+        |r| vec![r.created_at.clone(), r.id.clone()],
+    );
+    Ok(ReactionConnection {
+        nodes,
+        page_info,
+        groups: groups
+            .into_iter()
+            .map(|g| ReactionGroup {
+                emoji: g.emoji,
+                count: g.count as i32,
+            })
+            .collect(),
+    })
+}
+
+async fn create_reaction(
+    conn: &Connection,
+    retries: u32,
+    allow_impersonation: bool,
+    input: ReactionCreateInput,
+) -> Result<ReactionCreatePayload> {
+    let (subject, subject_id) = match (input.issue_id, input.comment_id) {
+        (Some(id), None) => (SubjectType::Issue, id),
+        (None, Some(id)) => (SubjectType::Comment, id),
+        _ => {
+            return Err(ValidationError(
+                "reactionCreate takes exactly one of issueId and commentId".to_string(),
+            )
+            .into());
+        }
+    };
+    let emoji = input.emoji.trim().to_string();
+    if emoji.is_empty() {
+        return Err(ValidationError("reaction emoji must not be empty".to_string()).into());
+    }
+    ensure_subject_exists(conn, subject, &subject_id).await?;
+    let user = resolve_actor(conn, input.create_as_user_id, allow_impersonation).await?;
+    let reaction_id = format!("reaction_{}", short_id());
+    let now = now_iso();
+    // The UNIQUE constraint dedupes; a second identical reaction inserts nothing.
+    let inserted = execute_retrying(
+        conn,
+        retries,
+        "INSERT INTO reactions (id, subject_type, subject_id, emoji, user_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (subject_type, subject_id, emoji, user_id) DO NOTHING",
+        vals(vec![
+            reaction_id.clone().into(),
+            subject.name().into(),
+            subject_id.clone().into(),
+            emoji.clone().into(),
+            user.id.clone().into(),
+            now.clone().into(),
+        ]),
+    )
+    .await?;
+    if inserted == 0 {
+        return Err(ValidationError(format!(
+            "user {} already reacted with {emoji} on {} {subject_id}",
+            user.id,
+            subject.name()
+        ))
+        .into());
+    }
+    Ok(ReactionCreatePayload {
+        success: true,
+        reaction: Reaction {
+            id: reaction_id,
+            emoji,
+            user: Some(user),
+            created_at: now,
+        },
+    })
+}
+
+#[derive(Deserialize)]
+struct FavoriteRow {
+    id: String,
+    subject_type: String,
+    subject_id: String,
+    created_at: String,
+}
+
+impl From<FavoriteRow> for Favorite {
+    fn from(row: FavoriteRow) -> Self {
+        Self {
+            id: row.id,
+            r#type: row.subject_type,
+            created_at: row.created_at,
+            subject_id: row.subject_id,
+        }
+    }
+}
+
+const FAVORITE_CURSOR_SCOPE: &str = "favorites:createdAt";
+
+async fn list_favorites(
+    conn: &Connection,
+    max_page_size: i32,
+    user_id: &str,
+    first: Option<i32>,
+    after: Option<String>,
+) -> Result<FavoriteConnection> {
+    let limit = clamp_limit(first, max_page_size);
+    let mut query = QueryBuilder::default();
+    let user_id = query.bind(user_id.to_string());
+    query.push(format!("user_id = {user_id}"));
+    let has_previous_page = after.is_some();
+    if let Some(cursor) = after {
+        let [created_at, id] = decode_cursor(&cursor, FAVORITE_CURSOR_SCOPE)?;
+        let created_at = query.bind(created_at);
+        let id = query.bind(id);
+        query.push(format!(
+            "(created_at > {created_at} OR (created_at = {created_at} AND id > {id}))"
+        ));
+    }
+    let limit_param = query.bind(i64::from(limit + 1));
+    let sql = format!(
+        "SELECT id, subject_type, subject_id, created_at
+         FROM favorites{}
+         ORDER BY created_at ASC, id ASC
+         LIMIT {}",
+        query.where_sql(),
+        limit_param
+    );
+    let rows: Vec<FavoriteRow> = fetch_all(conn, &sql, query.into_params()).await?;
+    let nodes: Vec<Favorite> = rows.into_iter().map(Favorite::from).collect();
+    let (nodes, page_info) = paginate(
+        nodes,
+        limit as usize,
+        has_previous_page,
+        FAVORITE_CURSOR_SCOPE,
+        |f| vec![f.created_at.clone(), f.id.clone()],
+    );
+    Ok(FavoriteConnection { nodes, page_info })
+}
+
+// Idempotent per viewer and subject: starring again returns the existing favorite.
+async fn create_favorite(
+    conn: &Connection,
+    retries: u32,
+    input: FavoriteCreateInput,
+) -> Result<FavoriteCreatePayload> {
+    let (subject, subject_id) = match (input.issue_id, input.project_id) {
+        (Some(id), None) => (SubjectType::Issue, id),
+        (None, Some(id)) => (SubjectType::Project, id),
+        _ => {
+            return Err(ValidationError(
+                "favoriteCreate takes exactly one of issueId and projectId".to_string(),
+            )
+            .into());
+        }
+    };
+    ensure_subject_exists(conn, subject, &subject_id).await?;
+    let viewer = get_viewer(conn).await?;
+    execute_retrying(
+        conn,
+        retries,
+        "INSERT INTO favorites (id, user_id, subject_type, subject_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (user_id, subject_type, subject_id) DO NOTHING",
+        vals(vec![
+            format!("favorite_{}", short_id()).into(),
+            viewer.id.clone().into(),
+            subject.name().into(),
+            subject_id.clone().into(),
+            now_iso().into(),
+        ]),
+    )
+    .await?;
+    let favorite: FavoriteRow = fetch_one(
+        conn,
+        "SELECT id, subject_type, subject_id, created_at
+         FROM favorites
+         WHERE user_id = ?1 AND subject_type = ?2 AND subject_id = ?3",
+        vec![viewer.id.into(), subject.name().into(), subject_id.into()],
+    )
+    .await?
+    .ok_or_else(|| anyhow::anyhow!("failed to load created favorite"))?;
+    Ok(FavoriteCreatePayload {
+        success: true,
+        favorite: favorite.into(),
+    })
+}
+
+async fn delete_favorite(
+    conn: &Connection,
+    retries: u32,
+    favorite_id: &str,
+) -> Result<FavoriteDeletePayload> {
+    let deleted = execute_retrying(
+        conn,
+        retries,
+        "DELETE FROM favorites WHERE id = ?1",
+        vals(vec![favorite_id.to_string().into()]),
+    )
+    .await?;
+    Ok(FavoriteDeletePayload {
+        success: deleted > 0,
+    })
+}
+
+async fn delete_reaction(
+    conn: &Connection,
+    retries: u32,
+    reaction_id: &str,
+) -> Result<ReactionDeletePayload> {
+    let deleted = execute_retrying(
+        conn,
+        retries,
+        "DELETE FROM reactions WHERE id = ?1",
+        vals(vec![reaction_id.to_string().into()]),
+    )
+    .await?;
+    Ok(ReactionDeletePayload {
+        success: deleted > 0,
+    })
+}
+
+async fn create_comment(
+    conn: &Connection,
+    retries: u32,
+    base_url: &str,
+    allow_impersonation: bool,
+    input: CommentCreateInput,
+) -> Result<CommentCreatePayload> {
+    let exists = count(
+        conn,
+        "SELECT COUNT(*) as value FROM issues WHERE id = ?1",
+        vec![input.issue_id.clone().into()],
+    )
+    .await?;
+    if exists == 0 {
+        return Err(NotFoundError(format!("issue not found: {}", input.issue_id)).into());
+    }
+    let user = resolve_actor(conn, input.create_as_user_id, allow_impersonation).await?;
+    let comment_id = format!("comment_{}", short_id());
+    let url = format!("{}/comment/{comment_id}", link_base(conn, base_url).await?);
+    let now = now_iso();
+    execute_retrying(
+        conn,
+        retries,
+        "INSERT INTO comments (id, issue_id, body, url, created_at, user_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        vals(vec![
+            comment_id.clone().into(),
+            input.issue_id.into(),
+            input.body.clone().into(),
+            url.clone().into(),
+            now.clone().into(),
+            user.id.clone().into(),
+        ]),
+    )
+    .await?;
+    Ok(CommentCreatePayload {
+        success: true,
+        comment: Comment {
+            id: comment_id,
+            body: input.body,
+            url,
+            created_at: now,
+            user: Some(user),
+        },
+    })
+}
+
+async fn update_issue(
+    conn: &Connection,
+    retries: u32,
+    issue_id: &str,
+    input: IssueUpdateInput,
+) -> Result<IssueUpdatePayload> {
+    if let MaybeUndefined::Value(parent_id) = &input.parent_id {
+        ensure_no_parent_cycle(conn, issue_id, parent_id).await?;
+    }
+    if let MaybeUndefined::Value(milestone_id) = &input.project_milestone_id {
+        ensure_issue_milestone(conn, issue_id, milestone_id).await?;
+    }
+    if let MaybeUndefined::Value(cycle_id) = &input.cycle_id {
+        ensure_issue_cycle(conn, issue_id, cycle_id).await?;
+    }
+    let (sets, mut params) = issue_update_sets(conn, input).await?;
+    params.push(issue_id.to_string().into());
+    let sql = format!("UPDATE issues SET {} WHERE id = ?", sets.join(", "));
+    let changed = execute_retrying(conn, retries, &sql, params).await?;
+    if changed == 0 {
+        return Err(NotFoundError(format!("issue not found: {issue_id}")).into());
+    }
+
+    let issue = get_issue(conn, issue_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("failed to load updated issue"))?;
+    Ok(IssueUpdatePayload {
+        success: true,
+        issue,
+    })
 }
 
-#[derive(InputObject, Clone, Default)]
-#[graphql(rename_fields = "camelCase")]
-struct ProjectFilter {
-    id: Option<IdFilter>,
-    name: Option<StringFilter>,
-}
+const ISSUE_BATCH_UPDATE_LIMIT: usize = 250;
 
-#[derive(InputObject, Clone, Default)]
-#[graphql(rename_fields = "camelCase")]
-struct StateFilter {
-    name: Option<StringFilter>,
-}
+async fn batch_update_issues(
+    conn: &Connection,
+    retries: u32,
+    ids: Vec<String>,
+    input: IssueUpdateInput,
+) -> Result<IssueBatchPayload> {
+    if ids.is_empty() {
+        return Err(
+            ValidationError("issueBatchUpdate requires at least one id".to_string()).into(),
+        );
+    }
+    if ids.len() > ISSUE_BATCH_UPDATE_LIMIT {
+        return Err(ValidationError(format!(
+            "issueBatchUpdate accepts at most {ISSUE_BATCH_UPDATE_LIMIT} ids, got {}",
+            ids.len()
+        ))
+        .into());
+    }
 
-#[derive(InputObject, Clone, Default)]
-#[graphql(rename_fields = "camelCase")]
-struct IssuesFilter {
-    team: Option<TeamFilter>,
-    project: Option<ProjectFilter>,
-    state: Option<StateFilter>,
-    number: Option<FloatFilter>,
-}
+    if let MaybeUndefined::Value(parent_id) = &input.parent_id {
+        for id in &ids {
+            ensure_no_parent_cycle(conn, id, parent_id).await?;
+        }
+    }
+    if let MaybeUndefined::Value(milestone_id) = &input.project_milestone_id {
+        for id in &ids {
+            ensure_issue_milestone(conn, id, milestone_id).await?;
+        }
+    }
+    if let MaybeUndefined::Value(cycle_id) = &input.cycle_id {
+        for id in &ids {
+            ensure_issue_cycle(conn, id, cycle_id).await?;
+        }
+    }
+    let (sets, params) = issue_update_sets(conn, input).await?;
+    let sql = format!("UPDATE issues SET {} WHERE id = ?", sets.join(", "));
+    let (sql, params, ids_ref) = (&sql, &params, &ids);
+    // Any missing id drops the transaction, rolling back the whole batch.
+    retry_busy(retries, || async move {
+        let tx = conn.transaction().await?;
+        for id in ids_ref {
+            let mut row_params = params.clone();
+            row_params.push(id.clone().into());
+            if tx.execute(sql, row_params).await? == 0 {
+                return Err(NotFoundError(format!("issue not found: {id}")).into());
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    })
+    .await?;
 
-#[derive(InputObject, Clone, Default)]
-#[graphql(rename_fields = "camelCase")]
-struct TeamsFilter {
-    name: Option<StringFilter>,
+    let mut issues = Vec::with_capacity(ids.len());
+    for id in &ids {
+        let issue = get_issue(conn, id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("failed to load updated issue"))?;
+        issues.push(issue);
+    }
+    Ok(IssueBatchPayload {
+        success: true,
+        issues,
+    })
 }
 
-#[derive(InputObject, Clone, Default)]
-#[graphql(rename_fields = "camelCase")]
-struct ProjectsFilter {
-    name: Option<StringFilter>,
+// SET fragments and their parameters shared by issueUpdate and
+// issueBatchUpdate; always ends with `updated_at`.
+async fn issue_update_sets(
+    conn: &Connection,
+    input: IssueUpdateInput,
+) -> Result<(Vec<String>, Vec<Value>)> {
+    let mut sets: Vec<String> = Vec::new();
+    let mut params: Vec<Value> = Vec::new();
+
+    if let Some(title) = input.title {
+        sets.push("title = ?".to_string());
+        params.push(title.into());
+    }
+    if let Some(description) = input.description {
+        sets.push("description = ?".to_string());
+        params.push(description.into());
+    }
+    if let Some(state_id) = input.state_id {
+        sets.push("state_id = ?".to_string());
+        sets.push(format!("completed_at = {COMPLETED_AT_FOR_STATE}"));
+        params.push(state_id.clone().into());
+        params.push(state_id.into());
+        params.push(now_iso().into());
+    }
+    if let Some(sort_order) = input.sort_order {
+        sets.push("sort_order = ?".to_string());
+        params.push(sort_order.into());
+    }
+    if let Some(priority) = input.priority {
+        sets.push("priority = ?".to_string());
+        params.push(validate_priority(priority)?.into());
+    }
+    match input.due_date {
+        MaybeUndefined::Undefined => {}
+        MaybeUndefined::Null => sets.push("due_date = NULL".to_string()),
+        MaybeUndefined::Value(due_date) => {
+            sets.push("due_date = ?".to_string());
+            params.push(parse_due_date(&due_date)?.into());
+        }
+    }
+    match input.estimate {
+        MaybeUndefined::Undefined => {}
+        MaybeUndefined::Null => sets.push("estimate = NULL".to_string()),
+        MaybeUndefined::Value(estimate) => {
+            validate_estimate(estimate)?;
+            sets.push("estimate = ?".to_string());
+            params.push(estimate.into());
+        }
+    }
+    match input.assignee_id {
+        MaybeUndefined::Undefined => {}
+        MaybeUndefined::Null => sets.push("assignee_id = NULL".to_string()),
+        MaybeUndefined::Value(assignee_id) => {
+            if get_user(conn, &assignee_id).await?.is_none() {
+                return Err(NotFoundError(format!("user not found: {assignee_id}")).into());
+            }
+            sets.push("assignee_id = ?".to_string());
+            params.push(assignee_id.into());
+        }
+    }
+    match input.parent_id {
+        MaybeUndefined::Undefined => {}
+        MaybeUndefined::Null => sets.push("parent_id = NULL".to_string()),
+        MaybeUndefined::Value(parent_id) => {
+            ensure_issue_exists(conn, &parent_id).await?;
+            sets.push("parent_id = ?".to_string());
+            params.push(parent_id.into());
+        }
+    }
+    // Ownership is checked by the callers, which know the issue ids.
+    match input.project_milestone_id {
+        MaybeUndefined::Undefined => {}
+        MaybeUndefined::Null => sets.push("project_milestone_id = NULL".to_string()),
+        MaybeUndefined::Value(milestone_id) => {
+            sets.push("project_milestone_id = ?".to_string());
+            params.push(milestone_id.into());
+        }
+    }
+    match input.cycle_id {
+        MaybeUndefined::Undefined => {}
+        MaybeUndefined::Null => sets.push("cycle_id = NULL".to_string()),
+        MaybeUndefined::Value(cycle_id) => {
+            ensure_cycle_exists(conn, &cycle_id).await?;
+            sets.push("cycle_id = ?".to_string());
+            params.push(cycle_id.into());
+        }
+    }
+    sets.push("updated_at = ?".to_string());
+    params.push(now_iso().into());
+    Ok((sets, params))
 }
 
-#[derive(InputObject, Clone, Default)]
-#[graphql(rename_fields = "camelCase")]
-struct WorkflowStatesFilter {
-    team: Option<TeamFilter>,
+async fn ensure_issue_exists(conn: &Connection, issue_id: &str) -> Result<()> {
+    let exists = count(
+        conn,
+        "SELECT COUNT(*) as value FROM issues WHERE id = ?1",
+        vec![issue_id.to_string().into()],
+    )
+    .await?;
+    if exists == 0 {
+        return Err(NotFoundError(format!("issue not found: {issue_id}")).into());
+    }
+    Ok(())
 }
 
-#[derive(InputObject, Clone)]
-#[graphql(rename_fields = "camelCase")]
-struct ProjectCreateInput {
-    team_ids: Vec<String>,
-    name: String,
+// Walks up from `parent_id`; finding `issue_id` on the way (including the
+// parent itself) means the assignment would create a cycle.
+async fn ensure_no_parent_cycle(conn: &Connection, issue_id: &str, parent_id: &str) -> Result<()> {
+    let on_chain = count(
+        conn,
+        "WITH RECURSIVE chain(id) AS (
+           SELECT ?2
+           UNION
+           SELECT i.parent_id FROM issues i INNER JOIN chain c ON i.id = c.id
+           WHERE i.parent_id IS NOT NULL
+         )
+         SELECT COUNT(*) as value FROM chain WHERE id = ?1",
+        vec![issue_id.to_string().into(), parent_id.to_string().into()],
+    )
+    .await?;
+    if on_chain > 0 {
+        return Err(ValidationError(format!(
+            "issue {issue_id} cannot have {parent_id} as its parent: that would create a cycle"
+        ))
+        .into());
+    }
+    Ok(())
 }
 
-#[derive(InputObject, Clone)]
-#[graphql(rename_fields = "camelCase")]
-struct IssueCreateInput {
-    team_id: String,
+#[derive(Deserialize)]
+struct IssueProjectRow {
     project_id: Option<String>,
-    title: String,
-    description: Option<String>,
 }
 
-#[derive(InputObject, Clone, Default)]
-#[graphql(rename_fields = "camelCase")]
-struct IssueUpdateInput {
-    title: Option<String>,
-    description: Option<String>,
-    state_id: Option<String>,
+async fn ensure_issue_milestone(
+    conn: &Connection,
+    issue_id: &str,
+    milestone_id: &str,
+) -> Result<()> {
+    let issue: IssueProjectRow = fetch_one(
+        conn,
+        "SELECT project_id FROM issues WHERE id = ?1",
+        vec![issue_id.to_string().into()],
+    )
+    .await?
+    .ok_or_else(|| NotFoundError(format!("issue not found: {issue_id}")))?;
+    ensure_milestone_in_project(conn, milestone_id, issue.project_id.as_deref()).await
 }
 
-#[derive(InputObject, Clone)]
-#[graphql(rename_fields = "camelCase")]
-struct CommentCreateInput {
-    issue_id: String,
-    body: String,
+async fn ensure_milestone_in_project(
+    conn: &Connection,
+    milestone_id: &str,
+    project_id: Option<&str>,
+) -> Result<()> {
+    let milestone: IssueProjectRow = fetch_one(
+        conn,
+        "SELECT project_id FROM project_milestones WHERE id = ?1",
+        vec![milestone_id.to_string().into()],
+    )
+    .await?
+    .ok_or_else(|| NotFoundError(format!("project milestone not found: {milestone_id}")))?;
+    if milestone.project_id.as_deref() != project_id {
+        return Err(ValidationError(format!(
+            "project milestone {milestone_id} belongs to project {}, not the issue's project",
+            milestone.project_id.unwrap_or_default()
+        ))
+        .into());
+    }
+    Ok(())
 }
 
-#[derive(InputObject, Clone)]
-#[graphql(rename_fields = "camelCase")]
-struct AdminImportProjectInput {
-    id: String,
-    name: String,
-    slug_id: String,
-    state: Option<String>,
-    archived_at: Option<String>,
-    url: String,
+#[derive(Deserialize)]
+struct TeamIdRow {
+    team_id: String,
 }
 
-#[derive(Enum, Clone, Copy, Eq, PartialEq)]
-enum IssueOrderBy {
-    #[graphql(name = "updatedAt")]
-    UpdatedAt,
+async fn ensure_issue_cycle(conn: &Connection, issue_id: &str, cycle_id: &str) -> Result<()> {
+    let issue: TeamIdRow = fetch_one(
+        conn,
+        "SELECT team_id FROM issues WHERE id = ?1",
+        vec![issue_id.to_string().into()],
+    )
+    .await?
+    .ok_or_else(|| NotFoundError(format!("issue not found: {issue_id}")))?;
+    ensure_cycle_in_team(conn, cycle_id, &issue.team_id).await
 }
 
-#[derive(Deserialize)]
-struct UserRow {
-    id: String,
-    name: String,
-    email: String,
+async fn ensure_cycle_in_team(conn: &Connection, cycle_id: &str, team_id: &str) -> Result<()> {
+    let cycle: TeamIdRow = fetch_one(
+        conn,
+        "SELECT team_id FROM cycles WHERE id = ?1",
+        vec![cycle_id.to_string().into()],
+    )
+    .await?
+    .ok_or_else(|| NotFoundError(format!("cycle not found: {cycle_id}")))?;
+    if cycle.team_id != team_id {
+        return Err(ValidationError(format!(
+            "cycle {cycle_id} belongs to team {}, not the issue's team",
+            cycle.team_id
+        ))
+        .into());
+    }
+    Ok(())
 }
 
-impl From<UserRow> for User {
-    fn from(v: UserRow) -> Self {
-        Self {
-            id: v.id,
-            name: v.name,
-            email: v.email,
-        }
+async fn set_issue_cycle(
+    conn: &Connection,
+    retries: u32,
+    issue_id: &str,
+    cycle_id: Option<String>,
+) -> Result<IssueSetCyclePayload> {
+    if let Some(cycle_id) = &cycle_id {
+        ensure_issue_cycle(conn, issue_id, cycle_id).await?;
     }
+    let changed = execute_retrying(
+        conn,
+        retries,
+        "UPDATE issues SET cycle_id = ?1, updated_at = ?2 WHERE id = ?3",
+        vals(vec![
+            option_string_to_value(cycle_id),
+            now_iso().into(),
+            issue_id.to_string().into(),
+        ]),
+    )
+    .await?;
+    if changed == 0 {
+        return Err(NotFoundError(format!("issue not found: {issue_id}")).into());
+    }
+    let issue = get_issue(conn, issue_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("failed to load updated issue"))?;
+    Ok(IssueSetCyclePayload {
+        success: true,
+        issue,
+    })
 }
 
-#[derive(Deserialize)]
-struct TeamRow {
-    id: String,
-    name: String,
-    key: String,
+async fn move_issue_to_active_cycle(
+    conn: &Connection,
+    retries: u32,
+    issue_id: &str,
+) -> Result<IssueSetCyclePayload> {
+    let issue: TeamIdRow = fetch_one(
+        conn,
+        "SELECT team_id FROM issues WHERE id = ?1",
+        vec![issue_id.to_string().into()],
+    )
+    .await?
+    .ok_or_else(|| NotFoundError(format!("issue not found: {issue_id}")))?;
+    let cycle = team_cycle(conn, &issue.team_id, CycleWindow::Active, &now_iso())
+        .await?
+        .ok_or_else(|| ValidationError(format!("team {} has no active cycle", issue.team_id)))?;
+    set_issue_cycle(conn, retries, issue_id, Some(cycle.id)).await
 }
 
-impl From<TeamRow> for Team {
-    fn from(v: TeamRow) -> Self {
-        Self {
-            id: v.id,
-            name: v.name,
-            key: v.key,
+async fn resolve_issue(
+    conn: &Connection,
+    retries: u32,
+    base_url: &str,
+    issue_id: &str,
+    state_id: &str,
+    body: String,
+) -> Result<IssueResolvePayload> {
+    let state_in_team = count(
+        conn,
+        "SELECT COUNT(*) as value
+         FROM issues i
+         INNER JOIN workflow_states ws ON ws.team_id = i.team_id
+         WHERE i.id = ?1 AND ws.id = ?2",
+        vec![issue_id.to_string().into(), state_id.to_string().into()],
+    )
+    .await?;
+    if state_in_team == 0 {
+        let issue_exists = count(
+            conn,
+            "SELECT COUNT(*) as value FROM issues WHERE id = ?1",
+            vec![issue_id.to_string().into()],
+        )
+        .await?;
+        if issue_exists == 0 {
+            return Err(NotFoundError(format!("issue not found: {issue_id}")).into());
         }
+        return Err(ValidationError(format!(
+            "workflow state {state_id} does not belong to the issue's team"
+        ))
+        .into());
     }
-}
 
-#[derive(Deserialize)]
-struct ProjectRow {
-    id: String,
-    name: String,
-    slug_id: Option<String>,
-    state: Option<String>,
-    archived_at: Option<String>,
-    url: Option<String>,
-}
+    // Same SET clauses as issueUpdate, so state changes behave identically.
+    let (sets, mut params) = issue_update_sets(
+        conn,
+        IssueUpdateInput {
+            state_id: Some(state_id.to_string()),
+            ..IssueUpdateInput::default()
+        },
+    )
+    .await?;
+    params.push(issue_id.to_string().into());
+    let update_sql = format!("UPDATE issues SET {} WHERE id = ?", sets.join(", "));
+    let (update_sql, params) = (&update_sql, &params);
 
-impl From<ProjectRow> for Project {
-    fn from(v: ProjectRow) -> Self {
-        Self {
-            id: v.id,
-            name: v.name,
-            slug_id: v.slug_id,
-            state: v.state,
-            archived_at: v.archived_at,
-            url: v.url,
-        }
-    }
+    let user = resolve_actor(conn, None, false).await?;
+    let comment_id = format!("comment_{}", short_id());
+    let url = format!("{}/comment/{comment_id}", link_base(conn, base_url).await?);
+    let (body_ref, url_ref, user_id) = (&body, &url, &user.id);
+    let comment_ref = &comment_id;
+    let now = now_iso();
+    let now_ref = &now;
+    retry_busy(retries, || async move {
+        let now = now_ref.clone();
+        let tx = conn.transaction().await?;
+        tx.execute(update_sql, params.clone()).await?;
+        tx.execute(
+            "INSERT INTO comments (id, issue_id, body, url, created_at, user_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            vals(vec![
+                comment_ref.clone().into(),
+                issue_id.to_string().into(),
+                body_ref.clone().into(),
+                url_ref.clone().into(),
+                now.into(),
+                user_id.clone().into(),
+            ]),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    })
+    .await?;
+
+    let issue = get_issue(conn, issue_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("failed to load resolved issue"))?;
+    Ok(IssueResolvePayload {
+        success: true,
+        issue,
+        comment: Comment {
+            id: comment_id,
+            body,
+            url,
+            created_at: now,
+            user: Some(user),
+        },
+    })
 }
 
-#[derive(Deserialize)]
-struct WorkflowStateRow {
-    id: String,
-    name: String,
-    state_type: Option<String>,
-}
+// Binds the new state id then the current time. Entering a `completed` state
+// stamps completed_at (kept if already set); leaving one clears it.
+const COMPLETED_AT_FOR_STATE: &str = "CASE
+     WHEN (SELECT type FROM workflow_states WHERE id = ?) = 'completed' THEN COALESCE(completed_at, ?)
+     ELSE NULL
+   END";
 
-impl From<WorkflowStateRow> for WorkflowState {
-    fn from(v: WorkflowStateRow) -> Self {
-        Self {
-            id: v.id,
-            name: v.name,
-            r#type: v.state_type,
-        }
+async fn cycle_issue_state(
+    conn: &Connection,
+    retries: u32,
+    issue_id: &str,
+    direction: CycleDirection,
+) -> Result<IssueUpdatePayload> {
+    let (step, order) = match direction {
+        CycleDirection::Forward => (">", "ASC"),
+        CycleDirection::Backward => ("<", "DESC"),
+    };
+    let exists = count(
+        conn,
+        "SELECT COUNT(*) as value FROM issues WHERE id = ?1",
+        vec![issue_id.to_string().into()],
+    )
+    .await?;
+    if exists == 0 {
+        return Err(NotFoundError(format!("issue not found: {issue_id}")).into());
     }
-}
+    let next: Option<IdRow> = fetch_one(
+        conn,
+        &format!(
+            "SELECT ws.id
+             FROM issues i
+             INNER JOIN workflow_states cur ON cur.id = i.state_id
+             INNER JOIN workflow_states ws ON ws.team_id = i.team_id AND ws.position {step} cur.position
+             WHERE i.id = ?1
+             ORDER BY ws.position {order}, ws.id {order}
+             LIMIT 1"
+        ),
+        vec![issue_id.to_string().into()],
+    )
+    .await?;
 
-#[derive(Deserialize)]
-struct LabelRow {
-    id: String,
-    name: String,
-}
+    // Already at the first/last state: leave the issue as it is.
+    if let Some(next) = next {
+        execute_retrying(
+            conn,
+            retries,
+            &format!(
+                "UPDATE issues SET state_id = ?, completed_at = {COMPLETED_AT_FOR_STATE}, updated_at = ? WHERE id = ?"
+            ),
+            vals(vec![
+                next.id.clone().into(),
+                next.id.into(),
+                now_iso().into(),
+                now_iso().into(),
+                issue_id.to_string().into(),
+            ]),
+        )
+        .await?;
+    }
 
-#[derive(Deserialize)]
-struct IssueBaseRow {
-    id: String,
-    identifier: String,
-    title: String,
-    url: String,
-    description: Option<String>,
-    updated_at: Option<String>,
-    ws_id: Option<String>,
-    ws_name: Option<String>,
-    ws_type: Option<String>,
-    p_id: Option<String>,
-    p_name: Option<String>,
-    p_slug_id: Option<String>,
-    p_state: Option<String>,
-    p_archived_at: Option<String>,
-    p_url: Option<String>,
-    u_id: Option<String>,
-    u_name: Option<String>,
-    u_email: Option<String>,
+    let issue = get_issue(conn, issue_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("failed to load updated issue"))?;
+    Ok(IssueUpdatePayload {
+        success: true,
+        issue,
+    })
 }
 
-async fn get_viewer(conn: &Connection) -> Result<Viewer> {
-    let row: UserRow = fetch_one(
+async fn set_issue_archived(
+    conn: &Connection,
+    retries: u32,
+    issue_id: &str,
+    archived: bool,
+) -> Result<IssueArchivePayload> {
+    let changed = execute_retrying(
         conn,
-        "SELECT id, name, email FROM users ORDER BY created_at ASC LIMIT 1",
-        vec![],
+        retries,
+        "UPDATE issues
+         SET archived = ?1, archived_at = CASE WHEN ?1 THEN ?2 END, updated_at = ?2
+         WHERE id = ?3",
+        vals(vec![
+            i64::from(archived).into(),
+            now_iso().into(),
+            issue_id.to_string().into(),
+        ]),
     )
-    .await?
-    .ok_or_else(|| anyhow::anyhow!("no viewer configured"))?;
-    Ok(Viewer {
-        id: row.id,
-        name: row.name,
-        email: row.email,
+    .await?;
+    Ok(IssueArchivePayload {
+        success: changed > 0,
     })
 }
 
-async fn list_teams(
+async fn set_labels(
     conn: &Connection,
-    filter: Option<TeamsFilter>,
-    first: Option<i32>,
-) -> Result<TeamConnection> {
-    let limit = clamp_limit(first);
-    let mut where_sql = String::new();
-    let mut params: Vec<Value> = Vec::new();
-    if let Some(f) = filter {
-        if let Some(name) = f.name.and_then(|v| v.eq).filter(|v| !v.is_empty()) {
-            where_sql.push_str(" WHERE name = ?1");
-            params.push(name.into());
-        }
+    retries: u32,
+    issue_id: &str,
+    label_ids: Vec<String>,
+) -> Result<IssueSetLabelsPayload> {
+    let issue_exists = count(
+        conn,
+        "SELECT COUNT(*) as value FROM issues WHERE id = ?1",
+        vec![issue_id.to_string().into()],
+    )
+    .await?;
+    if issue_exists == 0 {
+        return Err(NotFoundError(format!("issue not found: {issue_id}")).into());
     }
-    let limit_idx = params.len() + 1;
-    let sql = format!(
-        "SELECT id, name, key FROM teams{} ORDER BY name ASC LIMIT ?{}",
-        where_sql, limit_idx
-    );
-    params.push(i64::from(limit).into());
-    let rows: Vec<TeamRow> = fetch_all(conn, &sql, params).await?;
-    Ok(TeamConnection {
-        nodes: rows.into_iter().map(Team::from).collect(),
+
+    let mut label_ids = label_ids;
+    label_ids.sort();
+    label_ids.dedup();
+    let missing = missing_ids(conn, "labels", &label_ids).await?;
+    if !missing.is_empty() {
+        return Err(NotFoundError(format!("labels not found: {}", missing.join(", "))).into());
+    }
+
+    let label_ids = &label_ids;
+    retry_busy(retries, || async move {
+        let tx = conn.transaction().await?;
+        tx.execute(
+            "DELETE FROM issue_labels WHERE issue_id = ?1",
+            vals(vec![issue_id.to_string().into()]),
+        )
+        .await?;
+        for label_id in label_ids {
+            tx.execute(
+                "INSERT INTO issue_labels (issue_id, label_id) VALUES (?1, ?2)",
+                vals(vec![issue_id.to_string().into(), label_id.clone().into()]),
+            )
+            .await?;
+        }
+        tx.execute(
+            "UPDATE issues SET updated_at = ?1 WHERE id = ?2",
+            vals(vec![now_iso().into(), issue_id.to_string().into()]),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    })
+    .await?;
+
+    let issue = get_issue(conn, issue_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("failed to load updated issue"))?;
+    Ok(IssueSetLabelsPayload {
+        success: true,
+        issue,
     })
 }
 
-async fn get_team(conn: &Connection, id: &str) -> Result<Option<Team>> {
-    let row: Option<TeamRow> = fetch_one(
+async fn delete_project(
+    conn: &Connection,
+    retries: u32,
+    project_id: &str,
+    reassign: Option<bool>,
+) -> Result<ProjectDeletePayload> {
+    let exists = count(
         conn,
-        "SELECT id, name, key FROM teams WHERE id = ?1",
-        vec![id.to_string().into()],
+        "SELECT COUNT(*) as value FROM projects WHERE id = ?1",
+        vec![project_id.to_string().into()],
     )
     .await?;
-    Ok(row.map(Team::from))
-}
-
-async fn list_projects(
-    conn: &Connection,
-    filter: Option<ProjectsFilter>,
-    first: Option<i32>,
-) -> Result<ProjectConnection> {
-    let limit = clamp_limit(first);
-    let mut where_sql = String::new();
-    let mut params: Vec<Value> = Vec::new();
-    if let Some(f) = filter {
-        if let Some(name) = f.name.and_then(|v| v.eq).filter(|v| !v.is_empty()) {
-            where_sql.push_str(" WHERE name = ?");
-            params.push(name.into());
-        }
+    if exists == 0 {
+        return Err(NotFoundError(format!("project not found: {project_id}")).into());
     }
-    let sql = format!(
-        "SELECT id, name, slug_id, state, archived_at, url FROM projects{} ORDER BY created_at DESC LIMIT ?",
-        where_sql
-    );
-    params.push(i64::from(limit).into());
-    let rows: Vec<ProjectRow> = fetch_all(conn, &sql, params).await?;
-    Ok(ProjectConnection {
-        nodes: rows.into_iter().map(Project::from).collect(),
+    let issue_count = count(
+        conn,
+        "SELECT COUNT(*) as value FROM issues WHERE project_id = ?1",
+        vec![project_id.to_string().into()],
+    )
+    .await?;
+    if issue_count > 0 && reassign.is_none() {
+        return Err(ValidationError(format!(
+            "project {project_id} still has {issue_count} issues; pass reassign to detach them"
+        ))
+        .into());
+    }
+
+    retry_busy(retries, || async move {
+        let tx = conn.transaction().await?;
+        tx.execute(
+            "UPDATE issues SET project_id = NULL, project_milestone_id = NULL, updated_at = ?1 WHERE project_id = ?2",
+            vals(vec![now_iso().into(), project_id.to_string().into()]),
+        )
+        .await?;
+        tx.execute(
+            "DELETE FROM project_teams WHERE project_id = ?1",
+            vals(vec![project_id.to_string().into()]),
+        )
+        .await?;
+        tx.execute(
+            "DELETE FROM project_milestones WHERE project_id = ?1",
+            vals(vec![project_id.to_string().into()]),
+        )
+        .await?;
+        tx.execute(
+            "DELETE FROM favorites WHERE subject_type = 'project' AND subject_id = ?1",
+            vals(vec![project_id.to_string().into()]),
+        )
+        .await?;
+        tx.execute(
+            "DELETE FROM projects WHERE id = ?1",
+            vals(vec![project_id.to_string().into()]),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
     })
+    .await?;
+
+    Ok(ProjectDeletePayload { success: true })
 }
 
-async fn get_project(conn: &Connection, id: &str) -> Result<Option<Project>> {
-    let row: Option<ProjectRow> = fetch_one(
+async fn archive_project_issues(
+    conn: &Connection,
+    retries: u32,
+    project_id: &str,
+) -> Result<Vec<String>> {
+    let exists = count(
         conn,
-        "SELECT id, name, slug_id, state, archived_at, url FROM projects WHERE id = ?1",
-        vec![id.to_string().into()],
+        "SELECT COUNT(*) as value FROM projects WHERE id = ?1",
+        vec![project_id.to_string().into()],
     )
     .await?;
-    Ok(row.map(Project::from))
+    if exists == 0 {
+        return Err(NotFoundError(format!("project not found: {project_id}")).into());
+    }
+    let now = now_iso();
+    let archived: Vec<IdRow> = retry_busy(retries, || {
+        fetch_all(
+            conn,
+            "UPDATE issues SET archived = 1, archived_at = ?1, updated_at = ?1
+             WHERE project_id = ?2 AND archived = 0
+             RETURNING id",
+            vals(vec![now.clone().into(), project_id.to_string().into()]),
+        )
+    })
+    .await?;
+    Ok(archived.into_iter().map(|row| row.id).collect())
 }
 
-async fn get_issue(conn: &Connection, id: &str) -> Result<Option<Issue>> {
-    let sql = format!("{} WHERE i.id = ?1", issue_base_select());
-    let row: Option<IssueBaseRow> = fetch_one(conn, &sql, vec![id.to_string().into()]).await?;
-    match row {
-        Some(v) => Ok(Some(issue_from_row(conn, v).await?)),
-        None => Ok(None),
+async fn reassign_user_issues(
+    conn: &Connection,
+    retries: u32,
+    from_user_id: &str,
+    to_user_id: &str,
+) -> Result<UserReassignIssuesPayload> {
+    let user_ids = vec![from_user_id.to_string(), to_user_id.to_string()];
+    let missing = missing_ids(conn, "users", &user_ids).await?;
+    if !missing.is_empty() {
+        return Err(NotFoundError(format!("users not found: {}", missing.join(", "))).into());
     }
+    let changed = execute_retrying(
+        conn,
+        retries,
+        "UPDATE issues SET assignee_id = ?1, updated_at = ?2 WHERE assignee_id = ?3 AND ?1 <> ?3",
+        vals(vec![
+            to_user_id.to_string().into(),
+            now_iso().into(),
+            from_user_id.to_string().into(),
+        ]),
+    )
+    .await?;
+    Ok(UserReassignIssuesPayload {
+        success: true,
+        reassigned_count: changed as i64,
+    })
 }
 
-async fn list_issues(
+async fn add_label(
     conn: &Connection,
-    filter: Option<IssuesFilter>,
-    first: Option<i32>,
-    _order_by: Option<IssueOrderBy>,
-) -> Result<IssueConnection> {
-    let limit = clamp_limit(first);
-    let mut clauses = vec!["i.archived = 0".to_string()];
-    let mut params: Vec<Value> = Vec::new();
-
-    if let Some(filter) = filter {
-        if let Some(team_id) = filter
-            .team
-            .as_ref()
-            .and_then(|t| t.id.as_ref())
-            .and_then(|v| v.eq.clone())
-        {
-            clauses.push("i.team_id = ?".to_string());
-            params.push(team_id.into());
-        }
-        if let Some(team_key) = filter
-            .team
-            .as_ref()
-            .and_then(|t| t.key.as_ref())
-            .and_then(|k| k.eq.clone())
-        {
-            clauses.push("t.key = ?".to_string());
-            params.push(team_key.into());
-        }
-        if let Some(project_id) = filter
-            .project
-            .as_ref()
-            .and_then(|p| p.id.as_ref())
-            .and_then(|v| v.eq.clone())
-        {
-            clauses.push("i.project_id = ?".to_string());
-            params.push(project_id.into());
-        }
-        if let Some(state_name_eq) = filter
-            .state
-            .as_ref()
-            .and_then(|s| s.name.as_ref())
-            .and_then(|n| n.eq.clone())
-        {
-            clauses.push("ws.name = ?".to_string());
-            params.push(state_name_eq.into());
-        }
-        if let Some(state_name_neq) = filter
-            .state
-            .as_ref()
-            .and_then(|s| s.name.as_ref())
-            .and_then(|n| n.neq.clone())
-        {
-            clauses.push("ws.name <> ?".to_string());
-            params.push(state_name_neq.into());
-        }
-        if let Some(numbers) = filter
-            .number
-            .and_then(|n| n.in_values)
-            .filter(|v| !v.is_empty())
-        {
-            let placeholders = std::iter::repeat_n("?", numbers.len())
-                .collect::<Vec<_>>()
-                .join(", ");
-            clauses.push(format!("i.number IN ({placeholders})"));
-            for n in numbers {
-                params.push((n as i64).into());
-            }
-        }
+    retries: u32,
+    issue_id: &str,
+    label_id: &str,
+    strict: bool,
+) -> Result<IssueAddLabelPayload> {
+    let issue_exists = count(
+        conn,
+        "SELECT COUNT(*) as value FROM issues WHERE id = ?1",
+        vec![issue_id.to_string().into()],
+    )
+    .await?
+        > 0;
+    if !issue_exists {
+        return Ok(IssueAddLabelPayload { success: false });
     }
 
-    let where_sql = if clauses.is_empty() {
-        String::new()
+    // Leniently, an unknown id is taken as a label name: an existing label
+    // with that name is reused, otherwise one is created as labelCreate would.
+    let existing: Option<IdRow> = if strict {
+        fetch_one(
+            conn,
+            "SELECT id FROM labels WHERE id = ?1",
+            vec![label_id.to_string().into()],
+        )
+        .await?
     } else {
-        format!(" WHERE {}", clauses.join(" AND "))
+        fetch_one(
+            conn,
+            "SELECT id FROM labels WHERE id = ?1 OR name = ?1 ORDER BY id = ?1 DESC, id LIMIT 1",
+            vec![label_id.to_string().into()],
+        )
+        .await?
     };
-    let sql = format!(
-        "{}{} ORDER BY i.updated_at DESC LIMIT ?",
-        issue_base_select(),
-        where_sql
-    );
-    params.push(i64::from(limit).into());
-    let rows: Vec<IssueBaseRow> = fetch_all(conn, &sql, params).await?;
-    let mut issues = Vec::with_capacity(rows.len());
-    for row in rows {
-        issues.push(issue_from_row(conn, row).await?);
+    let label_id = match existing {
+        Some(row) => row.id,
+        None if strict => {
+            return Err(NotFoundError(format!("label not found: {label_id}")).into());
+        }
+        None => {
+            let input = LabelCreateInput {
+                name: label_id.to_string(),
+                color: None,
+            };
+            create_label(conn, input).await?.label.id
+        }
+    };
+    execute_retrying(
+        conn,
+        retries,
+        "INSERT OR IGNORE INTO issue_labels (issue_id, label_id) VALUES (?1, ?2)",
+        vals(vec![issue_id.to_string().into(), label_id.into()]),
+    )
+    .await?;
+
+    Ok(IssueAddLabelPayload { success: true })
+}
+
+async fn create_label(conn: &Connection, input: LabelCreateInput) -> Result<LabelCreatePayload> {
+    if input.name.trim().is_empty() {
+        return Err(ValidationError("label name must not be empty".to_string()).into());
     }
-    Ok(IssueConnection { nodes: issues })
+    if let Some(color) = &input.color {
+        validate_hex_color(color)?;
+    }
+    let label = Label {
+        id: format!("label_{}", short_id()),
+        name: input.name,
+        color: input.color,
+    };
+    conn.execute(
+        "INSERT INTO labels (id, name, color) VALUES (?1, ?2, ?3)",
+        vals(vec![
+            label.id.clone().into(),
+            label.name.clone().into(),
+            option_string_to_value(label.color.clone()),
+        ]),
+    )
+    .await?;
+    Ok(LabelCreatePayload {
+        success: true,
+        label,
+    })
 }
 
-async fn list_workflow_states(
+// Only detaches the label; the label row stays for other issues.
+async fn remove_label(
     conn: &Connection,
-    filter: Option<WorkflowStatesFilter>,
-) -> Result<WorkflowStateConnection> {
-    let mut params: Vec<Value> = Vec::new();
-    let mut where_sql = String::new();
-    if let Some(team_id) = filter
-        .and_then(|f| f.team)
-        .and_then(|t| t.id)
-        .and_then(|id| id.eq)
-    {
-        where_sql = " WHERE team_id = ?1".to_string();
-        params.push(team_id.into());
-    }
-    let sql = format!(
-        "SELECT id, name, type AS state_type FROM workflow_states{} ORDER BY position ASC",
-        where_sql
-    );
-    let rows: Vec<WorkflowStateRow> = fetch_all(conn, &sql, params).await?;
-    Ok(WorkflowStateConnection {
-        nodes: rows.into_iter().map(WorkflowState::from).collect(),
+    retries: u32,
+    issue_id: &str,
+    label_id: &str,
+) -> Result<IssueRemoveLabelPayload> {
+    let removed = execute_retrying(
+        conn,
+        retries,
+        "DELETE FROM issue_labels WHERE issue_id = ?1 AND label_id = ?2",
+        vals(vec![
+            issue_id.to_string().into(),
+            label_id.to_string().into(),
+        ]),
+    )
+    .await?;
+    Ok(IssueRemoveLabelPayload {
+        success: removed > 0,
     })
 }
 
-async fn create_project(
+async fn import_project_1to1(
     conn: &Connection,
-    base_url: &str,
-    input: ProjectCreateInput,
-) -> Result<ProjectCreatePayload> {
-    if input.team_ids.is_empty() {
-        return Err(anyhow::anyhow!("teamIds must contain at least one team id"));
-    }
-    for team_id in &input.team_ids {
-        let exists = count(
-            conn,
-            "SELECT COUNT(*) as value FROM teams WHERE id = ?1",
-            vec![team_id.clone().into()],
-        )
-        .await?;
-        if exists == 0 {
-            return Err(anyhow::anyhow!("team not found: {team_id}"));
-        }
-    }
+    input: AdminImportProjectInput,
+) -> Result<AdminImportProjectPayload> {
+    conn.execute(
+        "DELETE FROM projects WHERE slug_id = ?1 AND id <> ?2",
+        vals(vec![input.slug_id.clone().into(), input.id.clone().into()]),
+    )
+    .await?;
 
-    let project_id = format!("project_{}", short_id());
-    let slug = next_project_slug(conn, &input.name).await?;
-    let now = now_iso();
-    let url = format!("{}/project/{}", trim_trailing_slash(base_url), project_id);
     conn.execute(
         "INSERT INTO projects (id, name, slug_id, state, archived_at, url, created_at)
-         VALUES (?1, ?2, ?3, ?4, NULL, ?5, ?6)",
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(id) DO UPDATE SET
+           name = excluded.name,
+           slug_id = excluded.slug_id,
+           state = excluded.state,
+           archived_at = excluded.archived_at,
+           url = excluded.url",
         vals(vec![
-            project_id.clone().into(),
+            input.id.clone().into(),
             input.name.clone().into(),
-            slug.clone().into(),
-            "planned".into(),
-            url.clone().into(),
-            now.into(),
+            input.slug_id.clone().into(),
+            option_string_to_value(input.state.clone()),
+            option_string_to_value(input.archived_at.clone()),
+            input.url.clone().into(),
+            now_iso().into(),
         ]),
     )
     .await?;
 
-    for team_id in input.team_ids {
-        conn.execute(
-            "INSERT OR IGNORE INTO project_teams (project_id, team_id) VALUES (?1, ?2)",
-            vals(vec![project_id.clone().into(), team_id.into()]),
-        )
-        .await?;
-    }
-
-    let project = Project {
-        id: project_id,
-        name: input.name,
-        slug_id: Some(slug),
-        state: Some("planned".to_string()),
-        archived_at: None,
-        url: Some(url),
-    };
-
-    Ok(ProjectCreatePayload {
+    let project = get_project(conn, &input.id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("failed to load imported project"))?;
+    Ok(AdminImportProjectPayload {
         success: true,
         project,
     })
 }
 
-async fn create_issue(
+// Keeps the exported number and identifier instead of allocating new ones, so
+// later `issueCreate` calls continue after the highest imported number.
+// The team, state and numbering checks `adminImportIssue` and `/import` share.
+async fn check_imported_issue(
     conn: &Connection,
-    base_url: &str,
-    input: IssueCreateInput,
-) -> Result<IssueCreatePayload> {
-    let team: TeamRow = fetch_one(
+    id: &str,
+    team_id: &str,
+    state_id: &str,
+    number: i64,
+    identifier: &str,
+) -> Result<()> {
+    if number < 1 {
+        return Err(ValidationError("number must be at least 1".to_string()).into());
+    }
+    let team_exists = count(
         conn,
-        "SELECT id, name, key FROM teams WHERE id = ?1",
-        vec![input.team_id.clone().into()],
+        "SELECT COUNT(*) as value FROM teams WHERE id = ?1",
+        vec![team_id.to_string().into()],
     )
-    .await?
-    .ok_or_else(|| anyhow::anyhow!("team not found: {}", input.team_id))?;
-
-    if let Some(ref project_id) = input.project_id {
-        let exists = count(
+    .await?;
+    if team_exists == 0 {
+        return Err(NotFoundError(format!("team not found: {team_id}")).into());
+    }
+    let state_in_team = count(
+        conn,
+        "SELECT COUNT(*) as value FROM workflow_states WHERE id = ?1 AND team_id = ?2",
+        vec![state_id.to_string().into(), team_id.to_string().into()],
+    )
+    .await?;
+    if state_in_team == 0 {
+        let state_exists = count(
             conn,
-            "SELECT COUNT(*) as value FROM projects WHERE id = ?1",
-            vec![project_id.clone().into()],
+            "SELECT COUNT(*) as value FROM workflow_states WHERE id = ?1",
+            vec![state_id.to_string().into()],
         )
         .await?;
-        if exists == 0 {
-            return Err(anyhow::anyhow!("project not found: {project_id}"));
+        if state_exists == 0 {
+            return Err(NotFoundError(format!("workflow state not found: {state_id}")).into());
         }
+        return Err(ValidationError(format!(
+            "workflow state {state_id} does not belong to team {team_id}"
+        ))
+        .into());
+    }
+    let identifier_taken = count(
+        conn,
+        "SELECT COUNT(*) as value FROM issues WHERE identifier = ?1 AND id <> ?2",
+        vec![identifier.to_string().into(), id.to_string().into()],
+    )
+    .await?;
+    if identifier_taken > 0 {
+        return Err(ValidationError(format!("identifier already in use: {identifier}")).into());
+    }
+    let number_taken = count(
+        conn,
+        "SELECT COUNT(*) as value FROM issues WHERE team_id = ?1 AND number = ?2 AND id <> ?3",
+        vec![
+            team_id.to_string().into(),
+            number.into(),
+            id.to_string().into(),
+        ],
+    )
+    .await?;
+    if number_taken > 0 {
+        return Err(ValidationError(format!(
+            "issue number {number} already in use in team {team_id}"
+        ))
+        .into());
     }
+    Ok(())
+}
 
-    let state: WorkflowStateRow = fetch_one(
+async fn import_issue_1to1(
+    conn: &Connection,
+    retries: u32,
+    input: AdminImportIssueInput,
+) -> Result<(AdminImportIssuePayload, bool)> {
+    check_imported_issue(
         conn,
-        "SELECT id, name, type AS state_type
-         FROM workflow_states
-         WHERE team_id = ?1
-         ORDER BY position ASC
-         LIMIT 1",
-        vec![team.id.clone().into()],
+        &input.id,
+        &input.team_id,
+        &input.state_id,
+        i64::from(input.number),
+        &input.identifier,
     )
-    .await?
-    .ok_or_else(|| anyhow::anyhow!("team {} has no workflow states", team.id))?;
+    .await?;
 
-    let next_number = count(
+    let existed = count(
         conn,
-        "SELECT COALESCE(MAX(number), 0) as value FROM issues WHERE team_id = ?1",
-        vec![team.id.clone().into()],
+        "SELECT COUNT(*) as value FROM issues WHERE id = ?1",
+        vec![input.id.clone().into()],
     )
     .await?
-        + 1;
-    let identifier = format!("{}-{next_number}", team.key);
-    let issue_id = format!("issue_{}", short_id());
-    let url = format!("{}/issue/{}", trim_trailing_slash(base_url), identifier);
-    let now = now_iso();
-    conn.execute(
+        > 0;
+
+    // Validated, but stored as given so exported values read back unchanged.
+    let timestamp = |value: &Option<String>| -> Result<Option<String>> {
+        value
+            .as_deref()
+            .map(|v| parse_timestamp(v).map(|_| v.trim().to_string()))
+            .transpose()
+    };
+    let created_at = timestamp(&input.created_at)?;
+    let updated_at = timestamp(&input.updated_at)?;
+    let completed_at = timestamp(&input.completed_at)?;
+    let archived_at = timestamp(&input.archived_at)?;
+
+    execute_retrying(
+        conn,
+        retries,
         "INSERT INTO issues
-         (id, team_id, project_id, number, identifier, title, description, state_id, assignee_id, archived, url, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, NULL, 0, ?9, ?10, ?11)",
+         (id, team_id, project_id, number, identifier, title, description, state_id, assignee_id, archived, url, created_at, updated_at, completed_at, archived_at, sort_order)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11,
+           COALESCE(?12, ?16), COALESCE(?13, ?12, ?16), ?14,
+           CASE WHEN ?10 THEN COALESCE(?15, ?13, ?12, ?16) END,
+           (SELECT COALESCE(MAX(sort_order), 0) + 1 FROM issues WHERE team_id = ?2))
+         ON CONFLICT(id) DO UPDATE SET
+           team_id = excluded.team_id,
+           project_id = excluded.project_id,
+           number = excluded.number,
+           identifier = excluded.identifier,
+           title = excluded.title,
+           description = excluded.description,
+           state_id = excluded.state_id,
+           assignee_id = excluded.assignee_id,
+           archived = excluded.archived,
+           url = excluded.url,
+           created_at = COALESCE(?12, issues.created_at),
+           updated_at = COALESCE(?13, ?16),
+           completed_at = excluded.completed_at,
+           archived_at = CASE WHEN ?10 THEN COALESCE(?15, issues.archived_at, ?13, ?16) END",
         vals(vec![
-            issue_id.clone().into(),
-            team.id.into(),
+            input.id.clone().into(),
+            input.team_id.clone().into(),
             option_string_to_value(input.project_id.clone()),
-            next_number.into(),
-            identifier.clone().into(),
+            i64::from(input.number).into(),
+            input.identifier.clone().into(),
             input.title.clone().into(),
             option_string_to_value(input.description.clone()),
-            state.id.clone().into(),
-            url.clone().into(),
-            now.clone().into(),
-            now.into(),
+            input.state_id.clone().into(),
+            option_string_to_value(input.assignee_id.clone()),
+            i64::from(input.archived.unwrap_or(false)).into(),
+            input.url.clone().into(),
+            option_string_to_value(created_at),
+            option_string_to_value(updated_at),
+            option_string_to_value(completed_at),
+            option_string_to_value(archived_at),
+            now_iso().into(),
         ]),
     )
     .await?;
 
-    let issue = get_issue(conn, &issue_id)
+    let issue = get_issue(conn, &input.id)
         .await?
-        .ok_or_else(|| anyhow::anyhow!("failed to load created issue"))?;
-    Ok(IssueCreatePayload {
+        .ok_or_else(|| anyhow::anyhow!("failed to load imported issue"))?;
+    Ok((
+        AdminImportIssuePayload {
+            success: true,
+            issue,
+        },
+        !existed,
+    ))
+}
+
+async fn run_read_only_query(conn: &Connection, sql: &str) -> Result<AdminQueryPayload> {
+    let statement = read_only_statement(sql)?;
+    let (columns, rows) = fetch_json_rows(conn, statement).await?;
+    Ok(AdminQueryPayload {
         success: true,
-        issue,
+        columns,
+        rows: rows.into_iter().map(Json).collect(),
     })
 }
 
-async fn create_comment(
+// Introspection only: most other pragmas change connection state even in
+// their `name(arg)` form, and pooled connections outlive the request.
+const READ_ONLY_PRAGMAS: [&str; 13] = [
+    "collation_list",
+    "compile_options",
+    "database_list",
+    "foreign_key_check",
+    "foreign_key_list",
+    "function_list",
+    "index_info",
+    "index_list",
+    "index_xinfo",
+    "module_list",
+    "pragma_list",
+    "table_info",
+    "table_list",
+];
+
+fn read_only_statement(sql: &str) -> Result<&str> {
+    let statement = single_statement(sql)
+        .ok_or_else(|| ValidationError("adminQuery accepts a single statement".to_string()))?;
+    let (keyword, rest) = statement
+        .split_once(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or((statement, ""));
+    let allowed = match keyword.to_ascii_uppercase().as_str() {
+        "SELECT" => true,
+        "PRAGMA" => {
+            let rest = rest.trim_start();
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            let (name, args) = rest.split_at(end);
+            let name = name.rsplit('.').next().unwrap_or_default();
+            let args = args.trim_start();
+            READ_ONLY_PRAGMAS.contains(&name.to_ascii_lowercase().as_str())
+                && (args.is_empty() || args.starts_with('('))
+        }
+        _ => false,
+    };
+    if !allowed {
+        return Err(ValidationError(
+            "adminQuery only accepts SELECT or introspection PRAGMA statements".to_string(),
+        )
+        .into());
+    }
+    Ok(statement)
+}
+
+// The statement without its trailing `;`, or `None` when `sql` is empty or
+// holds more than one statement. Semicolons inside quotes or comments don't
+// end a statement.
+fn single_statement(sql: &str) -> Option<&str> {
+    let bytes = sql.as_bytes();
+    // Index just past the first `close` at or after `from`, or the end of input.
+    let skip_past = |from: usize, close: &[u8]| {
+        bytes[from..]
+            .windows(close.len())
+            .position(|w| w == close)
+            .map_or(bytes.len(), |at| from + at + close.len())
+    };
+    let mut end = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        i = match bytes[i] {
+            b'-' if bytes[i..].starts_with(b"--") => skip_past(i + 2, b"\n"),
+            b'/' if bytes[i..].starts_with(b"/*") => skip_past(i + 2, b"*/"),
+            b';' => {
+                end.get_or_insert(i);
+                i + 1
+            }
+            c if c.is_ascii_whitespace() => i + 1,
+            _ if end.is_some() => return None,
+            quote @ (b'\'' | b'"' | b'`') => skip_past(i + 1, &[quote]),
+            b'[' => skip_past(i + 1, b"]"),
+            _ => i + 1,
+        };
+    }
+    let statement = sql[..end.unwrap_or(sql.len())].trim();
+    (!statement.is_empty()).then_some(statement)
+}
+
+async fn fetch_json_rows(
     conn: &Connection,
-    base_url: &str,
-    input: CommentCreateInput,
-) -> Result<CommentCreatePayload> {
-    let exists = count(
-        conn,
-        "SELECT COUNT(*) as value FROM issues WHERE id = ?1",
-        vec![input.issue_id.clone().into()],
-    )
-    .await?;
-    if exists == 0 {
-        return Err(anyhow::anyhow!("issue not found: {}", input.issue_id));
+    sql: &str,
+) -> Result<(Vec<String>, Vec<serde_json::Value>)> {
+    let mut rows = conn.query(sql, ()).await?;
+    let columns = (0..rows.column_count())
+        .map(|idx| rows.column_name(idx).unwrap_or_default().to_string())
+        .collect::<Vec<_>>();
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let mut object = serde_json::Map::with_capacity(columns.len());
+        for (idx, column) in (0_i32..).zip(&columns) {
+            object.insert(column.clone(), sql_value_to_json(row.get_value(idx)?));
+        }
+        out.push(serde_json::Value::Object(object));
+    }
+    Ok((columns, out))
+}
+
+// Tables in dependency order; `/import` upserts them in this order and `/export` emits one key per table.
+const BUNDLE_TABLES: [&str; 16] = [
+    "organization",
+    "users",
+    "teams",
+    "team_members",
+    "workflow_states",
+    "projects",
+    "project_teams",
+    "project_milestones",
+    "cycles",
+    "issues",
+    "labels",
+    "issue_labels",
+    "comments",
+    "reactions",
+    "attachments",
+    "favorites",
+];
+
+async fn export_bundle(conn: &Connection) -> Result<serde_json::Value> {
+    let mut bundle = serde_json::Map::new();
+    for table in BUNDLE_TABLES {
+        let (_, rows) = fetch_json_rows(conn, &format!("SELECT * FROM {table}")).await?;
+        bundle.insert(table.to_string(), serde_json::Value::Array(rows));
+    }
+    Ok(serde_json::Value::Object(bundle))
+}
+
+fn snapshot_path(app: &AppContext, name: &str) -> Result<String> {
+    if !app.allow_snapshots {
+        return Err(FeatureNotAccessibleError(
+            "snapshots are disabled (set SUBLINEAR_ALLOW_SNAPSHOTS=true to enable)".to_string(),
+        )
+        .into());
+    }
+    if app.remote_db {
+        return Err(FeatureNotAccessibleError(
+            "snapshots are only supported for local file databases".to_string(),
+        )
+        .into());
+    }
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(ValidationError(format!(
+            "invalid snapshot name {name:?} (use letters, digits, '-' and '_')"
+        ))
+        .into());
+    }
+    let path = std::path::Path::new(&app.snapshot_dir).join(format!("{name}.db"));
+    Ok(path.to_string_lossy().into_owned())
+}
+
+async fn save_snapshot(conn: &Connection, path: &str) -> Result<()> {
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create snapshot dir {}", dir.display()))?;
+    }
+    // VACUUM INTO refuses to overwrite, so saving under an existing name replaces it.
+    if std::path::Path::new(path).exists() {
+        std::fs::remove_file(path).with_context(|| format!("failed to replace {path}"))?;
+    }
+    conn.execute("VACUUM INTO ?1", vals(vec![path.to_string().into()]))
+        .await
+        .with_context(|| format!("failed to write snapshot {path}"))?;
+    Ok(())
+}
+
+// The live file can't be swapped under the open connection, so restore copies
+// every bundle table back from the attached snapshot in one transaction.
+async fn restore_snapshot(conn: &Connection, path: &str) -> Result<()> {
+    if !std::path::Path::new(path).exists() {
+        return Err(NotFoundError(format!("snapshot not found: {path}")).into());
     }
-    let comment_id = format!("comment_{}", short_id());
-    let url = format!("{}/comment/{}", trim_trailing_slash(base_url), comment_id);
-    let now = now_iso();
     conn.execute(
-        "INSERT INTO comments (id, issue_id, body, url, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-        vals(vec![
-            comment_id.clone().into(),
-            input.issue_id.into(),
-            input.body.clone().into(),
-            url.clone().into(),
-            now.into(),
-        ]),
+        "ATTACH DATABASE ?1 AS snapshot",
+        vals(vec![path.to_string().into()]),
     )
-    .await?;
-    Ok(CommentCreatePayload {
-        success: true,
-        comment: Comment {
-            id: comment_id,
-            body: input.body,
-            url,
-        },
-    })
+    .await
+    .with_context(|| format!("failed to open snapshot {path}"))?;
+    let result = copy_snapshot_tables(conn).await;
+    conn.execute("DETACH DATABASE snapshot", ()).await?;
+    result
 }
 
-async fn update_issue(
-    conn: &Connection,
-    issue_id: &str,
-    input: IssueUpdateInput,
-) -> Result<IssueUpdatePayload> {
-    let mut sets: Vec<String> = Vec::new();
-    let mut params: Vec<Value> = Vec::new();
-
-    if let Some(title) = input.title {
-        sets.push("title = ?".to_string());
-        params.push(title.into());
+async fn copy_snapshot_tables(conn: &Connection) -> Result<()> {
+    let tx = conn.transaction().await?;
+    let mut copies = Vec::with_capacity(BUNDLE_TABLES.len());
+    for table in BUNDLE_TABLES {
+        let live: Vec<ColumnInfoRow> =
+            fetch_all(&tx, &format!("PRAGMA main.table_info({table})"), vec![]).await?;
+        let saved: Vec<ColumnInfoRow> =
+            fetch_all(&tx, &format!("PRAGMA snapshot.table_info({table})"), vec![]).await?;
+        let columns = saved
+            .iter()
+            .filter(|c| live.iter().any(|l| l.name == c.name))
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        // Tables the snapshot predates keep their live rows.
+        if !columns.is_empty() {
+            copies.push((table, columns));
+        }
     }
-    if let Some(description) = input.description {
-        sets.push("description = ?".to_string());
-        params.push(description.into());
+    for (table, _) in &copies {
+        tx.execute(&format!("DELETE FROM main.{table}"), ()).await?;
     }
-    if let Some(state_id) = input.state_id {
-        sets.push("state_id = ?".to_string());
-        params.push(state_id.into());
+    for (table, columns) in &copies {
+        tx.execute(
+            &format!("INSERT INTO main.{table} ({columns}) SELECT {columns} FROM snapshot.{table}"),
+            (),
+        )
+        .await?;
     }
-    sets.push("updated_at = ?".to_string());
-    params.push(now_iso().into());
+    tx.commit().await?;
+    Ok(())
+}
+
+// Rows go through the same checks as the `adminImport*` mutations, and the
+// counts say how many rows of each table were created and updated.
+async fn import_bundle(conn: &Connection, bundle: &serde_json::Value) -> Result<serde_json::Value> {
+    let bundle = bundle.as_object().ok_or_else(|| {
+        ValidationError("import bundle must be a JSON object keyed by table".to_string())
+    })?;
+    if let Some(unknown) = bundle.keys().find(|k| !BUNDLE_TABLES.contains(&k.as_str())) {
+        return Err(ValidationError(format!("unknown table in import bundle: {unknown}")).into());
+    }
+
+    let tx = conn.transaction().await?;
+    let mut counts = serde_json::Map::new();
+    for table in BUNDLE_TABLES {
+        let Some(rows) = bundle.get(table) else {
+            continue;
+        };
+        let rows = rows
+            .as_array()
+            .ok_or_else(|| ValidationError(format!("{table} must be an array of rows")))?
+            .iter()
+            .map(|row| {
+                row.as_object()
+                    .cloned()
+                    .ok_or_else(|| ValidationError(format!("{table} rows must be JSON objects")))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let known: Vec<ColumnInfoRow> =
+            fetch_all(&tx, &format!("PRAGMA table_info({table})"), vec![]).await?;
+        let key = primary_key(&known);
+        if table == "labels" {
+            let links = rows
+                .iter()
+                .map(|row| {
+                    let parent = row.get("parent_id").and_then(|v| v.as_str());
+                    Ok((bundle_text(table, row, "id")?, parent.map(str::to_string)))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            ensure_acyclic_labels(&tx, &links).await?;
+        }
 
-    params.push(issue_id.to_string().into());
-    let sql = format!("UPDATE issues SET {} WHERE id = ?", sets.join(", "));
-    let changed = conn.execute(&sql, params).await?;
-    if changed == 0 {
-        return Err(anyhow::anyhow!("issue not found: {issue_id}"));
+        let (mut created, mut updated) = (0, 0);
+        for mut row in rows {
+            match table {
+                "teams" => {
+                    let key = sanitize_team_key(&bundle_text(table, &row, "key")?);
+                    ensure_team_key_free(&tx, &key, &bundle_text(table, &row, "id")?).await?;
+                    row.insert("key".to_string(), key.into());
+                }
+                "workflow_states" => merge_seeded_workflow_state(&tx, &row).await?,
+                "issues" => {
+                    let number = row
+                        .get("number")
+                        .and_then(|v| v.as_i64())
+                        .ok_or_else(|| ValidationError("issues rows must include number".into()))?;
+                    check_imported_issue(
+                        &tx,
+                        &bundle_text(table, &row, "id")?,
+                        &bundle_text(table, &row, "team_id")?,
+                        &bundle_text(table, &row, "state_id")?,
+                        number,
+                        &bundle_text(table, &row, "identifier")?,
+                    )
+                    .await?;
+                }
+                _ => {}
+            }
+
+            let mut columns = Vec::with_capacity(row.len());
+            let mut params = Vec::with_capacity(row.len());
+            for (column, value) in &row {
+                if known.iter().all(|c| &c.name != column) {
+                    return Err(ValidationError(format!("unknown column {table}.{column}")).into());
+                }
+                columns.push(column.as_str());
+                params.push(json_to_sql_value(value).map_err(|err| {
+                    ValidationError(format!("invalid value for {table}.{column}: {err}"))
+                })?);
+            }
+            if let Some(missing) = key.iter().find(|k| !columns.contains(k)) {
+                return Err(ValidationError(format!("{table} rows must include {missing}")).into());
+            }
+            let key_params = key
+                .iter()
+                .map(|k| json_to_sql_value(&row[*k]))
+                .collect::<Result<Vec<_>>>()?;
+            let key_sql = key
+                .iter()
+                .enumerate()
+                .map(|(n, k)| format!("{k} = ?{}", n + 1))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            let existed = count(
+                &tx,
+                &format!("SELECT COUNT(*) as value FROM {table} WHERE {key_sql}"),
+                key_params,
+            )
+            .await?
+                > 0;
+            tx.execute(&bundle_upsert_sql(table, &key, &columns), params)
+                .await
+                .map_err(|err| match err {
+                    // Constraint failures come from the bundle's own rows.
+                    libsql::Error::SqliteFailure(code, message) if code & 0xff == 19 => {
+                        ValidationError(format!("failed to import {table} row: {message}")).into()
+                    }
+                    err => anyhow::Error::new(err).context(format!("failed to import {table} row")),
+                })?;
+            if existed {
+                updated += 1;
+            } else {
+                created += 1;
+            }
+        }
+        counts.insert(
+            table.to_string(),
+            serde_json::json!({ "created": created, "updated": updated }),
+        );
     }
+    tx.commit().await?;
 
-    let issue = get_issue(conn, issue_id)
-        .await?
-        .ok_or_else(|| anyhow::anyhow!("failed to load updated issue"))?;
-    Ok(IssueUpdatePayload {
-        success: true,
-        issue,
-    })
+    Ok(serde_json::json!({ "success": true, "counts": counts }))
 }
 
-async fn archive_issue(conn: &Connection, issue_id: &str) -> Result<IssueArchivePayload> {
-    let changed = conn
-        .execute(
-            "UPDATE issues SET archived = 1, updated_at = ?1 WHERE id = ?2",
-            vals(vec![now_iso().into(), issue_id.to_string().into()]),
-        )
-        .await?;
-    Ok(IssueArchivePayload {
-        success: changed > 0,
-    })
+fn bundle_text(
+    table: &str,
+    row: &serde_json::Map<String, serde_json::Value>,
+    column: &str,
+) -> Result<String> {
+    Ok(row
+        .get(column)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ValidationError(format!("{table} rows must include {column}")))?
+        .to_string())
 }
 
-async fn add_label(
-    conn: &Connection,
-    issue_id: &str,
-    label_id: &str,
-) -> Result<IssueAddLabelPayload> {
-    let issue_exists = count(
-        conn,
-        "SELECT COUNT(*) as value FROM issues WHERE id = ?1",
-        vec![issue_id.to_string().into()],
+fn primary_key(known: &[ColumnInfoRow]) -> Vec<&str> {
+    let mut key: Vec<&ColumnInfoRow> = known.iter().filter(|c| c.pk > 0).collect();
+    key.sort_by_key(|c| c.pk);
+    key.iter().map(|c| c.name.as_str()).collect()
+}
+
+// An upsert on the primary key rather than `INSERT OR REPLACE`: REPLACE deletes
+// the old row without firing delete triggers, which would strand its FTS entry.
+fn bundle_upsert_sql(table: &str, key: &[&str], columns: &[&str]) -> String {
+    let updates: Vec<String> = columns
+        .iter()
+        .filter(|c| !key.contains(c))
+        .map(|c| format!("{c} = excluded.{c}"))
+        .collect();
+    let action = if updates.is_empty() {
+        "NOTHING".to_string()
+    } else {
+        format!("UPDATE SET {}", updates.join(", "))
+    };
+    format!(
+        "INSERT INTO {table} ({}) VALUES ({}) ON CONFLICT({}) DO {action}",
+        columns.join(", "),
+        placeholders(columns.len()),
+        key.join(", ")
     )
-    .await?
-        > 0;
-    if !issue_exists {
-        return Ok(IssueAddLabelPayload { success: false });
-    }
+}
 
+// Seeded states get random ids per instance, so an imported state replaces the local one with the
+// same team and name (repointing its issues) instead of duplicating the column.
+async fn merge_seeded_workflow_state(
+    conn: &Connection,
+    row: &serde_json::Map<String, serde_json::Value>,
+) -> Result<()> {
+    let field = |name: &str| row.get(name).and_then(|v| v.as_str()).map(str::to_string);
+    let (Some(id), Some(team_id), Some(name)) = (field("id"), field("team_id"), field("name"))
+    else {
+        return Ok(());
+    };
     conn.execute(
-        "INSERT OR IGNORE INTO labels (id, name) VALUES (?1, ?2)",
-        vals(vec![
-            label_id.to_string().into(),
-            label_id.to_string().into(),
-        ]),
+        "UPDATE issues SET state_id = ?1
+         WHERE state_id IN (SELECT id FROM workflow_states WHERE team_id = ?2 AND name = ?3 AND id <> ?1)",
+        vals(vec![id.clone().into(), team_id.clone().into(), name.clone().into()]),
     )
     .await?;
     conn.execute(
-        "INSERT OR IGNORE INTO issue_labels (issue_id, label_id) VALUES (?1, ?2)",
-        vals(vec![
-            issue_id.to_string().into(),
-            label_id.to_string().into(),
-        ]),
+        "DELETE FROM workflow_states WHERE team_id = ?2 AND name = ?3 AND id <> ?1",
+        vals(vec![id.into(), team_id.into(), name.into()]),
     )
     .await?;
+    Ok(())
+}
 
-    Ok(IssueAddLabelPayload { success: true })
+fn json_to_sql_value(value: &serde_json::Value) -> Result<Value> {
+    Ok(match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(v) => Value::Integer(i64::from(*v)),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(v) => Value::Integer(v),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(v) => Value::Text(v.clone()),
+        serde_json::Value::Array(items) => Value::Blob(
+            items
+                .iter()
+                .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+                .collect::<Option<Vec<u8>>>()
+                .ok_or_else(|| anyhow::anyhow!("blob arrays must contain bytes"))?,
+        ),
+        serde_json::Value::Object(_) => {
+            return Err(anyhow::anyhow!("nested objects are not supported"));
+        }
+    })
 }
 
-async fn import_project_1to1(
-    conn: &Connection,
-    input: AdminImportProjectInput,
-) -> Result<AdminImportProjectPayload> {
-    conn.execute(
-        "DELETE FROM projects WHERE slug_id = ?1 AND id <> ?2",
-        vals(vec![input.slug_id.clone().into(), input.id.clone().into()]),
+fn sql_value_to_json(value: Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(v) => v.into(),
+        Value::Real(v) => v.into(),
+        Value::Text(v) => v.into(),
+        Value::Blob(v) => v.into(),
+    }
+}
+
+async fn ensure_team_key_free(conn: &Connection, key: &str, team_id: &str) -> Result<()> {
+    let key_taken = count(
+        conn,
+        "SELECT COUNT(*) as value FROM teams WHERE key = ?1 AND id <> ?2",
+        vec![key.to_string().into(), team_id.to_string().into()],
     )
     .await?;
+    if key_taken > 0 {
+        return Err(ValidationError(format!("team key already in use: {key}")).into());
+    }
+    Ok(())
+}
+
+async fn import_team_1to1(
+    conn: &Connection,
+    input: AdminImportTeamInput,
+) -> Result<AdminImportTeamPayload> {
+    let key = sanitize_team_key(&input.key);
+    let starting_number = input.starting_number.unwrap_or(1);
+    if starting_number < 1 {
+        return Err(ValidationError("startingNumber must be at least 1".to_string()).into());
+    }
+    ensure_team_key_free(conn, &key, &input.id).await?;
 
     conn.execute(
-        "INSERT INTO projects (id, name, slug_id, state, archived_at, url, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "INSERT INTO teams (id, name, key, starting_number, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
          ON CONFLICT(id) DO UPDATE SET
            name = excluded.name,
-           slug_id = excluded.slug_id,
-           state = excluded.state,
-           archived_at = excluded.archived_at,
-           url = excluded.url",
+           key = excluded.key,
+           starting_number = excluded.starting_number",
         vals(vec![
             input.id.clone().into(),
             input.name.clone().into(),
-            input.slug_id.clone().into(),
-            option_string_to_value(input.state.clone()),
-            option_string_to_value(input.archived_at.clone()),
-            input.url.clone().into(),
+            key.into(),
+            i64::from(starting_number).into(),
             now_iso().into(),
         ]),
     )
     .await?;
+    for (name, kind, position, color) in DEFAULT_WORKFLOW_STATES {
+        ensure_workflow_state(conn, &input.id, name, kind, position, color).await?;
+    }
 
-    let project = get_project(conn, &input.id)
+    let team = get_team(conn, &input.id)
         .await?
-        .ok_or_else(|| anyhow::anyhow!("failed to load imported project"))?;
-    Ok(AdminImportProjectPayload {
+        .ok_or_else(|| anyhow::anyhow!("failed to load imported team"))?;
+    Ok(AdminImportTeamPayload {
         success: true,
-        project,
+        team,
     })
 }
 
-async fn issue_from_row(conn: &Connection, row: IssueBaseRow) -> Result<Issue> {
-    let label_rows: Vec<LabelRow> = fetch_all(
+#[derive(Deserialize)]
+struct LabelParentRow {
+    id: String,
+    parent_id: Option<String>,
+}
+
+// Checks `links` (label id, new parent) against the hierarchy as it will look
+// once they are written.
+async fn ensure_acyclic_labels(
+    conn: &Connection,
+    links: &[(String, Option<String>)],
+) -> Result<()> {
+    let existing: Vec<LabelParentRow> = fetch_all(
         conn,
-        "SELECT l.id, l.name
-         FROM labels l
-         INNER JOIN issue_labels il ON il.label_id = l.id
-         WHERE il.issue_id = ?1
-         ORDER BY l.name ASC",
-        vec![row.id.clone().into()],
+        "SELECT id, parent_id FROM labels WHERE parent_id IS NOT NULL",
+        vec![],
+    )
+    .await?;
+    let mut parents: HashMap<String, String> = existing
+        .into_iter()
+        .filter_map(|row| row.parent_id.map(|parent| (row.id, parent)))
+        .collect();
+    for (id, parent) in links {
+        match parent {
+            Some(parent) => parents.insert(id.clone(), parent.clone()),
+            None => parents.remove(id),
+        };
+    }
+    for (id, _) in links {
+        let mut seen = vec![id.clone()];
+        let mut current = parents.get(id);
+        while let Some(parent) = current {
+            if seen.contains(parent) {
+                return Err(ValidationError(format!(
+                    "cyclic parent reference involving label {id}"
+                ))
+                .into());
+            }
+            seen.push(parent.clone());
+            current = parents.get(parent);
+        }
+    }
+    Ok(())
+}
+
+async fn import_labels_1to1(
+    conn: &Connection,
+    retries: u32,
+    inputs: Vec<AdminImportLabelInput>,
+) -> Result<AdminImportLabelsPayload> {
+    let mut batch_ids: Vec<String> = Vec::new();
+    for input in &inputs {
+        if batch_ids.contains(&input.id) {
+            return Err(
+                ValidationError(format!("duplicate label id in import: {}", input.id)).into(),
+            );
+        }
+        batch_ids.push(input.id.clone());
+        if let Some(color) = &input.color {
+            validate_hex_color(color)?;
+        }
+    }
+
+    let team_ids: Vec<String> = inputs.iter().filter_map(|i| i.team_id.clone()).collect();
+    let missing = missing_ids(conn, "teams", &team_ids).await?;
+    if !missing.is_empty() {
+        return Err(NotFoundError(format!("teams not found: {}", missing.join(", "))).into());
+    }
+    let external_parents: Vec<String> = inputs
+        .iter()
+        .filter_map(|i| i.parent_id.clone())
+        .filter(|id| !batch_ids.contains(id))
+        .collect();
+    let missing = missing_ids(conn, "labels", &external_parents).await?;
+    if !missing.is_empty() {
+        return Err(
+            NotFoundError(format!("parent labels not found: {}", missing.join(", "))).into(),
+        );
+    }
+
+    let links: Vec<(String, Option<String>)> = inputs
+        .iter()
+        .map(|i| (i.id.clone(), i.parent_id.clone()))
+        .collect();
+    ensure_acyclic_labels(conn, &links).await?;
+
+    let existing_ids = batch_ids.len() - missing_ids(conn, "labels", &batch_ids).await?.len();
+    let inputs = &inputs;
+    retry_busy(retries, || async move {
+        let tx = conn.transaction().await?;
+        for input in inputs {
+            tx.execute(
+                "INSERT INTO labels (id, name, color, parent_id, team_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(id) DO UPDATE SET
+                   name = excluded.name,
+                   color = excluded.color,
+                   parent_id = excluded.parent_id,
+                   team_id = excluded.team_id",
+                vals(vec![
+                    input.id.clone().into(),
+                    input.name.clone().into(),
+                    option_string_to_value(input.color.clone()),
+                    option_string_to_value(input.parent_id.clone()),
+                    option_string_to_value(input.team_id.clone()),
+                ]),
+            )
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    })
+    .await?;
+
+    Ok(AdminImportLabelsPayload {
+        success: true,
+        created_count: (batch_ids.len() - existing_ids) as i32,
+        updated_count: existing_ids as i32,
+    })
+}
+
+#[derive(Deserialize)]
+struct IssueCountRow {
+    issue_id: String,
+    value: i64,
+}
+
+// One windowed pass over the page's state columns rather than a count per issue.
+async fn rank_in_state_columns(conn: &Connection, rows: &mut [IssueBaseRow]) -> Result<()> {
+    let mut states: Vec<String> = rows
+        .iter()
+        .filter(|r| r.archived == 0)
+        .filter_map(|r| r.ws_id.clone())
+        .collect();
+    states.sort();
+    states.dedup();
+    if states.is_empty() {
+        return Ok(());
+    }
+    let mut query = QueryBuilder::default();
+    let states = query.bind_list(states);
+    let ids = query.bind_list(rows.iter().map(|r| r.id.clone()));
+    let ranks: HashMap<String, i64> = fetch_all::<IssueCountRow>(
+        conn,
+        &format!(
+            "SELECT issue_id, value FROM (
+               SELECT id AS issue_id,
+                      ROW_NUMBER() OVER (PARTITION BY state_id ORDER BY sort_order, id) - 1 AS value
+               FROM issues
+               WHERE archived = 0 AND state_id IN ({states})
+             ) WHERE issue_id IN ({ids})"
+        ),
+        query.into_params(),
+    )
+    .await?
+    .into_iter()
+    .map(|r| (r.issue_id, r.value))
+    .collect();
+    for row in rows {
+        row.state_column_rank = ranks.get(&row.id).copied();
+    }
+    Ok(())
+}
+
+async fn issues_from_rows(conn: &Connection, rows: Vec<IssueBaseRow>) -> Result<Vec<Issue>> {
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut query = QueryBuilder::default();
+    let ids = query.bind_list(rows.iter().map(|r| r.id.clone()));
+    let params = query.into_params();
+    let comment_counts: HashMap<String, i64> = fetch_all::<IssueCountRow>(
+        conn,
+        &format!(
+            "SELECT issue_id, COUNT(*) as value FROM comments WHERE issue_id IN ({ids}) GROUP BY issue_id"
+        ),
+        params.clone(),
+    )
+    .await?
+    .into_iter()
+    .map(|r| (r.issue_id, r.value))
+    .collect();
+
+    let mut labels_by_issue: HashMap<String, Vec<Label>> = HashMap::new();
+    let label_rows: Vec<IssueLabelRow> = fetch_all(
+        conn,
+        &format!(
+            "SELECT il.issue_id, l.id, l.name, l.color
+             FROM labels l
+             INNER JOIN issue_labels il ON il.label_id = l.id
+             WHERE il.issue_id IN ({ids})
+             ORDER BY l.name ASC"
+        ),
+        params,
     )
     .await?;
+    for row in label_rows {
+        labels_by_issue
+            .entry(row.issue_id)
+            .or_default()
+            .push(Label {
+                id: row.id,
+                name: row.name,
+                color: row.color,
+            });
+    }
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let comment_count = comment_counts.get(&row.id).copied().unwrap_or(0);
+            let labels = labels_by_issue.remove(&row.id).unwrap_or_default();
+            issue_from_row(row, labels, comment_count)
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct IssueLabelRow {
+    issue_id: String,
+    id: String,
+    name: String,
+    color: Option<String>,
+}
+
+fn issue_from_row(row: IssueBaseRow, label_nodes: Vec<Label>, comment_count: i64) -> Issue {
     let labels = LabelConnection {
-        nodes: label_rows
-            .into_iter()
-            .map(|l| Label {
-                id: l.id,
-                name: l.name,
-            })
-            .collect(),
+        page_info: page_info(&label_nodes, false, false, LABEL_CURSOR_SCOPE, |l| {
+            vec![l.name.clone(), l.id.clone()]
+        }),
+        nodes: label_nodes,
     };
 
     let state = WorkflowState {
@@ -1504,6 +8985,8 @@ async fn issue_from_row(conn: &Connection, row: IssueBaseRow) -> Result<Issue> {
             .unwrap_or_else(|| "state_missing".to_string()),
         name: row.ws_name.clone().unwrap_or_else(|| "Backlog".to_string()),
         r#type: row.ws_type.clone(),
+        position: row.ws_position.unwrap_or_default() as i32,
+        color: row.ws_color.clone(),
     };
 
     let project = row.p_id.map(|id| Project {
@@ -1511,8 +8994,25 @@ async fn issue_from_row(conn: &Connection, row: IssueBaseRow) -> Result<Issue> {
         name: row.p_name.unwrap_or_default(),
         slug_id: row.p_slug_id,
         state: row.p_state,
+        description: row.p_description,
         archived_at: row.p_archived_at,
         url: row.p_url,
+        sort_order: row.p_sort_order.unwrap_or_default(),
+    });
+
+    let project_milestone = row.pm_id.map(|id| ProjectMilestone {
+        id,
+        name: row.pm_name.unwrap_or_default(),
+        sort_order: row.pm_sort_order.unwrap_or_default(),
+    });
+
+    let cycle = row.c_id.map(|id| Cycle {
+        id,
+        number: row.c_number.unwrap_or_default() as i32,
+        name: row.c_name,
+        starts_at: row.c_starts_at.unwrap_or_default(),
+        ends_at: row.c_ends_at.unwrap_or_default(),
+        created_at: row.c_created_at.unwrap_or_default(),
     });
 
     let assignee = row.u_id.map(|id| User {
@@ -1521,44 +9021,117 @@ async fn issue_from_row(conn: &Connection, row: IssueBaseRow) -> Result<Issue> {
         email: row.u_email.unwrap_or_default(),
     });
 
-    Ok(Issue {
+    let creator = row.cu_id.map(|id| User {
+        id,
+        name: row.cu_name.unwrap_or_default(),
+        email: row.cu_email.unwrap_or_default(),
+    });
+
+    let team_key = row
+        .identifier
+        .rsplit_once('-')
+        .map(|(prefix, _)| prefix.to_string())
+        .unwrap_or_default();
+    let team = Team {
+        id: row.t_id,
+        name: row.t_name.unwrap_or_default(),
+        key: row.t_key.unwrap_or_else(|| team_key.clone()),
+    };
+
+    Issue {
         id: row.id,
         identifier: row.identifier,
+        number: row.number as i32,
+        parent_id: row.parent_id,
+        team_key,
         title: row.title,
         url: row.url,
         description: row.description,
+        team,
         assignee,
+        creator,
         project,
+        project_milestone,
+        cycle,
         state,
         labels,
+        created_at: row.created_at,
         updated_at: row.updated_at,
-    })
+        completed_at: row.completed_at,
+        archived: row.archived != 0,
+        archived_at: row.archived_at,
+        priority: row.priority as i32,
+        priority_label: priority_label(row.priority).to_string(),
+        estimate: row.estimate,
+        due_date: row.due_date,
+        sort_order: row.sort_order,
+        state_column_rank: row.state_column_rank.map(|r| r as i32),
+        comment_count: comment_count as i32,
+    }
 }
 
+// The joins `list_issues` filters reference, without the select list.
+const ISSUE_COUNT_FROM: &str = "issues i
+     LEFT JOIN workflow_states ws ON ws.id = i.state_id
+     LEFT JOIN teams t ON t.id = i.team_id";
+
 fn issue_base_select() -> &'static str {
     "SELECT
        i.id,
        i.identifier,
+       i.number,
+       i.parent_id,
        i.title,
        i.url,
        i.description,
+       i.created_at,
        i.updated_at,
+       i.completed_at,
+       i.archived,
+       i.archived_at,
+       i.priority,
+       i.estimate,
+       i.due_date,
+       i.sort_order,
+       NULL AS state_column_rank,
        ws.id AS ws_id,
        ws.name AS ws_name,
        ws.type AS ws_type,
+       ws.position AS ws_position,
+       ws.color AS ws_color,
        p.id AS p_id,
        p.name AS p_name,
        p.slug_id AS p_slug_id,
        p.state AS p_state,
+       p.description AS p_description,
        p.archived_at AS p_archived_at,
        p.url AS p_url,
+       p.sort_order AS p_sort_order,
+       pm.id AS pm_id,
+       pm.name AS pm_name,
+       pm.sort_order AS pm_sort_order,
+       c.id AS c_id,
+       c.number AS c_number,
+       c.name AS c_name,
+       c.starts_at AS c_starts_at,
+       c.ends_at AS c_ends_at,
+       c.created_at AS c_created_at,
        u.id AS u_id,
        u.name AS u_name,
-       u.email AS u_email
+       u.email AS u_email,
+       cu.id AS cu_id,
+       cu.name AS cu_name,
+       cu.email AS cu_email,
+       i.team_id AS t_id,
+       t.name AS t_name,
+       t.key AS t_key
      FROM issues i
      LEFT JOIN workflow_states ws ON ws.id = i.state_id
      LEFT JOIN projects p ON p.id = i.project_id
+     LEFT JOIN project_milestones pm ON pm.id = i.project_milestone_id
+     LEFT JOIN cycles c ON c.id = i.cycle_id
      LEFT JOIN users u ON u.id = i.assignee_id
+     LEFT JOIN users cu ON cu.id = i.creator_id
      LEFT JOIN teams t ON t.id = i.team_id"
 }
 
@@ -1588,6 +9161,126 @@ fn option_string_to_value(v: Option<String>) -> Value {
     }
 }
 
+/// Collects WHERE clauses for the list queries. `bind` records a parameter and
+/// returns its numbered `?N` placeholder, so clauses can be added in any order
+/// and a value can be referenced more than once.
+#[derive(Clone, Default)]
+struct QueryBuilder {
+    clauses: Vec<String>,
+    params: Vec<Value>,
+}
+
+impl QueryBuilder {
+    fn bind(&mut self, value: impl Into<Value>) -> String {
+        self.params.push(value.into());
+        format!("?{}", self.params.len())
+    }
+
+    fn bind_list<V: Into<Value>>(&mut self, values: impl IntoIterator<Item = V>) -> String {
+        values
+            .into_iter()
+            .map(|v| self.bind(v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn push(&mut self, clause: impl Into<String>) {
+        self.clauses.push(clause.into());
+    }
+
+    fn push_string_filter(&mut self, column: &str, filter: &StringFilter) {
+        if let Some(eq) = &filter.eq {
+            let eq = self.bind(eq.clone());
+            self.push(format!("{column} = {eq}"));
+        }
+        if let Some(neq) = &filter.neq {
+            let neq = self.bind(neq.clone());
+            self.push(format!("{column} <> {neq}"));
+        }
+        if let Some(values) = filter.in_values.as_ref().filter(|v| !v.is_empty()) {
+            let values = self.bind_list(values.iter().cloned());
+            self.push(format!("{column} IN ({values})"));
+        }
+        let patterns = [
+            (&filter.contains, "%", "%"),
+            (&filter.starts_with, "", "%"),
+            (&filter.ends_with, "%", ""),
+        ];
+        for (value, prefix, suffix) in patterns {
+            if let Some(value) = value {
+                let pattern = self.bind(format!("{prefix}{}{suffix}", escape_like(value)));
+                self.push(format!("{column} LIKE {pattern} ESCAPE '\\'"));
+            }
+        }
+    }
+
+    fn push_date_filter(&mut self, column: &str, filter: &DateFilter) -> Result<()> {
+        let bounds = [
+            (&filter.gt, ">"),
+            (&filter.gte, ">="),
+            (&filter.lt, "<"),
+            (&filter.lte, "<="),
+        ];
+        for (value, op) in bounds {
+            if let Some(value) = value {
+                let value = self.bind(parse_timestamp(value)?);
+                self.push(format!("{column} {op} {value}"));
+            }
+        }
+        Ok(())
+    }
+
+    fn where_sql(&self) -> String {
+        if self.clauses.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", self.clauses.join(" AND "))
+        }
+    }
+
+    fn into_params(self) -> Vec<Value> {
+        self.params
+    }
+
+    // Call before binding the cursor so the count spans every page.
+    fn total_count(&self, from: &str) -> TotalCount {
+        TotalCount::Query {
+            sql: format!("SELECT COUNT(*) as value FROM {from}{}", self.where_sql()),
+            params: self.params.clone(),
+        }
+    }
+
+    // Like `total_count`, for the issue estimate totals.
+    fn issue_estimates(&self, from: &str) -> IssueEstimates {
+        IssueEstimates::new(format!("{from}{}", self.where_sql()), self.params.clone())
+    }
+
+    // Per-group counts over the same filters; rows carry the group column
+    // and `value`.
+    fn grouped_count(&self, from: &str, group_by: &str) -> (String, Vec<Value>) {
+        let sql = format!(
+            "SELECT {group_by}, COUNT(*) as value FROM {from}{} GROUP BY {group_by}",
+            self.where_sql()
+        );
+        (sql, self.params.clone())
+    }
+}
+
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn placeholders(n: usize) -> String {
+    std::iter::repeat_n("?", n).collect::<Vec<_>>().join(", ")
+}
+
 fn vals(values: Vec<Value>) -> Vec<Value> {
     values
 }
@@ -1633,10 +9326,97 @@ fn sanitize_team_key(input: &str) -> String {
     }
 }
 
-fn clamp_limit(first: Option<i32>) -> i32 {
-    first.unwrap_or(50).clamp(1, 500)
+// Indexed by Linear's priority value: 0 = none, 1 = urgent ... 4 = low.
+const PRIORITY_LABELS: [&str; 5] = ["No priority", "Urgent", "High", "Medium", "Low"];
+
+fn validate_priority(priority: i32) -> Result<i64> {
+    if (0..PRIORITY_LABELS.len() as i32).contains(&priority) {
+        Ok(i64::from(priority))
+    } else {
+        Err(ValidationError(format!("priority must be between 0 and 4 (got {priority})")).into())
+    }
+}
+
+fn validate_estimate(estimate: f64) -> Result<()> {
+    if estimate.is_finite() && estimate >= 0.0 {
+        Ok(())
+    } else {
+        Err(ValidationError(format!(
+            "estimate must be a non-negative number (got {estimate})"
+        ))
+        .into())
+    }
+}
+
+// Stored normalized as YYYY-MM-DD, matching Linear's `dueDate` (TimelessDate).
+fn parse_due_date(value: &str) -> Result<String> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .map_err(|_| {
+            ValidationError(format!("dueDate must be a YYYY-MM-DD date (got {value:?})")).into()
+        })
+}
+
+// Re-rendered in the same UTC form as `now_iso` so string comparison against
+// stored timestamps follows time order.
+fn parse_timestamp(value: &str) -> Result<String> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map(|t| t.with_timezone(&Utc).to_rfc3339())
+        .map_err(|_| {
+            ValidationError(format!("expected an RFC 3339 timestamp (got {value:?})")).into()
+        })
+}
+
+fn priority_label(priority: i64) -> &'static str {
+    usize::try_from(priority)
+        .ok()
+        .and_then(|p| PRIORITY_LABELS.get(p))
+        .copied()
+        .unwrap_or(PRIORITY_LABELS[0])
+}
+
+fn validate_hex_color(color: &str) -> Result<()> {
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError(format!("invalid color {color:?} (expected #RRGGBB)")).into())
+    }
+}
+
+// `max` is SUBLINEAR_MAX_PAGE_SIZE; the default page never exceeds it.
+fn clamp_limit(first: Option<i32>, max: i32) -> i32 {
+    first.unwrap_or(50).clamp(1, max)
+}
+
+#[derive(Deserialize)]
+struct UrlKeyRow {
+    url_key: String,
+}
+
+// Issue, project and comment links follow Linear's `{base}/{orgKey}/...` shape
+// once the organization has a url key, and `{base}/...` otherwise.
+async fn link_base(conn: &Connection, base_url: &str) -> Result<String> {
+    let base = trim_trailing_slash(base_url);
+    let key = fetch_one::<UrlKeyRow>(
+        conn,
+        "SELECT url_key FROM organization ORDER BY created_at ASC LIMIT 1",
+        vec![],
+    )
+    .await?
+    .map(|row| row.url_key)
+    .filter(|key| !key.is_empty());
+    Ok(match key {
+        Some(key) => format!("{base}/{key}"),
+        None => base.to_string(),
+    })
 }
 
 fn trim_trailing_slash(input: &str) -> &str {
     input.trim_end_matches('/')
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,2254 @@
+use super::*;
+
+const TEST_API_KEY: &str = "test-key";
+
+thread_local! {
+    static QUERY_LOG: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+// Called from `fetch_all`; current-thread tests see every query their resolvers ran.
+pub(super) fn record_query(sql: &str) {
+    QUERY_LOG.with(|log| log.borrow_mut().push(sql.to_string()));
+}
+
+fn take_queries() -> Vec<String> {
+    QUERY_LOG.with(|log| std::mem::take(&mut *log.borrow_mut()))
+}
+
+// A fresh app over its own temporary database; the directory goes away on drop.
+struct TestApp {
+    router: Router,
+    state: AppState,
+    dir: std::path::PathBuf,
+}
+
+impl TestApp {
+    async fn new() -> Self {
+        Self::with_vars(&[]).await
+    }
+
+    async fn with_vars(vars: &[(&str, &str)]) -> Self {
+        let dir = env::temp_dir().join(format!("sublinear-test-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut all = HashMap::from([
+            (
+                "TURSO_DATABASE_URL".to_string(),
+                dir.join("test.db").display().to_string(),
+            ),
+            ("SUBLINEAR_API_KEY".to_string(), TEST_API_KEY.to_string()),
+            (
+                "SUBLINEAR_SNAPSHOT_DIR".to_string(),
+                dir.join("snapshots").display().to_string(),
+            ),
+        ]);
+        for (name, value) in vars {
+            all.insert(name.to_string(), value.to_string());
+        }
+        let config = Config::from_vars(|name| all.get(name).cloned()).unwrap();
+        let (router, state) = build_app(Arc::new(config)).await.unwrap();
+        Self { router, state, dir }
+    }
+
+    async fn execute(&self, query: &str, variables: serde_json::Value) -> async_graphql::Response {
+        self.state
+            .schema
+            .execute(
+                async_graphql::Request::new(query)
+                    .variables(Variables::from_json(variables))
+                    .data(RequestAuth { authorized: true }),
+            )
+            .await
+    }
+
+    // The response data, failing the test on any GraphQL error.
+    async fn gql(&self, query: &str, variables: serde_json::Value) -> serde_json::Value {
+        let response = self.execute(query, variables).await;
+        assert!(response.errors.is_empty(), "{query}: {:?}", response.errors);
+        response.data.into_json().unwrap()
+    }
+
+    // `extensions.type` of the first error, failing the test if there is none.
+    async fn error_type(&self, query: &str, variables: serde_json::Value) -> String {
+        let response = self.execute(query, variables).await;
+        let error = response.errors.first().expect("expected an error");
+        match error.extensions.as_ref().and_then(|e| e.get("type")) {
+            Some(async_graphql::Value::String(kind)) => kind.clone(),
+            other => panic!("no error type on {error:?}: {other:?}"),
+        }
+    }
+
+    async fn create_issue(&self, title: &str) -> serde_json::Value {
+        let data = self
+            .gql(
+                "mutation($title: String!) {
+                   issueCreate(input: { teamId: \"team_default\", title: $title }) {
+                     issue { id identifier number title }
+                   }
+                 }",
+                serde_json::json!({ "title": title }),
+            )
+            .await;
+        data["issueCreate"]["issue"].clone()
+    }
+
+    async fn create_label(&self, name: &str) -> String {
+        let data = self
+            .gql(
+                "mutation($name: String!) {
+                   labelCreate(input: { name: $name, color: \"#aabbcc\" }) { label { id } }
+                 }",
+                serde_json::json!({ "name": name }),
+            )
+            .await;
+        data["labelCreate"]["label"]["id"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    async fn create_project(&self, name: &str) -> String {
+        let data = self
+            .gql(
+                "mutation($name: String!) {
+                   projectCreate(input: { teamIds: [\"team_default\"], name: $name }) {
+                     project { id }
+                   }
+                 }",
+                serde_json::json!({ "name": name }),
+            )
+            .await;
+        data["projectCreate"]["project"]["id"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    // There's no user mutation, so tests add users straight to the table.
+    async fn insert_user(&self, id: &str, name: &str) {
+        self.state
+            .pool
+            .get()
+            .await
+            .unwrap()
+            .execute(
+                "INSERT INTO users (id, name, email, created_at) VALUES (?1, ?2, ?3, ?4)",
+                vec![
+                    Value::from(id),
+                    Value::from(name),
+                    Value::from(format!("{id}@example.com")),
+                    Value::from(now_iso()),
+                ],
+            )
+            .await
+            .unwrap();
+    }
+
+    // Serves the router on an ephemeral local port.
+    async fn listen(&self) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = self.router.clone();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        });
+        addr
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+// Status, headers and body text of one request to a served app.
+async fn http(
+    addr: SocketAddr,
+    method: &str,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, hyper::HeaderMap, String) {
+    let mut request = hyper::Request::builder()
+        .method(method)
+        .uri(format!("http://{addr}{path}"))
+        .header(
+            hyper::header::AUTHORIZATION,
+            format!("Bearer {TEST_API_KEY}"),
+        );
+    if body.is_some() {
+        request = request.header(hyper::header::CONTENT_TYPE, "application/json");
+    }
+    let body = body.map(|b| b.to_string()).unwrap_or_default();
+    let response = hyper::Client::new()
+        .request(request.body(hyper::Body::from(body)).unwrap())
+        .await
+        .unwrap();
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+    let headers = response.headers().clone();
+    let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, headers, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+async fn search_titles(app: &TestApp, term: &str) -> Vec<String> {
+    let data = app
+        .gql(
+            "query($term: String!) { searchIssues(term: $term) { nodes { title } } }",
+            serde_json::json!({ "term": term }),
+        )
+        .await;
+    data["searchIssues"]["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["title"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn admin_query_accepts_single_read_only_statements() {
+    for sql in [
+        "SELECT 1",
+        "select id from issues;",
+        "SELECT ';' AS semi; -- trailing comment",
+        "SELECT 'a;b', \"odd;name\" FROM issues /* ; */",
+        "PRAGMA table_info(issues)",
+        "pragma main.index_list('issues');",
+        "PRAGMA table_list",
+    ] {
+        assert!(read_only_statement(sql).is_ok(), "rejected {sql:?}");
+    }
+    assert_eq!(read_only_statement("SELECT ';' ;  ").unwrap(), "SELECT ';'",);
+}
+
+#[test]
+fn admin_query_rejects_writes_and_multiple_statements() {
+    for sql in [
+        "",
+        " ; ",
+        "SELECT 1; SELECT 2",
+        "SELECT 1; DELETE FROM issues",
+        "DELETE FROM issues",
+        "PRAGMA foreign_keys = OFF",
+        "PRAGMA foreign_keys(OFF)",
+        "PRAGMA journal_mode(DELETE)",
+        "PRAGMA wal_checkpoint(TRUNCATE)",
+        "PRAGMA incremental_vacuum(10)",
+        "PRAGMA main.user_version",
+        "PRAGMA table_info_x(issues)",
+    ] {
+        assert!(read_only_statement(sql).is_err(), "accepted {sql:?}");
+    }
+}
+
+#[tokio::test]
+async fn search_follows_issue_title_updates() {
+    let app = TestApp::new().await;
+    let issue = app.create_issue("Flaky widget rendering").await;
+    assert_eq!(
+        search_titles(&app, "widget").await,
+        ["Flaky widget rendering"]
+    );
+
+    app.gql(
+        "mutation($id: String!) {
+           issueUpdate(id: $id, input: { title: \"Slow gadget rendering\" }) { success }
+         }",
+        serde_json::json!({ "id": issue["id"] }),
+    )
+    .await;
+    assert!(search_titles(&app, "widget").await.is_empty());
+    assert_eq!(
+        search_titles(&app, "gadget").await,
+        ["Slow gadget rendering"]
+    );
+}
+
+#[tokio::test]
+async fn search_has_no_stale_hits_after_reimport() {
+    let app = TestApp::new().await;
+    let issue = app.create_issue("Flaky widget rendering").await;
+    let addr = app.listen().await;
+
+    let (status, _, body) = http(addr, "GET", "/export", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let mut bundle: serde_json::Value = serde_json::from_str(&body).unwrap();
+    for row in bundle["issues"].as_array_mut().unwrap() {
+        if row["id"] == issue["id"] {
+            row["title"] = "Slow gadget rendering".into();
+        }
+    }
+    let (status, _, body) = http(addr, "POST", "/import", Some(bundle)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    assert!(search_titles(&app, "widget").await.is_empty());
+    assert_eq!(
+        search_titles(&app, "gadget").await,
+        ["Slow gadget rendering"]
+    );
+}
+
+#[tokio::test]
+async fn imports_count_created_and_updated_rows_and_reject_bad_bundles() {
+    let app = TestApp::new().await;
+    let issue = app.create_issue("Existing").await;
+    let addr = app.listen().await;
+
+    let (_, _, body) = http(addr, "GET", "/export", None).await;
+    let bundle: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let mut copy = bundle["issues"][0].clone();
+    copy["id"] = "issue_imported".into();
+    copy["number"] = 99.into();
+    copy["identifier"] = "SYN-99".into();
+    let issues = serde_json::json!({ "issues": [bundle["issues"][0].clone(), copy.clone()] });
+    let (status, _, body) = http(addr, "POST", "/import", Some(issues)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let result: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        result["counts"]["issues"],
+        serde_json::json!({ "created": 1, "updated": 1 })
+    );
+
+    let mut clash = copy;
+    clash["id"] = "issue_clash".into();
+    clash["identifier"] = "SYN-100".into();
+    clash["number"] = issue["number"].clone();
+    let mut team = bundle["teams"][0].clone();
+    team["id"] = "team_copy".into();
+    team["key"] = "syn".into();
+    let bad_bundles = [
+        serde_json::json!({ "issues": [clash] }),
+        serde_json::json!({ "teams": [team] }),
+        serde_json::json!({ "labels": [
+            { "id": "label_a", "name": "A", "parent_id": "label_b" },
+            { "id": "label_b", "name": "B", "parent_id": "label_a" },
+        ] }),
+        serde_json::json!({ "widgets": [] }),
+    ];
+    for bad in bad_bundles {
+        let (status, _, body) = http(addr, "POST", "/import", Some(bad)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    }
+}
+
+#[tokio::test]
+async fn issues_by_state_pages_each_state() {
+    let app = TestApp::new().await;
+    for n in 1..=3 {
+        app.create_issue(&format!("Issue {n}")).await;
+    }
+    let data = app
+        .gql(
+            "{ issuesByState(filter: { team: { id: { eq: \"team_default\" } } }, first: 2) {
+                 state { id }
+                 issues { totalCount nodes { title } pageInfo { hasNextPage } }
+               } }",
+            serde_json::json!({}),
+        )
+        .await;
+    let groups = data["issuesByState"].as_array().unwrap();
+    let (filled, empty): (Vec<_>, Vec<_>) =
+        groups.iter().partition(|g| g["issues"]["totalCount"] != 0);
+    assert_eq!(filled.len(), 1);
+    let issues = &filled[0]["issues"];
+    assert_eq!(issues["totalCount"], 3);
+    assert_eq!(issues["nodes"].as_array().unwrap().len(), 2);
+    assert_eq!(issues["pageInfo"]["hasNextPage"], true);
+    for group in empty {
+        assert!(group["issues"]["nodes"].as_array().unwrap().is_empty());
+        assert_eq!(group["issues"]["pageInfo"]["hasNextPage"], false);
+    }
+}
+
+#[tokio::test]
+async fn issues_by_state_counts_every_match_per_state() {
+    let app = TestApp::new().await;
+    let mut issues = Vec::new();
+    for n in 1..=4 {
+        issues.push(app.create_issue(&format!("Issue {n}")).await);
+    }
+    let query = "query($numbers: [Int!]) {
+                   issuesByState(
+                     filter: { team: { id: { eq: \"team_default\" } }, number: { in: $numbers } }
+                     first: 1
+                   ) { state { id } issues { totalCount } }
+                 }";
+    let totals = |data: serde_json::Value| -> HashMap<String, i64> {
+        data["issuesByState"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|g| {
+                let id = g["state"]["id"].as_str().unwrap().to_string();
+                (id, g["issues"]["totalCount"].as_i64().unwrap())
+            })
+            .collect()
+    };
+    let before = totals(app.gql(query, serde_json::json!({})).await);
+    let (start_state, _) = before.iter().find(|(_, total)| **total == 4).unwrap();
+    let other_state = before.keys().find(|id| *id != start_state).unwrap();
+    app.gql(
+        "mutation($id: String!, $stateId: String!) {
+           issueUpdate(id: $id, input: { stateId: $stateId }) { success }
+         }",
+        serde_json::json!({ "id": issues[0]["id"], "stateId": other_state }),
+    )
+    .await;
+
+    let after = totals(app.gql(query, serde_json::json!({})).await);
+    assert_eq!(after[start_state], 3);
+    assert_eq!(after[other_state], 1);
+    let filtered = totals(
+        app.gql(query, serde_json::json!({ "numbers": [1, 2] }))
+            .await,
+    );
+    assert_eq!(filtered[start_state], 1);
+    assert_eq!(filtered[other_state], 1);
+}
+
+#[test]
+fn rate_limit_buckets_only_known_keys() {
+    let config =
+        Config::from_vars(|name| (name == "SUBLINEAR_API_KEYS").then(|| "alpha,beta".to_string()))
+            .unwrap();
+    let peer: SocketAddr = "10.0.0.7:4000".parse().unwrap();
+    let key_for = |value: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, value.parse().unwrap());
+        rate_limit_key(&headers, &config, peer)
+    };
+    assert_eq!(
+        key_for("Bearer alpha"),
+        format!("key:{}", api_key_label("alpha"))
+    );
+    assert_ne!(key_for("beta"), key_for("alpha"));
+    assert_eq!(key_for("Bearer made-up"), "ip:10.0.0.7");
+    assert_eq!(key_for("another-guess"), "ip:10.0.0.7");
+}
+
+#[tokio::test]
+async fn rate_limited_requests_get_429_with_retry_after() {
+    let app = TestApp::with_vars(&[
+        ("SUBLINEAR_RATE_LIMIT", "1"),
+        ("SUBLINEAR_RATE_LIMIT_BURST", "3"),
+    ])
+    .await;
+    let addr = app.listen().await;
+    let query = serde_json::json!({ "query": "{ viewer { id } }" });
+    for _ in 0..3 {
+        let (status, _, body) = http(addr, "POST", "/graphql", Some(query.clone())).await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+    let (status, headers, body) = http(addr, "POST", "/graphql", Some(query)).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert!(headers.contains_key(hyper::header::RETRY_AFTER));
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["errors"][0]["extensions"]["type"], "RATELIMITED");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_issue_creates_get_distinct_numbers() {
+    let app = Arc::new(TestApp::new().await);
+    let tasks: Vec<_> = (1..=20)
+        .map(|n| {
+            let app = app.clone();
+            tokio::spawn(async move { app.create_issue(&format!("Issue {n}")).await })
+        })
+        .collect();
+    let mut numbers = Vec::new();
+    for task in tasks {
+        numbers.push(task.await.unwrap()["number"].as_i64().unwrap());
+    }
+    numbers.sort_unstable();
+    assert_eq!(numbers, (1..=20).collect::<Vec<_>>());
+}
+
+// Serves a receiver that forwards each delivery's signature header and body.
+async fn webhook_receiver() -> (
+    SocketAddr,
+    tokio::sync::mpsc::UnboundedReceiver<(Option<String>, String)>,
+) {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let app = Router::new().route(
+        "/hook",
+        post(move |headers: HeaderMap, body: String| {
+            let signature = headers
+                .get("X-Sublinear-Signature")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            let _ = tx.send((signature, body));
+            async { StatusCode::OK }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (addr, rx)
+}
+
+#[tokio::test]
+async fn cycling_an_issue_state_sends_a_signed_webhook() {
+    let (receiver, mut deliveries) = webhook_receiver().await;
+    let url = format!("http://{receiver}/hook");
+    let app = TestApp::with_vars(&[
+        ("SUBLINEAR_WEBHOOK_URL", &url),
+        ("SUBLINEAR_WEBHOOK_SECRET", "s3cret"),
+    ])
+    .await;
+    let issue = app.create_issue("Webhook target").await;
+    let mut next_delivery = async || {
+        let (signature, body) = tokio::time::timeout(Duration::from_secs(5), deliveries.recv())
+            .await
+            .expect("webhook not delivered")
+            .unwrap();
+        assert_eq!(signature, Some(webhook_signature("s3cret", &body)));
+        serde_json::from_str::<serde_json::Value>(&body).unwrap()
+    };
+    assert_eq!(next_delivery().await["action"], "create");
+
+    app.gql(
+        "mutation($id: String!) { issueCycleState(id: $id, direction: FORWARD) { success } }",
+        serde_json::json!({ "id": issue["id"] }),
+    )
+    .await;
+    let delivery = next_delivery().await;
+    assert_eq!(delivery["action"], "update");
+    assert_eq!(delivery["type"], "Issue");
+    assert_eq!(delivery["data"]["id"], issue["id"]);
+}
+
+#[tokio::test]
+async fn subscribers_see_creates_and_label_changes() {
+    let app = TestApp::new().await;
+    let mut events = app.state.schema.execute_stream(
+        async_graphql::Request::new(
+            "subscription { issues { id title labels { nodes { name } } } }",
+        )
+        .data(RequestAuth { authorized: true }),
+    );
+    // The first poll registers the subscription.
+    assert!(
+        tokio::time::timeout(Duration::from_millis(20), events.next())
+            .await
+            .is_err()
+    );
+    let mut next_event = async || {
+        let response = tokio::time::timeout(Duration::from_secs(5), events.next())
+            .await
+            .expect("no event")
+            .unwrap();
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        response.data.into_json().unwrap()["issues"].clone()
+    };
+
+    let issue = app.create_issue("Streamed").await;
+    let created = next_event().await;
+    assert_eq!(created["id"], issue["id"]);
+    assert_eq!(created["title"], "Streamed");
+
+    let label_id = app.create_label("Bug").await;
+    app.gql(
+        "mutation($id: String!, $labelId: String!) {
+           issueAddLabel(id: $id, labelId: $labelId) { success }
+         }",
+        serde_json::json!({ "id": issue["id"], "labelId": label_id }),
+    )
+    .await;
+    let labelled = next_event().await;
+    assert_eq!(labelled["labels"]["nodes"][0]["name"], "Bug");
+}
+
+#[tokio::test]
+async fn resolving_an_issue_applies_the_state_change_like_an_update() {
+    let app = TestApp::new().await;
+    let issue = app.create_issue("Resolve me").await;
+    let states = app
+        .gql(
+            "{ workflowStates { nodes { id type } } }",
+            serde_json::json!({}),
+        )
+        .await;
+    let done = states["workflowStates"]["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|s| s["type"] == "completed")
+        .unwrap()["id"]
+        .clone();
+    let data = app
+        .gql(
+            "mutation($id: String!, $stateId: String!) {
+               issueResolve(id: $id, stateId: $stateId, comment: \"Fixed\") {
+                 issue { state { id } completedAt } comment { body }
+               }
+             }",
+            serde_json::json!({ "id": issue["id"], "stateId": done }),
+        )
+        .await;
+    let resolved = &data["issueResolve"];
+    assert_eq!(resolved["issue"]["state"]["id"], done);
+    assert!(resolved["issue"]["completedAt"].is_string());
+    assert_eq!(resolved["comment"]["body"], "Fixed");
+
+    let response = app
+        .execute(
+            "mutation($id: String!) {
+               issueResolve(id: $id, stateId: \"no_such_state\", comment: \"x\") { success }
+             }",
+            serde_json::json!({ "id": issue["id"] }),
+        )
+        .await;
+    let error = response.errors[0].extensions.as_ref().unwrap();
+    assert_eq!(error.get("type"), Some(&"INVALID_INPUT".into()));
+}
+
+#[tokio::test]
+async fn bad_field_values_are_invalid_input() {
+    let app = TestApp::new().await;
+    let issue = app.create_issue("Validate me").await;
+    let vars = serde_json::json!({ "id": issue["id"] });
+    for input in [
+        "{ priority: 7 }",
+        "{ estimate: -1 }",
+        "{ dueDate: \"next week\" }",
+    ] {
+        let query = format!(
+            "mutation($id: String!) {{ issueUpdate(id: $id, input: {input}) {{ success }} }}"
+        );
+        assert_eq!(
+            app.error_type(&query, vars.clone()).await,
+            "INVALID_INPUT",
+            "{input}"
+        );
+    }
+    for query in [
+        "mutation { labelCreate(input: { name: \"Bug\", color: \"red\" }) { success } }",
+        "mutation { labelCreate(input: { name: \"  \" }) { success } }",
+        "mutation { teamCreate(input: { name: \"Zero\", startingNumber: 0 }) { success } }",
+    ] {
+        assert_eq!(
+            app.error_type(query, serde_json::json!({})).await,
+            "INVALID_INPUT",
+            "{query}"
+        );
+    }
+
+    let create_team =
+        "mutation { teamCreate(input: { name: \"Platform\", key: \"PLT\" }) { success } }";
+    app.gql(create_team, serde_json::json!({})).await;
+    assert_eq!(
+        app.error_type(create_team, serde_json::json!({})).await,
+        "INVALID_INPUT"
+    );
+}
+
+const ADD_LABEL: &str = "mutation($id: String!, $labelId: String!) {
+  issueAddLabel(id: $id, labelId: $labelId) { success }
+}";
+
+#[tokio::test]
+async fn lenient_add_label_creates_one_label_per_name() {
+    let app = TestApp::new().await;
+    for title in ["First", "Second"] {
+        let issue = app.create_issue(title).await;
+        app.gql(
+            ADD_LABEL,
+            serde_json::json!({ "id": issue["id"], "labelId": "Needs triage" }),
+        )
+        .await;
+    }
+    let data = app
+        .gql("{ labels { nodes { id name } } }", serde_json::json!({}))
+        .await;
+    let labels = data["labels"]["nodes"].as_array().unwrap();
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels[0]["name"], "Needs triage");
+    assert!(labels[0]["id"].as_str().unwrap().starts_with("label_"));
+}
+
+#[tokio::test]
+async fn strict_add_label_requires_an_existing_id() {
+    let app = TestApp::with_vars(&[("SUBLINEAR_STRICT_LABELS", "true")]).await;
+    let issue = app.create_issue("Strict").await;
+    let vars = serde_json::json!({ "id": issue["id"], "labelId": "Needs triage" });
+    assert_eq!(app.error_type(ADD_LABEL, vars).await, "ENTITY_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn state_column_rank_counts_unarchived_issues_ahead() {
+    let app = TestApp::new().await;
+    let mut ids = Vec::new();
+    for title in ["A", "B", "C"] {
+        ids.push(app.create_issue(title).await["id"].clone());
+    }
+    let ranks = async || -> Vec<serde_json::Value> {
+        let data = app
+            .gql(
+                "{ issues(first: 10, includeArchived: true) { nodes { title stateColumnRank } } }",
+                serde_json::json!({}),
+            )
+            .await;
+        let mut nodes = data["issues"]["nodes"].as_array().unwrap().clone();
+        nodes.sort_by_key(|n| n["title"].as_str().unwrap().to_string());
+        nodes.iter().map(|n| n["stateColumnRank"].clone()).collect()
+    };
+    take_queries();
+    assert_eq!(ranks().await, [0, 1, 2]);
+    let windowed = take_queries()
+        .iter()
+        .filter(|sql| sql.contains("ROW_NUMBER() OVER (PARTITION BY state_id"))
+        .count();
+    assert_eq!(windowed, 1);
+
+    app.gql(
+        "mutation($id: String!) { issueArchive(id: $id) { success } }",
+        serde_json::json!({ "id": ids[0] }),
+    )
+    .await;
+    assert_eq!(ranks().await, [serde_json::Value::Null, 0.into(), 1.into()]);
+
+    let data = app
+        .gql(
+            "query($id: String!) { issue(id: $id) { stateColumnRank } }",
+            serde_json::json!({ "id": ids[1] }),
+        )
+        .await;
+    assert!(data["issue"]["stateColumnRank"].is_null());
+}
+
+#[tokio::test]
+async fn uploaded_files_are_served_as_downloads() {
+    let uploads = env::temp_dir().join(format!("sublinear-uploads-{}", Uuid::new_v4()));
+    let app = TestApp::with_vars(&[("SUBLINEAR_UPLOAD_DIR", &uploads.display().to_string())]).await;
+    let issue = app.create_issue("Has files").await;
+    let addr = app.listen().await;
+
+    let source = app.dir.join("page.html");
+    std::fs::write(&source, "<script>alert(1)</script>").unwrap();
+    let mut request = async_graphql::Request::new(
+        "mutation($id: String!, $file: Upload!) {
+           attachmentUpload(issueId: $id, file: $file) { attachment { id } }
+         }",
+    )
+    .variables(Variables::from_json(
+        serde_json::json!({ "id": issue["id"], "file": null }),
+    ))
+    .data(RequestAuth { authorized: true });
+    request.set_upload(
+        "variables.file",
+        async_graphql::UploadValue {
+            filename: "page.html".to_string(),
+            content_type: Some("text/html".to_string()),
+            content: std::fs::File::open(&source).unwrap(),
+        },
+    );
+    let response = app.state.schema.execute(request).await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    let id = data["attachmentUpload"]["attachment"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let (status, headers, body) = http(addr, "GET", &format!("/files/{id}"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "<script>alert(1)</script>");
+    assert_eq!(headers["content-type"], "text/html");
+    assert_eq!(headers["content-disposition"], "attachment");
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+
+    let data = app
+        .gql(
+            "mutation($id: String!) {
+               attachmentCreate(input: { issueId: $id, title: \"Spec\", url: \"https://example.com/spec\" }) {
+                 attachment { id }
+               }
+             }",
+            serde_json::json!({ "id": issue["id"] }),
+        )
+        .await;
+    let link = data["attachmentCreate"]["attachment"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    for path in [
+        format!("/files/{link}"),
+        "/files/test.db".to_string(),
+        "/files/attachment_..%2F..%2Ftest.db".to_string(),
+    ] {
+        let (status, _, _) = http(addr, "GET", &path, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{path}");
+    }
+    let _ = std::fs::remove_dir_all(uploads);
+}
+
+#[tokio::test]
+async fn disabled_features_are_not_accessible() {
+    let app = TestApp::new().await;
+    let disabled = [
+        "mutation { adminQuery(sql: \"SELECT 1\") { columns } }",
+        "mutation { adminSnapshot(name: \"before\") { success } }",
+        "mutation {
+           issueCreate(input: { teamId: \"team_default\", title: \"As Ada\", createAsUserId: \"user_ada\" }) {
+             success
+           }
+         }",
+    ];
+    for query in disabled {
+        assert_eq!(
+            app.error_type(query, serde_json::json!({})).await,
+            "FEATURE_NOT_ACCESSIBLE",
+            "{query}"
+        );
+    }
+
+    let app = TestApp::with_vars(&[("SUBLINEAR_ALLOW_SNAPSHOTS", "true")]).await;
+    let bad_name = "mutation { adminSnapshot(name: \"../escape\") { success } }";
+    assert_eq!(
+        app.error_type(bad_name, serde_json::json!({})).await,
+        "INVALID_INPUT"
+    );
+}
+
+#[tokio::test]
+async fn restoring_a_snapshot_brings_back_rows_and_search() {
+    let app = TestApp::with_vars(&[("SUBLINEAR_ALLOW_SNAPSHOTS", "true")]).await;
+    let issue = app.create_issue("Flaky widget").await;
+    let snapshot =
+        |mutation: &str| format!("mutation {{ {mutation}(name: \"before\") {{ success }} }}");
+    let data = app
+        .gql(&snapshot("adminSnapshot"), serde_json::json!({}))
+        .await;
+    assert_eq!(data["adminSnapshot"]["success"], true);
+
+    app.gql(
+        "mutation($id: String!) {
+           issueUpdate(id: $id, input: { title: \"Slow gadget\" }) { success }
+         }",
+        serde_json::json!({ "id": issue["id"] }),
+    )
+    .await;
+    app.create_issue("Later crash").await;
+    assert_eq!(search_titles(&app, "gadget").await, ["Slow gadget"]);
+
+    let data = app
+        .gql(&snapshot("adminRestore"), serde_json::json!({}))
+        .await;
+    assert_eq!(data["adminRestore"]["success"], true);
+    assert_eq!(listed_titles(&app, "{}").await, ["Flaky widget"]);
+    assert_eq!(search_titles(&app, "widget").await, ["Flaky widget"]);
+    assert!(search_titles(&app, "gadget").await.is_empty());
+    assert!(search_titles(&app, "crash").await.is_empty());
+
+    let missing = "mutation { adminRestore(name: \"never\") { success } }";
+    assert_eq!(
+        app.error_type(missing, serde_json::json!({})).await,
+        "ENTITY_NOT_FOUND"
+    );
+}
+
+#[tokio::test]
+async fn snapshots_need_the_flag_and_a_local_database() {
+    let app = TestApp::new().await;
+    let save = "mutation { adminSnapshot(name: \"before\") { success } }";
+    assert_eq!(
+        app.error_type(save, serde_json::json!({})).await,
+        "FEATURE_NOT_ACCESSIBLE"
+    );
+    assert!(!app.dir.join("snapshots").join("before.db").exists());
+
+    // A remote database can't be opened here, so check the guard directly.
+    let remote = AppContext {
+        pool: app.state.pool.clone(),
+        base_url: String::new(),
+        require_auth: false,
+        allow_raw_sql: false,
+        not_found_as_null: false,
+        strict_labels: false,
+        allow_impersonation: false,
+        state_cache: app.state.state_cache.clone(),
+        allow_snapshots: true,
+        snapshot_dir: app.dir.join("snapshots").display().to_string(),
+        upload_dir: None,
+        remote_db: true,
+        busy_retries: 0,
+        max_page_size: 50,
+        max_issues_per_team: None,
+        issue_fts: false,
+        webhook: None,
+        issue_events: broadcast::channel(1).0,
+    };
+    let err = snapshot_path(&remote, "before").unwrap_err();
+    assert!(
+        err.downcast_ref::<FeatureNotAccessibleError>().is_some(),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn impersonated_issues_default_to_the_acting_user() {
+    let app = TestApp::with_vars(&[("SUBLINEAR_ALLOW_IMPERSONATION", "true")]).await;
+    app.insert_user("user_ada", "Ada").await;
+    let create_as_ada = async |assignee: &str| {
+        let query = format!(
+            "mutation {{
+               issueCreate(input: {{
+                 teamId: \"team_default\", title: \"As Ada\", createAsUserId: \"user_ada\"{assignee}
+               }}) {{ issue {{ creator {{ id }} assignee {{ id }} }} }}
+             }}"
+        );
+        app.gql(&query, serde_json::json!({})).await["issueCreate"]["issue"].clone()
+    };
+    let issue = create_as_ada("").await;
+    assert_eq!(issue["creator"]["id"], "user_ada");
+    assert_eq!(issue["assignee"]["id"], "user_ada");
+    let issue = create_as_ada(", assigneeId: \"viewer_default\"").await;
+    assert_eq!(issue["assignee"]["id"], "viewer_default");
+    let issue = create_as_ada(", assigneeId: null").await;
+    assert!(issue["assignee"].is_null());
+
+    let unassigned = app.create_issue("As the viewer").await;
+    let data = app
+        .gql(
+            "query($id: String!) { issue(id: $id) { assignee { id } } }",
+            serde_json::json!({ "id": unassigned["id"] }),
+        )
+        .await;
+    assert!(data["issue"]["assignee"].is_null());
+}
+
+#[tokio::test]
+async fn due_date_is_cleared_by_null() {
+    let app = TestApp::new().await;
+    let issue = app.create_issue("Due soon").await;
+    let update = async |input: &str| {
+        let query = format!(
+            "mutation($id: String!) {{
+               issueUpdate(id: $id, input: {input}) {{ issue {{ dueDate }} }}
+             }}"
+        );
+        app.gql(&query, serde_json::json!({ "id": issue["id"] }))
+            .await["issueUpdate"]["issue"]["dueDate"]
+            .clone()
+    };
+    assert_eq!(update("{ dueDate: \"2026-11-02\" }").await, "2026-11-02");
+    assert_eq!(update("{ title: \"Still due\" }").await, "2026-11-02");
+    assert!(update("{ dueDate: null }").await.is_null());
+}
+
+#[tokio::test]
+async fn search_matches_words_and_pages_in_rank_order() {
+    let app = TestApp::new().await;
+    for title in [
+        "Fix auth bug",
+        "Update billing page",
+        "Auth token expiry",
+        "Authorize exports",
+    ] {
+        app.create_issue(title).await;
+    }
+    let mut found = search_titles(&app, "auth").await;
+    found.sort();
+    assert_eq!(
+        found,
+        ["Auth token expiry", "Authorize exports", "Fix auth bug"]
+    );
+    assert!(search_titles(&app, "invoice").await.is_empty());
+    // Punctuation-only terms take the LIKE path.
+    app.create_issue("Support C++ builds").await;
+    assert_eq!(search_titles(&app, "++").await, ["Support C++ builds"]);
+
+    let everything = search_titles(&app, "auth").await;
+    let mut paged = Vec::new();
+    let mut after = serde_json::Value::Null;
+    loop {
+        let data = app
+            .gql(
+                "query($after: String) {
+                   searchIssues(term: \"auth\", first: 1, after: $after) {
+                     nodes { title } pageInfo { hasNextPage endCursor }
+                   }
+                 }",
+                serde_json::json!({ "after": after }),
+            )
+            .await;
+        let page = &data["searchIssues"];
+        paged.extend(
+            page["nodes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|n| n["title"].as_str().unwrap().to_string()),
+        );
+        if page["pageInfo"]["hasNextPage"] != true {
+            break;
+        }
+        after = page["pageInfo"]["endCursor"].clone();
+    }
+    assert_eq!(paged, everything);
+}
+
+const IMPORT_ISSUE: &str = "mutation($input: AdminImportIssueInput!) {
+  adminImportIssue(input: $input) { issue { id identifier number url createdAt updatedAt } }
+}";
+
+#[tokio::test]
+async fn imported_issues_keep_their_identifier_and_timestamps() {
+    let app = TestApp::new().await;
+    let state = app.create_issue("Seed").await;
+    let data = app
+        .gql(
+            "query($id: String!) { issue(id: $id) { state { id } } }",
+            serde_json::json!({ "id": state["id"] }),
+        )
+        .await;
+    let input = serde_json::json!({
+        "id": "issue_imported",
+        "teamId": "team_default",
+        "number": 42,
+        "identifier": "ENG-42",
+        "title": "Imported",
+        "stateId": data["issue"]["state"]["id"],
+        "url": "https://linear.app/acme/issue/ENG-42",
+        "createdAt": "2024-03-01T09:30:00+02:00",
+        "updatedAt": "2024-03-02T10:00:00.123Z",
+    });
+    let imported = app
+        .gql(IMPORT_ISSUE, serde_json::json!({ "input": input }))
+        .await;
+    let issue = &imported["adminImportIssue"]["issue"];
+    assert_eq!(issue["identifier"], "ENG-42");
+    assert_eq!(issue["number"], 42);
+    assert_eq!(issue["url"], "https://linear.app/acme/issue/ENG-42");
+    assert_eq!(issue["createdAt"], "2024-03-01T09:30:00+02:00");
+    assert_eq!(issue["updatedAt"], "2024-03-02T10:00:00.123Z");
+
+    let mut clash = input.clone();
+    clash["id"] = "issue_other".into();
+    clash["identifier"] = "ENG-420".into();
+    assert_eq!(
+        app.error_type(IMPORT_ISSUE, serde_json::json!({ "input": clash }))
+            .await,
+        "INVALID_INPUT"
+    );
+    let mut bad_time = input;
+    bad_time["createdAt"] = "yesterday".into();
+    assert_eq!(
+        app.error_type(IMPORT_ISSUE, serde_json::json!({ "input": bad_time }))
+            .await,
+        "INVALID_INPUT"
+    );
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct AppliedMigrationRow {
+    version: i64,
+    applied_at: String,
+}
+
+async fn applied_migrations(conn: &Connection) -> Vec<AppliedMigrationRow> {
+    fetch_all(
+        conn,
+        "SELECT version, applied_at FROM schema_migrations ORDER BY version",
+        vec![],
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn migrations_apply_each_version_once() {
+    let app = TestApp::new().await;
+    let conn = app.state.pool.get().await.unwrap();
+    let before = applied_migrations(&conn).await;
+    assert_eq!(
+        before.iter().map(|m| m.version).collect::<Vec<_>>(),
+        MIGRATIONS.iter().map(|m| m.version).collect::<Vec<_>>()
+    );
+    migrate(&conn).await.unwrap();
+    assert_eq!(applied_migrations(&conn).await, before);
+
+    let mut extended: Vec<Migration> = MIGRATIONS
+        .iter()
+        .map(|m| Migration {
+            version: m.version,
+            up: m.up,
+        })
+        .collect();
+    extended.push(Migration {
+        version: 1000,
+        up: "CREATE TABLE migration_probe (id INTEGER PRIMARY KEY)",
+    });
+    run_migrations(&conn, &extended).await.unwrap();
+    // A second run would fail on CREATE TABLE if the version re-applied.
+    run_migrations(&conn, &extended).await.unwrap();
+    let after = applied_migrations(&conn).await;
+    assert_eq!(after.len(), before.len() + 1);
+    assert_eq!(after[..before.len()], before[..]);
+    assert_eq!(after.last().unwrap().version, 1000);
+}
+
+// Label queries made by one `issues` listing once `count` issues carry a label.
+async fn label_queries_for(count: usize) -> usize {
+    let app = TestApp::new().await;
+    for n in 0..count {
+        let issue = app.create_issue(&format!("Issue {n}")).await;
+        app.gql(
+            ADD_LABEL,
+            serde_json::json!({ "id": issue["id"], "labelId": format!("Label {n}") }),
+        )
+        .await;
+    }
+    take_queries();
+    let data = app
+        .gql(
+            "{ issues(first: 50) { nodes { labels { nodes { name } } } } }",
+            serde_json::json!({}),
+        )
+        .await;
+    assert_eq!(data["issues"]["nodes"].as_array().unwrap().len(), count);
+    take_queries()
+        .iter()
+        .filter(|sql| sql.contains("issue_labels"))
+        .count()
+}
+
+#[tokio::test]
+async fn listing_issues_loads_labels_in_one_query() {
+    assert_eq!(label_queries_for(2).await, 1);
+    assert_eq!(label_queries_for(10).await, 1);
+}
+
+#[tokio::test]
+async fn shutdown_stops_the_listener_and_drains_the_pool() {
+    let app = TestApp::new().await;
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(serve(
+        app.router.clone(),
+        app.state.clone(),
+        listener,
+        async {
+            let _ = stopped.await;
+        },
+    ));
+
+    let (status, _, _) = http(addr, "GET", "/healthz", None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    stop.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server stops after shutdown")
+        .unwrap()
+        .unwrap();
+    assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+    assert!(app.state.pool.get().await.is_err());
+}
+
+fn config_with(vars: &[(&str, &str)]) -> Config {
+    Config::from_vars(|name| {
+        vars.iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v.to_string())
+    })
+    .unwrap()
+}
+
+#[test]
+fn any_listed_api_key_authorizes_in_raw_or_bearer_form() {
+    let config = config_with(&[("SUBLINEAR_API_KEYS", "alpha, beta")]);
+    for value in [
+        "alpha",
+        "Bearer alpha",
+        "beta",
+        "Bearer beta",
+        "  Bearer beta  ",
+    ] {
+        assert!(authorization_matches(Some(value), &config), "{value}");
+    }
+    for value in ["gamma", "Bearer gamma", "alpha,beta", "Bearer ", ""] {
+        assert!(!authorization_matches(Some(value), &config), "{value}");
+    }
+    assert!(!authorization_matches(None, &config));
+}
+
+#[test]
+fn legacy_single_api_key_still_authorizes() {
+    let config = config_with(&[("SUBLINEAR_API_KEY", "legacy")]);
+    assert!(authorization_matches(Some("legacy"), &config));
+    assert!(authorization_matches(Some("Bearer legacy"), &config));
+    assert!(!authorization_matches(Some("Bearer other"), &config));
+
+    let both = config_with(&[
+        ("SUBLINEAR_API_KEY", "legacy"),
+        ("SUBLINEAR_API_KEYS", "alpha"),
+    ]);
+    assert!(authorization_matches(Some("Bearer legacy"), &both));
+    assert!(authorization_matches(Some("Bearer alpha"), &both));
+}
+
+#[tokio::test]
+async fn teams_with_open_issues_cannot_be_deleted() {
+    let app = TestApp::new().await;
+    let data = app
+        .gql(
+            "mutation { teamCreate(input: { name: \"Ops\", key: \"OPS\" }) { team { id } } }",
+            serde_json::json!({}),
+        )
+        .await;
+    let team_id = data["teamCreate"]["team"]["id"].clone();
+    let data = app
+        .gql(
+            "mutation($teamId: String!) {
+               issueCreate(input: { teamId: $teamId, title: \"Pager\" }) { issue { id } }
+             }",
+            serde_json::json!({ "teamId": team_id }),
+        )
+        .await;
+    let issue_id = data["issueCreate"]["issue"]["id"].clone();
+
+    let delete = "mutation($id: String!) { teamDelete(id: $id) { success } }";
+    let vars = serde_json::json!({ "id": team_id });
+    assert_eq!(app.error_type(delete, vars.clone()).await, "INVALID_INPUT");
+    let team = "query($id: String!) { team(id: $id) { id } }";
+    assert_eq!(app.gql(team, vars.clone()).await["team"]["id"], team_id);
+
+    app.gql(
+        "mutation($id: String!) { issueArchive(id: $id) { success } }",
+        serde_json::json!({ "id": issue_id }),
+    )
+    .await;
+    assert_eq!(app.error_type(delete, vars.clone()).await, "INVALID_INPUT");
+    assert_eq!(app.gql(team, vars.clone()).await["team"]["id"], team_id);
+
+    let delete_archived = "mutation($id: String!) {
+      teamDelete(id: $id, deleteArchivedIssues: true) { success }
+    }";
+    assert_eq!(
+        app.gql(delete_archived, vars.clone()).await["teamDelete"]["success"],
+        true
+    );
+    assert!(app.gql(team, vars).await["team"].is_null());
+}
+
+#[tokio::test]
+async fn removing_a_label_keeps_the_others() {
+    let app = TestApp::new().await;
+    let issue = app.create_issue("Two labels").await;
+    let bug = app.create_label("Bug").await;
+    let ui = app.create_label("UI").await;
+    for label in [&bug, &ui] {
+        app.gql(
+            ADD_LABEL,
+            serde_json::json!({ "id": issue["id"], "labelId": label }),
+        )
+        .await;
+    }
+
+    let remove = "mutation($id: String!, $labelId: String!) {
+      issueRemoveLabel(id: $id, labelId: $labelId) { success }
+    }";
+    let vars = serde_json::json!({ "id": issue["id"], "labelId": bug });
+    let data = app.gql(remove, vars.clone()).await;
+    assert_eq!(data["issueRemoveLabel"]["success"], true);
+
+    let data = app
+        .gql(
+            "query($id: String!) { issue(id: $id) { labels { nodes { id name } } } }",
+            serde_json::json!({ "id": issue["id"] }),
+        )
+        .await;
+    assert_eq!(
+        data["issue"]["labels"]["nodes"],
+        serde_json::json!([{ "id": ui, "name": "UI" }])
+    );
+
+    let data = app.gql(remove, vars).await;
+    assert_eq!(data["issueRemoveLabel"]["success"], false);
+}
+
+const REACT: &str = "mutation($issueId: String!, $emoji: String!) {
+  reactionCreate(input: { issueId: $issueId, emoji: $emoji }) { reaction { id emoji } }
+}";
+
+async fn reactions_on(app: &TestApp, issue_id: &serde_json::Value) -> serde_json::Value {
+    app.gql(
+        "query($id: String!) {
+           issue(id: $id) { reactions { nodes { id emoji } groups { emoji count } } }
+         }",
+        serde_json::json!({ "id": issue_id }),
+    )
+    .await["issue"]["reactions"]
+        .clone()
+}
+
+#[tokio::test]
+async fn reactions_are_added_and_removed() {
+    let app = TestApp::new().await;
+    let issue = app.create_issue("Nice work").await;
+    let data = app
+        .gql(
+            REACT,
+            serde_json::json!({ "issueId": issue["id"], "emoji": "+1" }),
+        )
+        .await;
+    let reaction_id = data["reactionCreate"]["reaction"]["id"].clone();
+    app.gql(
+        REACT,
+        serde_json::json!({ "issueId": issue["id"], "emoji": "tada" }),
+    )
+    .await;
+
+    let reactions = reactions_on(&app, &issue["id"]).await;
+    assert_eq!(reactions["nodes"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        reactions["groups"],
+        serde_json::json!([{ "emoji": "+1", "count": 1 }, { "emoji": "tada", "count": 1 }])
+    );
+
+    let delete = "mutation($id: String!) { reactionDelete(id: $id) { success } }";
+    let vars = serde_json::json!({ "id": reaction_id });
+    assert_eq!(
+        app.gql(delete, vars.clone()).await["reactionDelete"]["success"],
+        true
+    );
+    let reactions = reactions_on(&app, &issue["id"]).await;
+    assert_eq!(
+        reactions["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|n| n["emoji"].clone())
+            .collect::<Vec<_>>(),
+        vec!["tada"]
+    );
+    assert_eq!(
+        app.gql(delete, vars).await["reactionDelete"]["success"],
+        false
+    );
+}
+
+#[tokio::test]
+async fn repeating_a_reaction_is_rejected_without_a_duplicate() {
+    let app = TestApp::new().await;
+    let issue = app.create_issue("Again").await;
+    let vars = serde_json::json!({ "issueId": issue["id"], "emoji": "+1" });
+    app.gql(REACT, vars.clone()).await;
+    assert_eq!(app.error_type(REACT, vars).await, "INVALID_INPUT");
+    let reactions = reactions_on(&app, &issue["id"]).await;
+    assert_eq!(reactions["nodes"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        reactions["groups"],
+        serde_json::json!([{ "emoji": "+1", "count": 1 }])
+    );
+}
+
+// Titles of one `issues` page in order, plus its end cursor when there's more.
+async fn issue_page(
+    app: &TestApp,
+    args: &str,
+    after: Option<String>,
+) -> (Vec<String>, Option<String>) {
+    let query = format!(
+        "query($after: String) {{
+           issues({args}, after: $after) {{
+             nodes {{ title }}
+             pageInfo {{ hasNextPage endCursor }}
+           }}
+         }}"
+    );
+    let data = app.gql(&query, serde_json::json!({ "after": after })).await;
+    let issues = &data["issues"];
+    let titles = issues["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["title"].as_str().unwrap().to_string())
+        .collect();
+    let next = (issues["pageInfo"]["hasNextPage"] == true).then(|| {
+        issues["pageInfo"]["endCursor"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    });
+    (titles, next)
+}
+
+async fn seed_prioritized_issues(app: &TestApp) {
+    for (title, priority) in [("A", 2), ("B", 0), ("C", 1), ("D", 4), ("E", 2)] {
+        app.gql(
+            "mutation($title: String!, $priority: Int!) {
+               issueCreate(input: { teamId: \"team_default\", title: $title, priority: $priority }) {
+                 success
+               }
+             }",
+            serde_json::json!({ "title": title, "priority": priority }),
+        )
+        .await;
+    }
+}
+
+#[tokio::test]
+async fn issues_order_by_each_key() {
+    let app = TestApp::new().await;
+    seed_prioritized_issues(&app).await;
+    let cases = [
+        ("orderBy: number", "EDCBA"),
+        ("orderBy: createdAt, orderDirection: ASC", "ABCDE"),
+        ("orderBy: updatedAt", "EDCBA"),
+        // No priority sorts after Low; ties fall back to updatedAt DESC.
+        ("orderBy: priority", "CEADB"),
+        ("orderBy: priority, orderDirection: DESC", "BDEAC"),
+    ];
+    for (args, expected) in cases {
+        let (titles, next) = issue_page(&app, &format!("first: 10, {args}"), None).await;
+        assert_eq!(titles.concat(), expected, "{args}");
+        assert!(next.is_none());
+    }
+}
+
+#[tokio::test]
+async fn issue_cursors_continue_a_multi_key_sort() {
+    let app = TestApp::new().await;
+    seed_prioritized_issues(&app).await;
+    let args = "first: 2, sort: [{ orderBy: priority }, { orderBy: createdAt, direction: ASC }]";
+    let mut titles = Vec::new();
+    let mut after = None;
+    loop {
+        let (page, next) = issue_page(&app, args, after).await;
+        titles.extend(page);
+        match next {
+            Some(cursor) => after = Some(cursor),
+            None => break,
+        }
+    }
+    assert_eq!(titles.concat(), "CAEDB");
+
+    // A cursor from one sort doesn't resume another.
+    let (_, cursor) = issue_page(&app, args, None).await;
+    let query = "query($after: String) { issues(first: 2, orderBy: number, after: $after) { nodes { id } } }";
+    let vars = serde_json::json!({ "after": cursor });
+    assert_eq!(app.error_type(query, vars).await, "INVALID_INPUT");
+}
+
+#[test]
+fn query_builder_numbers_placeholders_in_bind_order() {
+    let mut query = QueryBuilder::default();
+    query.push_string_filter(
+        "t.key",
+        &StringFilter {
+            eq: Some("ENG".to_string()),
+            ..Default::default()
+        },
+    );
+    query.push_string_filter(
+        "ws.name",
+        &StringFilter {
+            in_values: Some(vec!["Backlog".to_string(), "Done".to_string()]),
+            ..Default::default()
+        },
+    );
+    let order = IssueOrder::default();
+    let after = order
+        .after_sql(
+            &mut query,
+            "cursor",
+            vec!["2026-01-01T00:00:00Z".to_string(), "issue_1".to_string()],
+        )
+        .unwrap();
+    query.push(after);
+
+    assert_eq!(
+        query.where_sql(),
+        " WHERE t.key = ?1 AND ws.name IN (?2, ?3) AND \
+         (i.updated_at < ?5 OR (i.updated_at = ?5 AND i.id < ?4))"
+    );
+    let params: Vec<String> = query
+        .into_params()
+        .into_iter()
+        .map(|v| match v {
+            Value::Text(text) => text,
+            other => panic!("unexpected param {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        params,
+        ["ENG", "Backlog", "Done", "issue_1", "2026-01-01T00:00:00Z"]
+    );
+}
+
+#[tokio::test]
+async fn filtered_issue_pages_bind_filters_and_cursor_together() {
+    let app = TestApp::new().await;
+    for title in ["One", "Two", "Three", "Four"] {
+        app.create_issue(title).await;
+    }
+    let args =
+        "first: 1, filter: { state: { name: { eq: \"Backlog\" } }, number: { in: [1, 2, 4] } }";
+    let mut titles = Vec::new();
+    let mut after = None;
+    loop {
+        let (page, next) = issue_page(&app, args, after).await;
+        titles.extend(page);
+        match next {
+            Some(cursor) => after = Some(cursor),
+            None => break,
+        }
+    }
+    assert_eq!(titles, ["Four", "Two", "One"]);
+}
+
+async fn listed_titles(app: &TestApp, filter: &str) -> Vec<String> {
+    let (mut titles, _) = issue_page(app, &format!("first: 50, filter: {filter}"), None).await;
+    titles.sort();
+    titles
+}
+
+#[tokio::test]
+async fn label_filters_return_exactly_the_labelled_issues() {
+    let app = TestApp::new().await;
+    let bug = app.create_label("Bug").await;
+    let docs = app.create_label("Docs").await;
+    for (title, labels) in [
+        ("Crash", vec![&bug]),
+        ("Typo", vec![&bug, &docs]),
+        ("Guide", vec![&docs]),
+        ("Plain", vec![]),
+    ] {
+        let issue = app.create_issue(title).await;
+        for label in labels {
+            app.gql(
+                ADD_LABEL,
+                serde_json::json!({ "id": issue["id"], "labelId": label }),
+            )
+            .await;
+        }
+    }
+
+    let by_id = format!("{{ labels: {{ id: {{ eq: \"{bug}\" }} }} }}");
+    assert_eq!(listed_titles(&app, &by_id).await, ["Crash", "Typo"]);
+    let by_some = format!("{{ labels: {{ some: {{ id: {{ eq: \"{bug}\" }} }} }} }}");
+    assert_eq!(listed_titles(&app, &by_some).await, ["Crash", "Typo"]);
+    let by_name = "{ labels: { name: { eq: \"Bug\" } } }";
+    assert_eq!(listed_titles(&app, by_name).await, ["Crash", "Typo"]);
+}
+
+#[tokio::test]
+async fn assignee_filters_match_assigned_unassigned_and_one_user() {
+    let app = TestApp::new().await;
+    app.insert_user("user_ada", "Ada").await;
+    for (title, assignee) in [
+        ("Ada's", "\"user_ada\""),
+        ("Viewer's", "\"viewer_default\""),
+        ("Nobody's", "null"),
+    ] {
+        app.gql(
+            &format!(
+                "mutation {{
+                   issueCreate(input: {{
+                     teamId: \"team_default\", title: \"{title}\", assigneeId: {assignee}
+                   }}) {{ success }}
+                 }}"
+            ),
+            serde_json::json!({}),
+        )
+        .await;
+    }
+
+    let assigned = "{ assignee: { null: false } }";
+    assert_eq!(listed_titles(&app, assigned).await, ["Ada's", "Viewer's"]);
+    let unassigned = "{ assignee: { null: true } }";
+    assert_eq!(listed_titles(&app, unassigned).await, ["Nobody's"]);
+    let ada = "{ assignee: { id: { eq: \"user_ada\" } } }";
+    assert_eq!(listed_titles(&app, ada).await, ["Ada's"]);
+}
+
+#[tokio::test]
+async fn estimate_is_set_then_cleared_by_null() {
+    let app = TestApp::new().await;
+    let issue = app.create_issue("Sized").await;
+    let update = async |input: &str| {
+        let query = format!(
+            "mutation($id: String!) {{
+               issueUpdate(id: $id, input: {input}) {{ issue {{ estimate }} }}
+             }}"
+        );
+        app.gql(&query, serde_json::json!({ "id": issue["id"] }))
+            .await["issueUpdate"]["issue"]["estimate"]
+            .clone()
+    };
+    assert_eq!(update("{ estimate: 3 }").await, 3.0);
+    assert_eq!(update("{ title: \"Still sized\" }").await, 3.0);
+    assert!(update("{ estimate: null }").await.is_null());
+}
+
+const IMPORT_LABELS: &str = "mutation($inputs: [AdminImportLabelInput!]!) {
+  adminImportLabels(inputs: $inputs) { createdCount updatedCount }
+}";
+
+#[tokio::test]
+async fn label_imports_count_creates_and_updates_and_reject_cycles() {
+    let app = TestApp::new().await;
+    let pair = serde_json::json!({ "inputs": [
+        { "id": "label_area", "name": "Area" },
+        { "id": "label_ui", "name": "UI", "parentId": "label_area" },
+    ] });
+    let data = app.gql(IMPORT_LABELS, pair.clone()).await;
+    assert_eq!(
+        data["adminImportLabels"],
+        serde_json::json!({ "createdCount": 2, "updatedCount": 0 })
+    );
+    let parents: Vec<LabelParentRow> = fetch_all(
+        &app.state.pool.get().await.unwrap(),
+        "SELECT id, parent_id FROM labels WHERE id = 'label_ui'",
+        vec![],
+    )
+    .await
+    .unwrap();
+    assert_eq!(parents[0].parent_id.as_deref(), Some("label_area"));
+
+    let data = app.gql(IMPORT_LABELS, pair).await;
+    assert_eq!(
+        data["adminImportLabels"],
+        serde_json::json!({ "createdCount": 0, "updatedCount": 2 })
+    );
+
+    let in_batch = serde_json::json!({ "inputs": [
+        { "id": "label_a", "name": "A", "parentId": "label_b" },
+        { "id": "label_b", "name": "B", "parentId": "label_a" },
+    ] });
+    let against_existing = serde_json::json!({ "inputs": [
+        { "id": "label_area", "name": "Area", "parentId": "label_ui" },
+    ] });
+    for cyclic in [in_batch, against_existing] {
+        assert_eq!(app.error_type(IMPORT_LABELS, cyclic).await, "INVALID_INPUT");
+    }
+    let data = app
+        .gql(
+            "{ labels(first: 10) { nodes { id } } }",
+            serde_json::json!({}),
+        )
+        .await;
+    assert_eq!(
+        data["labels"]["nodes"],
+        serde_json::json!([{ "id": "label_area" }, { "id": "label_ui" }])
+    );
+}
+
+#[tokio::test]
+async fn labels_filter_by_name() {
+    let app = TestApp::new().await;
+    for name in ["Bug", "Backend", "Frontend", "Docs"] {
+        app.create_label(name).await;
+    }
+    let names = async |filter: &str| {
+        let query = format!("{{ labels(filter: {{ name: {filter} }}) {{ nodes {{ name }} }} }}");
+        app.gql(&query, serde_json::json!({})).await["labels"]["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|n| n["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("{ eq: \"Bug\" }").await, ["Bug"]);
+    assert_eq!(names("{ startsWith: \"b\" }").await, ["Backend", "Bug"]);
+    assert_eq!(
+        names("{ contains: \"end\" }").await,
+        ["Backend", "Frontend"]
+    );
+    assert_eq!(
+        names("{ in: [\"Docs\", \"Bug\", \"Missing\"] }").await,
+        ["Bug", "Docs"]
+    );
+}
+
+#[tokio::test]
+async fn archived_projects_are_hidden_until_unarchived() {
+    let app = TestApp::new().await;
+    let roadmap = app.create_project("Roadmap").await;
+    app.create_project("Website").await;
+    let names = async |filter: &str| {
+        let query = format!(
+            "{{ projects(filter: {filter}, orderBy: name, orderDirection: ASC) {{ nodes {{ name }} }} }}"
+        );
+        app.gql(&query, serde_json::json!({})).await["projects"]["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|n| n["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    let set_archived = async |mutation: &str| {
+        let query = format!("mutation($id: String!) {{ {mutation}(id: $id) {{ success }} }}");
+        app.gql(&query, serde_json::json!({ "id": roadmap })).await[mutation]["success"].clone()
+    };
+
+    assert_eq!(set_archived("projectArchive").await, true);
+    assert_eq!(names("{}").await, ["Website"]);
+    assert_eq!(names("{ archived: true }").await, ["Roadmap"]);
+    let data = app
+        .gql(
+            "query($id: String!) { project(id: $id) { archivedAt } }",
+            serde_json::json!({ "id": roadmap }),
+        )
+        .await;
+    assert!(data["project"]["archivedAt"].is_string());
+
+    assert_eq!(set_archived("projectUnarchive").await, true);
+    assert_eq!(names("{}").await, ["Roadmap", "Website"]);
+    assert!(names("{ archived: true }").await.is_empty());
+}
+
+#[tokio::test]
+async fn deleting_a_project_detaches_issues_only_when_asked() {
+    let app = TestApp::new().await;
+    let project = app.create_project("Roadmap").await;
+    let data = app
+        .gql(
+            "mutation($projectId: String!) {
+               issueCreate(input: { teamId: \"team_default\", projectId: $projectId, title: \"Plan\" }) {
+                 issue { id }
+               }
+             }",
+            serde_json::json!({ "projectId": project }),
+        )
+        .await;
+    let issue_id = data["issueCreate"]["issue"]["id"].clone();
+    let issue_project = async || {
+        app.gql(
+            "query($id: String!) { issue(id: $id) { project { id } } }",
+            serde_json::json!({ "id": issue_id }),
+        )
+        .await["issue"]["project"]["id"]
+            .clone()
+    };
+
+    let vars = serde_json::json!({ "id": project });
+    let refused = app
+        .error_type(
+            "mutation($id: String!) { projectDelete(id: $id) { success } }",
+            vars.clone(),
+        )
+        .await;
+    assert_eq!(refused, "INVALID_INPUT");
+    assert_eq!(issue_project().await, project);
+
+    let data = app
+        .gql(
+            "mutation($id: String!) { projectDelete(id: $id, reassign: false) { success } }",
+            vars.clone(),
+        )
+        .await;
+    assert_eq!(data["projectDelete"]["success"], true);
+    assert!(issue_project().await.is_null());
+    let missing = app
+        .error_type("query($id: String!) { project(id: $id) { id } }", vars)
+        .await;
+    assert_eq!(missing, "ENTITY_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn the_seeded_team_lists_its_issues_and_projects() {
+    let app = TestApp::new().await;
+    app.create_issue("Older").await;
+    app.create_issue("Newer").await;
+    app.create_project("Roadmap").await;
+    let data = app
+        .gql(
+            "{ teams { nodes { key
+                 issues(first: 10) { nodes { title team { key } } }
+                 projects { nodes { name } }
+               } } }",
+            serde_json::json!({}),
+        )
+        .await;
+    let team = &data["teams"]["nodes"][0];
+    assert_eq!(team["key"], "SYN");
+    assert_eq!(
+        team["issues"]["nodes"],
+        serde_json::json!([
+            { "title": "Newer", "team": { "key": "SYN" } },
+            { "title": "Older", "team": { "key": "SYN" } },
+        ])
+    );
+    assert_eq!(
+        team["projects"]["nodes"],
+        serde_json::json!([{ "name": "Roadmap" }])
+    );
+}
+
+// Names from every page of a top-level connection, walked through `endCursor`.
+async fn walk_names(app: &TestApp, field: &str) -> (Vec<String>, Vec<String>) {
+    let query = format!(
+        "query($after: String) {{
+           {field}(first: 2, after: $after) {{ nodes {{ name }} pageInfo {{ hasNextPage endCursor }} }}
+         }}"
+    );
+    let mut names = Vec::new();
+    let mut cursors = Vec::new();
+    let mut after: Option<String> = None;
+    loop {
+        let data = app.gql(&query, serde_json::json!({ "after": after })).await;
+        let page = &data[field];
+        let nodes = page["nodes"].as_array().unwrap();
+        assert!(nodes.len() <= 2);
+        names.extend(
+            nodes
+                .iter()
+                .map(|n| n["name"].as_str().unwrap().to_string()),
+        );
+        if page["pageInfo"]["hasNextPage"] != true {
+            break;
+        }
+        let cursor = page["pageInfo"]["endCursor"].as_str().unwrap().to_string();
+        cursors.push(cursor.clone());
+        after = Some(cursor);
+    }
+    (names, cursors)
+}
+
+#[tokio::test]
+async fn nested_connections_continue_from_their_end_cursor() {
+    let app = TestApp::new().await;
+    let issue = app.create_issue("Thread").await;
+    for body in ["First", "Second", "Third"] {
+        app.gql(
+            "mutation($issueId: String!, $body: String!) {
+               commentCreate(input: { issueId: $issueId, body: $body }) { success }
+             }",
+            serde_json::json!({ "issueId": issue["id"], "body": body }),
+        )
+        .await;
+    }
+    for (id, name) in [("user_ada", "Ada"), ("user_bea", "Bea"), ("user_cy", "Cy")] {
+        app.insert_user(id, name).await;
+        app.gql(
+            "mutation($userId: String!) {
+               teamMembershipCreate(teamId: \"team_default\", userId: $userId) { success }
+             }",
+            serde_json::json!({ "userId": id }),
+        )
+        .await;
+    }
+
+    let comments = "query($id: String!, $after: String) {
+      issue(id: $id) { comments(first: 2, after: $after) {
+        nodes { body } pageInfo { hasNextPage hasPreviousPage endCursor }
+      } }
+    }";
+    let page = app
+        .gql(comments, serde_json::json!({ "id": issue["id"] }))
+        .await["issue"]["comments"]
+        .clone();
+    assert_eq!(
+        page["nodes"],
+        serde_json::json!([{ "body": "First" }, { "body": "Second" }])
+    );
+    assert_eq!(page["pageInfo"]["hasNextPage"], true);
+    let cursor = page["pageInfo"]["endCursor"].clone();
+    let page = app
+        .gql(
+            comments,
+            serde_json::json!({ "id": issue["id"], "after": cursor }),
+        )
+        .await["issue"]["comments"]
+        .clone();
+    assert_eq!(page["nodes"], serde_json::json!([{ "body": "Third" }]));
+    assert_eq!(page["pageInfo"]["hasNextPage"], false);
+    assert_eq!(page["pageInfo"]["hasPreviousPage"], true);
+
+    let members = "query($after: String) {
+      team(id: \"team_default\") { members(first: 2, after: $after) {
+        nodes { name } pageInfo { endCursor }
+      } }
+    }";
+    let mut names = Vec::new();
+    let mut after = serde_json::Value::Null;
+    loop {
+        let page = app
+            .gql(members, serde_json::json!({ "after": after }))
+            .await["team"]["members"]
+            .clone();
+        let nodes = page["nodes"].as_array().unwrap().clone();
+        if nodes.is_empty() {
+            break;
+        }
+        names.extend(
+            nodes
+                .iter()
+                .map(|n| n["name"].as_str().unwrap().to_string()),
+        );
+        after = page["pageInfo"]["endCursor"].clone();
+    }
+    assert!(names.starts_with(&["Ada".to_string(), "Bea".to_string()]));
+    assert_eq!(names.iter().filter(|n| *n == "Cy").count(), 1);
+    assert_eq!(
+        app.error_type(members, serde_json::json!({ "after": cursor }))
+            .await,
+        "INVALID_INPUT"
+    );
+}
+
+#[tokio::test]
+async fn teams_and_projects_page_through_end_cursors() {
+    let app = TestApp::new().await;
+    for n in 1..=5 {
+        app.gql(
+            &format!("mutation {{ teamCreate(input: {{ name: \"Team {n}\" }}) {{ success }} }}"),
+            serde_json::json!({}),
+        )
+        .await;
+    }
+    for n in 1..=6 {
+        app.create_project(&format!("Project {n}")).await;
+    }
+
+    let (teams, team_cursors) = walk_names(&app, "teams").await;
+    assert_eq!(teams.len(), 6);
+    assert_eq!(team_cursors.len(), 2);
+    let mut sorted = teams.clone();
+    sorted.sort();
+    assert_eq!(teams, sorted);
+
+    let (mut projects, project_cursors) = walk_names(&app, "projects").await;
+    assert_eq!(project_cursors.len(), 2);
+    projects.sort();
+    let expected: Vec<String> = (1..=6).map(|n| format!("Project {n}")).collect();
+    assert_eq!(projects, expected);
+
+    let projects_after =
+        "query($after: String) { projects(first: 2, after: $after) { nodes { id } } }";
+    let cross_scope = serde_json::json!({ "after": team_cursors[0] });
+    assert_eq!(
+        app.error_type(projects_after, cross_scope).await,
+        "INVALID_INPUT"
+    );
+
+    // Swap in a different key but keep the original signature.
+    let (payload, signature) = project_cursors[0].split_once('.').unwrap();
+    let mut decoded: serde_json::Value =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+    decoded["key"][1] = serde_json::json!("project_other");
+    let forged = format!(
+        "{}.{signature}",
+        URL_SAFE_NO_PAD.encode(decoded.to_string())
+    );
+    let tampered = serde_json::json!({ "after": forged });
+    assert_eq!(
+        app.error_type(projects_after, tampered).await,
+        "INVALID_INPUT"
+    );
+}
+
+// A cycle on the seeded team, dated in days from now; returns its id.
+async fn create_cycle(app: &TestApp, name: &str, starts_in_days: i64, ends_in_days: i64) -> String {
+    create_team_cycle(app, "team_default", name, starts_in_days, ends_in_days).await
+}
+
+async fn create_team_cycle(
+    app: &TestApp,
+    team_id: &str,
+    name: &str,
+    starts_in_days: i64,
+    ends_in_days: i64,
+) -> String {
+    let at = |days: i64| (Utc::now() + chrono::TimeDelta::days(days)).to_rfc3339();
+    let data = app
+        .gql(
+            "mutation($teamId: String!, $name: String!, $startsAt: String!, $endsAt: String!) {
+               cycleCreate(input: { teamId: $teamId, name: $name, startsAt: $startsAt, endsAt: $endsAt }) {
+                 cycle { id }
+               }
+             }",
+            serde_json::json!({
+                "teamId": team_id,
+                "name": name,
+                "startsAt": at(starts_in_days),
+                "endsAt": at(ends_in_days),
+            }),
+        )
+        .await;
+    data["cycleCreate"]["cycle"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn team_cycle_fields_follow_the_current_time() {
+    let app = TestApp::new().await;
+    let query = "{ teams { nodes { key
+        activeCycle { name } previousCycle { name } nextCycle { name }
+      } } }";
+    let cycles = async || {
+        let data = app.gql(query, serde_json::json!({})).await;
+        let team = data["teams"]["nodes"][0].clone();
+        [
+            team["previousCycle"]["name"].clone(),
+            team["activeCycle"]["name"].clone(),
+            team["nextCycle"]["name"].clone(),
+        ]
+    };
+    assert!(cycles().await.iter().all(|c| c.is_null()));
+
+    create_cycle(&app, "Older", -28, -14).await;
+    create_cycle(&app, "Last", -14, -1).await;
+    create_cycle(&app, "Later", 14, 28).await;
+    create_cycle(&app, "Soon", 1, 14).await;
+    assert_eq!(
+        cycles().await,
+        [
+            serde_json::json!("Last"),
+            serde_json::Value::Null,
+            serde_json::json!("Soon")
+        ]
+    );
+
+    create_cycle(&app, "Now", -1, 1).await;
+    assert_eq!(cycles().await[1], "Now");
+}
+
+#[tokio::test]
+async fn issues_move_between_cycles_of_their_own_team() {
+    let app = TestApp::new().await;
+    let issue = app.create_issue("Sprint work").await;
+    let set_cycle = "mutation($id: String!, $cycleId: String) {
+      issueSetCycle(id: $id, cycleId: $cycleId) { issue { cycle { name } } }
+    }";
+    let move_to_active = "mutation($id: String!) {
+      issueMoveToActiveCycle(id: $id) { issue { cycle { name } } }
+    }";
+    let id = serde_json::json!({ "id": issue["id"] });
+    assert_eq!(
+        app.error_type(move_to_active, id.clone()).await,
+        "INVALID_INPUT"
+    );
+
+    let upcoming = create_cycle(&app, "Upcoming", 7, 21).await;
+    create_cycle(&app, "Current", -7, 7).await;
+    let data = app
+        .gql(
+            set_cycle,
+            serde_json::json!({ "id": issue["id"], "cycleId": upcoming }),
+        )
+        .await;
+    assert_eq!(data["issueSetCycle"]["issue"]["cycle"]["name"], "Upcoming");
+    let data = app.gql(move_to_active, id).await;
+    assert_eq!(
+        data["issueMoveToActiveCycle"]["issue"]["cycle"]["name"],
+        "Current"
+    );
+    let data = app
+        .gql(
+            set_cycle,
+            serde_json::json!({ "id": issue["id"], "cycleId": null }),
+        )
+        .await;
+    assert!(data["issueSetCycle"]["issue"]["cycle"].is_null());
+
+    let data = app
+        .gql(
+            "mutation { teamCreate(input: { name: \"Ops\" }) { team { id } } }",
+            serde_json::json!({}),
+        )
+        .await;
+    let other_team = data["teamCreate"]["team"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let foreign = create_team_cycle(&app, &other_team, "Ops sprint", -7, 7).await;
+    let vars = serde_json::json!({ "id": issue["id"], "cycleId": foreign });
+    assert_eq!(app.error_type(set_cycle, vars).await, "INVALID_INPUT");
+    let update = "mutation($id: String!, $cycleId: String!) {
+      issueUpdate(id: $id, input: { cycleId: $cycleId }) { success }
+    }";
+    let vars = serde_json::json!({ "id": issue["id"], "cycleId": foreign });
+    assert_eq!(app.error_type(update, vars).await, "INVALID_INPUT");
+    let vars = serde_json::json!({ "id": issue["id"], "cycleId": "cycle_missing" });
+    assert_eq!(app.error_type(set_cycle, vars).await, "ENTITY_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn attachments_carry_metadata_and_filter_issues() {
+    let app = TestApp::new().await;
+    let linked = app.create_issue("Linked PR").await;
+    app.create_issue("No links").await;
+    let attach = "mutation($issueId: String!, $metadata: JSON) {
+      attachmentCreate(input: {
+        issueId: $issueId, title: \"PR #12\", url: \"https://github.com/o/r/pull/12\",
+        metadata: $metadata, sourceType: \"github\"
+      }) { attachment { metadata sourceType } }
+    }";
+    let metadata = serde_json::json!({ "number": 12, "status": "open" });
+    let data = app
+        .gql(
+            attach,
+            serde_json::json!({ "issueId": linked["id"], "metadata": metadata }),
+        )
+        .await;
+    assert_eq!(
+        data["attachmentCreate"]["attachment"],
+        serde_json::json!({ "metadata": metadata, "sourceType": "github" })
+    );
+    let data = app
+        .gql(
+            "query($id: String!) { issue(id: $id) { attachments { nodes { metadata sourceType } } } }",
+            serde_json::json!({ "id": linked["id"] }),
+        )
+        .await;
+    assert_eq!(
+        data["issue"]["attachments"]["nodes"][0]["metadata"],
+        metadata
+    );
+
+    let not_an_object = serde_json::json!({ "issueId": linked["id"], "metadata": [1, 2] });
+    assert_eq!(app.error_type(attach, not_an_object).await, "INVALID_INPUT");
+
+    assert_eq!(
+        listed_titles(&app, "{ hasAttachments: true }").await,
+        ["Linked PR"]
+    );
+    assert_eq!(
+        listed_titles(&app, "{ hasAttachments: false }").await,
+        ["No links"]
+    );
+}
+
+#[tokio::test]
+async fn issue_connections_total_estimates_across_pages() {
+    let app = TestApp::new().await;
+    for (title, estimate) in [("Small", "1"), ("Large", "5"), ("Unsized", "null")] {
+        app.gql(
+            &format!(
+                "mutation {{
+                   issueCreate(input: {{ teamId: \"team_default\", title: \"{title}\", estimate: {estimate} }}) {{
+                     success
+                   }}
+                 }}"
+            ),
+            serde_json::json!({}),
+        )
+        .await;
+    }
+    let totals = async |args: &str| {
+        let query = format!("{{ issues({args}) {{ totalCount estimateSum estimateCount }} }}");
+        app.gql(&query, serde_json::json!({})).await["issues"].clone()
+    };
+    assert_eq!(
+        totals("first: 1").await,
+        serde_json::json!({ "totalCount": 3, "estimateSum": 6.0, "estimateCount": 2 })
+    );
+    assert_eq!(
+        totals("filter: { number: { in: [3] } }").await,
+        serde_json::json!({ "totalCount": 1, "estimateSum": 0.0, "estimateCount": 0 })
+    );
+
+    let data = app
+        .gql(
+            "{ issuesByState(filter: { team: { id: { eq: \"team_default\" } } }, first: 1) {
+                 state { name } issues { estimateSum estimateCount }
+               } }",
+            serde_json::json!({}),
+        )
+        .await;
+    let groups = data["issuesByState"].as_array().unwrap();
+    let backlog = groups
+        .iter()
+        .find(|g| g["state"]["name"] == "Backlog")
+        .unwrap();
+    assert_eq!(
+        backlog["issues"],
+        serde_json::json!({ "estimateSum": 6.0, "estimateCount": 2 })
+    );
+    let done = groups
+        .iter()
+        .find(|g| g["state"]["name"] == "Done")
+        .unwrap();
+    assert_eq!(done["issues"]["estimateSum"], 0.0);
+}
+
+#[tokio::test]
+async fn links_include_the_organization_key() {
+    let app = TestApp::with_vars(&[
+        ("SUBLINEAR_BASE_URL", "https://tracker.test/"),
+        ("SUBLINEAR_SEED_ORG_KEY", "Acme Corp"),
+    ])
+    .await;
+    let issue = app.create_issue("Linked").await;
+    let data = app
+        .gql(
+            "mutation($issueId: String!) {
+               commentCreate(input: { issueId: $issueId, body: \"Hi\" }) { comment { id url } }
+             }",
+            serde_json::json!({ "issueId": issue["id"] }),
+        )
+        .await;
+    let comment = &data["commentCreate"]["comment"];
+    assert_eq!(
+        comment["url"],
+        format!(
+            "https://tracker.test/acme-corp/comment/{}",
+            comment["id"].as_str().unwrap()
+        )
+    );
+    let data = app
+        .gql(
+            "query($id: String!) { issue(id: $id) { url } }",
+            serde_json::json!({ "id": issue["id"] }),
+        )
+        .await;
+    assert_eq!(
+        data["issue"]["url"],
+        "https://tracker.test/acme-corp/issue/SYN-1"
+    );
+    let project = app.create_project("Roadmap").await;
+    let data = app
+        .gql(
+            "query($id: String!) { project(id: $id) { url } }",
+            serde_json::json!({ "id": project }),
+        )
+        .await;
+    assert_eq!(
+        data["project"]["url"],
+        format!("https://tracker.test/acme-corp/project/{project}")
+    );
+
+    app.gql(
+        "mutation { teamUpdate(id: \"team_default\", input: { key: \"OPS\" }) { success } }",
+        serde_json::json!({}),
+    )
+    .await;
+    let data = app
+        .gql(
+            "query($id: String!) { issue(id: $id) { url } }",
+            serde_json::json!({ "id": issue["id"] }),
+        )
+        .await;
+    assert_eq!(
+        data["issue"]["url"],
+        "https://tracker.test/acme-corp/issue/OPS-1"
+    );
+
+    app.state
+        .pool
+        .get()
+        .await
+        .unwrap()
+        .execute("DELETE FROM organization", ())
+        .await
+        .unwrap();
+    let issue = app.create_issue("Unkeyed").await;
+    let data = app
+        .gql(
+            "query($id: String!) { issue(id: $id) { url } }",
+            serde_json::json!({ "id": issue["id"] }),
+        )
+        .await;
+    assert_eq!(data["issue"]["url"], "https://tracker.test/issue/OPS-2");
+}